@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::state::PetStats;
+
+// health is scaled so that a fight lasts several rounds
+pub(crate) static HP_PER_HEALTH: u16 = 10;
+// every pet can strike this many times plus its stamina
+pub(crate) static BASE_BATTLE_TURNS: u16 = 3;
+// bounds the gas and the size of the log for pets with a lot of stamina
+pub(crate) static MAX_BATTLE_ROUNDS: u16 = 30;
+// crit and dodge chances in percent
+pub(crate) static CHANCE_PER_INTELLIGENCE: u16 = 3;
+pub(crate) static MAX_CHANCE: u16 = 40;
+// both pets roll this die plus their luck when a tie is broken by a luck roll
+pub(crate) static TIE_BREAK_DIE_SIDES: u16 = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BattleOutcome {
+    ChallengerWin,
+    DefenderWin,
+    Draw
+}
+
+impl BattleOutcome {
+    /// battles stored before draws existed kept whether the challenger won
+    pub(crate) fn from_challenger_wins(challenger_wins: bool) -> Self {
+        if challenger_wins {
+            BattleOutcome::ChallengerWin
+        } else {
+            BattleOutcome::DefenderWin
+        }
+    }
+}
+
+/// how a battle that ends with both pets on the same share of their hp is decided, ties that
+/// the tie-breaker cannot decide either are draws
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreaker {
+    #[default]
+    Draw,
+    // the pet with the higher sum of its current stats wins
+    TotalStats,
+    // both pets roll a die plus their luck
+    LuckRoll
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrikeResult {
+    Hit,
+    Critical,
+    Dodged
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BattleTurn {
+    pub round: u16,
+    // the pet striking this turn
+    pub attacker: String,
+    pub result: StrikeResult,
+    pub damage: u16,
+    // the hp the struck pet has left
+    pub defender_hp: u16
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BattleLog {
+    // the hp both pets started with
+    pub pet_hp: u16,
+    pub other_pet_hp: u16,
+    pub rounds: u16,
+    pub turns: Vec<BattleTurn>,
+    // set when the pets tied and the tie-breaker decided the battle
+    #[serde(default)]
+    pub tie_broken_by: Option<TieBreaker>
+}
+
+struct Fighter<'a> {
+    pet_id: &'a str,
+    stats: &'a PetStats,
+    max_hp: u16,
+    hp: u16,
+    turns_left: u16
+}
+
+impl<'a> Fighter<'a> {
+    fn new(pet_id: &'a str, stats: &'a PetStats) -> Self {
+        let max_hp = stats.health.saturating_mul(HP_PER_HEALTH);
+        Fighter {
+            pet_id,
+            stats,
+            max_hp,
+            hp: max_hp,
+            turns_left: BASE_BATTLE_TURNS.saturating_add(stats.stamina),
+        }
+    }
+
+    fn can_strike(&self) -> bool {
+        self.hp > 0 && self.turns_left > 0
+    }
+}
+
+fn chance(intelligence: u16) -> u16 {
+    intelligence.saturating_mul(CHANCE_PER_INTELLIGENCE).min(MAX_CHANCE)
+}
+
+/// the attacker spends a turn on a strike: intelligence decides crits and dodges, luck adds
+/// a random bonus on top of the strength of the strike
+fn strike(
+    round: u16,
+    attacker: &mut Fighter,
+    defender: &mut Fighter,
+    rng: &mut SmallRng
+) -> BattleTurn {
+    attacker.turns_left -= 1;
+
+    let (result, damage) = if rng.gen_range(0..100) < chance(defender.stats.intelligence) {
+        (StrikeResult::Dodged, 0)
+    } else {
+        let damage = attacker.stats.strength.saturating_add(rng.gen_range(0..=attacker.stats.luck));
+        if rng.gen_range(0..100) < chance(attacker.stats.intelligence) {
+            (StrikeResult::Critical, damage.saturating_mul(2))
+        } else {
+            (StrikeResult::Hit, damage)
+        }
+    };
+    defender.hp = defender.hp.saturating_sub(damage);
+
+    BattleTurn {
+        round,
+        attacker: attacker.pet_id.to_string(),
+        result,
+        damage,
+        defender_hp: defender.hp,
+    }
+}
+
+fn break_tie(
+    pet: &PetStats,
+    other_pet: &PetStats,
+    tie_breaker: &TieBreaker,
+    rng: &mut SmallRng
+) -> Ordering {
+    match tie_breaker {
+        TieBreaker::Draw => Ordering::Equal,
+        TieBreaker::TotalStats => pet.total().cmp(&other_pet.total()),
+        TieBreaker::LuckRoll => {
+            let roll = rng.gen_range(1..=TIE_BREAK_DIE_SIDES).saturating_add(pet.luck);
+            let other_roll = rng.gen_range(1..=TIE_BREAK_DIE_SIDES).saturating_add(other_pet.luck);
+            roll.cmp(&other_roll)
+        }
+    }
+}
+
+/// fights the challenging pet against the challenged pet. The challenger strikes first every
+/// round until a pet is knocked out or both are out of turns, then the pet with the larger
+/// share of its hp left wins and ties go to the tie-breaker.
+pub(crate) fn simulate_battle(
+    pet_id: &str,
+    pet: &PetStats,
+    other_pet_id: &str,
+    other_pet: &PetStats,
+    tie_breaker: &TieBreaker,
+    rng: &mut SmallRng
+) -> (BattleOutcome, BattleLog) {
+    let mut challenger = Fighter::new(pet_id, pet);
+    let mut defender = Fighter::new(other_pet_id, other_pet);
+    let mut turns: Vec<BattleTurn> = vec![];
+
+    let mut rounds = 0;
+    while rounds < MAX_BATTLE_ROUNDS && (challenger.can_strike() || defender.can_strike()) {
+        rounds += 1;
+        if challenger.can_strike() && defender.hp > 0 {
+            turns.push(strike(rounds, &mut challenger, &mut defender, rng));
+        }
+        if defender.can_strike() && challenger.hp > 0 {
+            turns.push(strike(rounds, &mut defender, &mut challenger, rng));
+        }
+        if challenger.hp == 0 || defender.hp == 0 {
+            break;
+        }
+    }
+
+    // compare the hp left as a share of the max hp, cross multiplied to stay in integers
+    let challenger_share = u32::from(challenger.hp) * u32::from(defender.max_hp);
+    let defender_share = u32::from(defender.hp) * u32::from(challenger.max_hp);
+    let mut tie_broken_by = None;
+    let order = match challenger_share.cmp(&defender_share) {
+        Ordering::Equal => {
+            let order = break_tie(pet, other_pet, tie_breaker, rng);
+            if order != Ordering::Equal {
+                tie_broken_by = Some(tie_breaker.clone());
+            }
+            order
+        }
+        order => order
+    };
+    let outcome = match order {
+        Ordering::Greater => BattleOutcome::ChallengerWin,
+        Ordering::Less => BattleOutcome::DefenderWin,
+        Ordering::Equal => BattleOutcome::Draw
+    };
+
+    let log = BattleLog {
+        pet_hp: challenger.max_hp,
+        other_pet_hp: defender.max_hp,
+        rounds,
+        turns,
+        tie_broken_by,
+    };
+    (outcome, log)
+}
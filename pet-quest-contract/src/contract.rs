@@ -1,31 +1,40 @@
-use std::ops::Mul;
 use cosmwasm_std::{entry_point, to_binary, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Timestamp, Uint128, WasmMsg};
-use rand::rngs::SmallRng;
-use rand::{SeedableRng};
 use secret_toolkit::permit::Permit;
+use rand::rngs::SmallRng;
+use crate::error::ContractError;
 use crate::loot20::{Loot20ExecuteMsg, Loot20QueryAnswer, Loot20QueryMsg, Loot20QueryWithPermit};
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryAnswer, QueryMsg, QueryWithPermits};
-use crate::pet721::{Pet721ExecuteMsg, Pet721QueryAnswer, Pet721QueryMsg, Pet721QueryWithPermit};
+use crate::migrate::{migrate_storage, set_contract_info, STORAGE_VERSION};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryAnswer, QueryMsg, QueryWithPermits};
+use crate::battle::{simulate_battle, BattleLog, BattleOutcome};
+use crate::pet721::{Pet721ExecuteMsg, Pet721QueryAnswer, Pet721QueryMsg, Pet721QueryWithPermit, PetTokenMetadata};
 use crate::pet721::Pet721QueryWithPermit::OwnerOf;
-use crate::randomness::generate_seed;
-use crate::state::{calculate_loot, calculate_outcome, check_pet_availability, check_quest_availability, check_quest_awaiting_claim, generate_new_pet, generate_new_quests_for_addr, insert_quest_history, update_pet_on_quest, update_quest_after_claiming, update_quest_after_starting_explore, Config, ContractData, PetState, Quest, QuestHistory, QuestOutcome, Stat, CONFIG, LOOT20_DATA, PET721_DATA, PETS, PET_COUNTER, QUESTS, get_quest_history, QUEST_EXPLORE_TIME_SECONDS, get_cost_of_stat_upgrade, get_quest, Loot, QuestSummary, remove_pet_on_quest, BattleInfo, BATTLE_COUNTER, BATTLES, insert_battle_for_pet, remove_battle_for_pet, PET_BATTLES, BattleInfoWithoutInitiator};
+use crate::randomness::{block_entropy, commit_entropy, draw_rng, fold_execute_entropy, init_prng_seed, mix_prng_entropy};
+use crate::state::{calculate_loot, calculate_outcome, check_pet_availability, check_quest_availability, check_quest_awaiting_claim, generate_new_pet, generate_new_quests_for_addr, insert_quest_history, update_pet_on_quest, update_quest_after_claiming, update_quest_after_starting_explore, Config, ContractData, PetState, Quest, QuestHistory, QuestOutcome, CONFIG, LOOT20_DATA, PET721_DATA, PETS, PET_COUNTER, QUESTS, get_player_quest_history, get_pet_quest_history, QuestHistoryFilter, DEFAULT_QUEST_HISTORY_PAGE_SIZE, MAX_QUEST_HISTORY_PAGE_SIZE, load_upgrade_config, UPGRADE_CONFIG, save_pet, get_quest, Loot, QuestSummary, remove_pet_on_quest, BattleInfo, BATTLE_COUNTER, BATTLES, insert_battle_for_pet, remove_battle_for_pet, PET_BATTLES, BattleInfoWithoutInitiator, BattleStatus, ContractStatusLevel, CONTRACT_STATUS, MIN_MAX_STATS, MAX_STAT_VALUE, CONTRACT_INFO, get_quest_catalogue, get_quest_definition, load_player_quests, save_default_quest_catalogue, QUEST_CATALOGUE, load_progression_config, update_pet_progression, PROGRESSION_CONFIG, roll_quest_die, get_quest_check, spend_pet_energy, BATTLE_ENERGY_COST, QUEST_ENERGY_COST, Expiration, DEFAULT_BATTLE_EXPIRY_SECONDS, MAX_BATTLE_EXPIRY_SECONDS, BATTLE_LOGS, get_pending_battles, check_pet_free, MAX_PENDING_BATTLES_PER_PET, MIN_BATTLE_WAGER, update_battle_records, update_leaderboard, LeaderboardEntry, get_leaderboard_page, get_leaderboard_rating, RatedPet, DEFAULT_LEADERBOARD_LIMIT, MAX_LEADERBOARD_LIMIT, MatchmakingEntry, MATCHMAKING_PETS, enter_matchmaking, leave_matchmaking, get_matchmaking_candidates, Tournament, TournamentEntrant, TournamentStatus, TOURNAMENTS, TOURNAMENT_COUNTER, TOURNAMENT_PETS, DEFAULT_TOURNAMENT_REGISTRATION_SECONDS, MAX_TOURNAMENT_REGISTRATION_SECONDS, load_tie_breaker, TIE_BREAKER, load_privacy_policy, PRIVACY_POLICY, MAX_PETS_INFO, breed_pets, load_breeding_config, BREEDING_CONFIG, load_species_config, SPECIES_CONFIG, get_rarity_supply, record_token_metadata, validate_pet_name, TOKEN_METADATA};
+use crate::tournament::{place_entrants, resolve_bracket, DEFAULT_PRIZE_SPLIT, MAX_PRIZE_PLACES, MAX_TOURNAMENT_ENTRANTS, MIN_TOURNAMENT_ENTRANTS};
 
 #[entry_point]
 pub(crate) fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let admin = msg.clone().admin;
+    if msg.max_stats < MIN_MAX_STATS || msg.max_stats > MAX_STAT_VALUE {
+        return Err(ContractError::InvalidMaxStats { min: MIN_MAX_STATS, max: MAX_STAT_VALUE });
+    }
     let config = Config {
         admin: admin.clone(),
         max_stats: msg.clone().max_stats,
         entropy: msg.clone().entropy
     };
     CONFIG.save(deps.storage, &config)?;
+    init_prng_seed(deps.storage, &env, config.entropy.as_bytes())?;
     LOOT20_DATA.save(deps.storage, &msg.clone().loot_contract)?;
     PET721_DATA.save(deps.storage, &msg.clone().pet_contract)?;
+    CONTRACT_STATUS.save(deps.storage, &ContractStatusLevel::NormalRun)?;
+    save_default_quest_catalogue(deps.storage)?;
+    set_contract_info(deps.storage)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
@@ -33,25 +42,37 @@ pub(crate) fn instantiate(
 }
 
 #[entry_point]
-pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub(crate) fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = migrate_storage(deps.storage, &env)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_storage_version", from_version.to_string())
+        .add_attribute("storage_version", STORAGE_VERSION.to_string()))
+}
+
+#[entry_point]
+pub(crate) fn execute(mut deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
+    check_contract_status(deps.storage, &msg)?;
+
+    // every execute moves the contract prng state forward
+    fold_execute_entropy(deps.storage, &env, &info.sender)?;
+
     match msg {
-        ExecuteMsg::MintPet { .. } => {
+        ExecuteMsg::MintPet { ref entropy, .. } => {
             let funds = vec![];
 
-            let next_pet_id = PET_COUNTER.may_load(deps.storage)?.unwrap_or(0);
-            let token_id = format!("PET_{}", next_pet_id);
-            PET_COUNTER.save(deps.storage, &(next_pet_id + 1))?;
+            let token_id = next_pet_id(deps.storage)?;
 
-            let config = CONFIG.load(deps.storage)?;
-            let seed = generate_seed(
-                &info.sender,
-                env.block.time.seconds(),
-                config.entropy.as_bytes()
-            );
-            let rng = SmallRng::seed_from_u64(seed);
-            generate_new_pet(token_id.clone(), deps.storage, rng.clone())?;
-
-            let nft_msg = Pet721ExecuteMsg::from_execute_msg(msg, Some(token_id.clone()));
+            let addr = deps.api.addr_canonicalize(info.sender.as_str())?;
+            let entropy = block_entropy(deps.storage, &env, &addr, entropy.clone())?;
+            let rng = draw_rng(deps.storage, &entropy)?;
+            let pet = generate_new_pet(token_id.clone(), deps.storage, rng)?;
+
+            // the token is minted with the pet's metadata, see `sync_pet_metadata`
+            let metadata = PetTokenMetadata::from_pet(&pet, &load_privacy_policy(deps.storage)?);
+            record_token_metadata(&token_id, &metadata, deps.storage)?;
+            let nft_msg = Pet721ExecuteMsg::from_execute_msg(msg, Some(token_id.clone()), metadata);
             let binary = Binary::from(to_binary(&nft_msg)?);
             let contract_data = PET721_DATA.load(deps.storage)?;
             let cosmos_msg = handle_pet_nft_execute(contract_data, binary, funds);
@@ -61,15 +82,22 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
                 .add_attribute("action", "mint_pet")
                 .add_attribute("pet_id", token_id);
 
-            let addr = deps.api.addr_canonicalize(info.sender.as_str())?;
             let quests: Option<Vec<Quest>> = QUESTS.get(deps.storage, &addr);
             if quests.is_none() {
+                let rng = draw_rng(deps.storage, &entropy)?;
                 generate_new_quests_for_addr(addr, deps.storage, rng)?;
                 response = response.add_attribute("action", "generate_user_quests");
             }
 
             Ok(response)
         }
+        ExecuteMsg::CommitEntropy { commitment } => {
+            let addr = deps.api.addr_canonicalize(info.sender.as_str())?;
+            commit_entropy(deps.storage, &env, &addr, commitment)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "commit_entropy"))
+        }
         ExecuteMsg::ReleasePet { pet_id, permit: _, pet_permit, loot_permit: _ } => {
             // check that we own the pet
             let addr = info.sender.to_string();
@@ -79,36 +107,67 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
             if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to burn it",
-                ));
+                return Err(ContractError::NotPetOwner);
             }
 
             let pet = match PETS.get(deps.storage, &pet_id) {
                 Some(pet) => pet,
-                None => {
-                    return Err(StdError::generic_err(
-                        "Could not find pet",
-                    ));
-                }
+                None => return Err(ContractError::PetNotFound { pet_id })
             };
 
             if pet.on_quest.is_some() && pet.clone().on_quest.unwrap().finished_exploring.is_some() {
                 if pet.on_quest.unwrap().finished_exploring.unwrap() > Timestamp::from_seconds(env.block.time.seconds()) {
-                    return Err(StdError::generic_err(
-                        "Cannot release a pet when it is on a quest",
-                    ));
+                    return Err(ContractError::PetOnQuest);
+                }
+            }
+
+            // settle the battles of the pet before it is gone, pending challenges are refunded
+            // to their initiators and unclaimed winnings and drawn wagers are paid to the owner
+            let mut messages: Vec<CosmosMsg> = vec![];
+            for battle_id in PET_BATTLES.get(deps.storage, &pet_id).unwrap_or_default() {
+                let battle = match BATTLES.get(deps.storage, &battle_id) {
+                    Some(battle) => battle,
+                    None => continue
+                };
+                if battle.status != BattleStatus::Accepted {
+                    messages.push(refund_battle(deps.branch(), &battle, vec![])?);
+                } else {
+                    let payout = battle.payout(&pet_id)?;
+                    if !payout.is_zero() {
+                        messages.push(release_wager(deps.as_ref(), &battle, addr.clone(), payout, vec![])?);
+                    }
+                }
+            }
+            PET_BATTLES.remove(deps.storage, &pet_id)?;
+            if let Some(entry) = leave_matchmaking(&pet_id, deps.storage)? {
+                let recipient = deps.api.addr_humanize(&entry.owner)?.to_string();
+                messages.push(transfer_loot(deps.as_ref(), recipient, Uint128::from(entry.wager), vec![])?);
+            }
+            if let Some(tournament_id) = TOURNAMENT_PETS.get(deps.storage, &pet_id) {
+                TOURNAMENT_PETS.remove(deps.storage, &pet_id)?;
+                if let Some(mut tournament) = TOURNAMENTS.get(deps.storage, &tournament_id) {
+                    if let Some(index) = tournament.entrants.iter().position(|entrant| entrant.pet_id == pet_id) {
+                        let entrant = tournament.entrants.remove(index);
+                        TOURNAMENTS.insert(deps.storage, &tournament_id, &tournament)?;
+                        if tournament.entry_fee > 0 {
+                            let recipient = deps.api.addr_humanize(&entrant.owner)?.to_string();
+                            messages.push(transfer_loot(deps.as_ref(), recipient, Uint128::from(tournament.entry_fee), vec![])?);
+                        }
+                    }
                 }
             }
 
             // remove pet data from this contract
             PETS.remove(deps.storage, &pet_id)?;
+            TOKEN_METADATA.remove(deps.storage, &pet_id)?;
+            update_leaderboard(&pet_id, Some(pet.record.rating), None, deps.storage)?;
 
             Ok(Response::new()
                 .add_attribute("action", "release_pet")
-                .add_attribute("pet_id", pet_id))
+                .add_attribute("pet_id", pet_id)
+                .add_messages(messages))
         }
-        ExecuteMsg::UpgradePetStats { pet_id, stat,
+        ExecuteMsg::UpgradePetStats { pet_id, upgrades,
             permit: _, pet_permit, loot_permit } => {
             // check that we own the pet
             let addr = info.sender.to_string();
@@ -118,51 +177,225 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
             if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to upgrade stats",
-                ));
+                return Err(ContractError::NotPetOwner);
             }
 
-            // early return if stat is maxed
-            let pet_state: Option<PetState> = PETS.get(deps.storage, &pet_id.clone());
-            if pet_state.is_none() {
-                return Err(StdError::generic_err(
-                    format!("Could not find any stat data for {pet_id}"),
-                ));
-            }
-            let pet_state = pet_state.unwrap();
+            let mut pet_state = match PETS.get(deps.storage, &pet_id) {
+                Some(pet_state) => pet_state,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
 
-            let stat = Stat::from_string(stat);
-            if pet_state.current.get_stat(stat.clone()) >= pet_state.max.get_stat(stat.clone()) {
-                return Err(StdError::generic_err(
-                    "Already maxed this stat for this pet"
-                ));
-            }
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+
+            // early return if a stat would pass its max
+            let config = load_upgrade_config(deps.storage)?;
+            let cost_of_upgrade = pet_state.apply_upgrades(&upgrades, &config)?;
 
             // check if we have the funds
             let query = Loot20QueryWithPermit::Balance {};
             let query_with_permit = Loot20QueryMsg::WithPermit { query, permit: loot_permit };
             let balance = get_loot_balance(deps.as_ref(), query_with_permit)?;
-
-            // early return if balance is insufficient
-            let cost_of_upgrade = get_cost_of_stat_upgrade(&pet_state.current, stat.clone())? as u64;
             if balance < cost_of_upgrade {
-                return Err(StdError::generic_err(
-                    "You do not have sufficient funds to upgrade this pet",
-                ));
+                return Err(ContractError::InsufficientLoot { needed: cost_of_upgrade, have: balance });
             }
 
-            // burn the funds
+            // burn the funds for all upgrades at once
             let burn_msg = burn_loot(info.sender.to_string(), deps.as_ref(), cost_of_upgrade, info.funds)?;
-
-            // update own storage to have increased stat
-            upgrade_pets_stat(deps, pet_id, stat)?;
+            save_pet(&mut pet_state, deps.storage)?;
+            let metadata_msg = sync_pet_metadata(deps.storage, &pet_state)?;
 
             Ok(Response::default()
                 .add_attribute("action", "upgrade_pet")
-                .add_message(burn_msg))
+                .add_attribute("pet_id", pet_id)
+                .add_attribute("cost", cost_of_upgrade.to_string())
+                .add_message(burn_msg)
+                .add_messages(metadata_msg))
+        }
+        ExecuteMsg::RespecPet { pet_id, permit: _, pet_permit, loot_permit } => {
+            // check that we own the pet
+            let addr = info.sender.to_string();
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit
+            };
+            let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+            if owner != addr {
+                return Err(ContractError::NotPetOwner);
+            }
+
+            let mut pet_state = match PETS.get(deps.storage, &pet_id) {
+                Some(pet_state) => pet_state,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+
+            let config = load_upgrade_config(deps.storage)?;
+            let refund = pet_state.respec(&config);
+
+            // the fee is netted against the refund, so only the difference is burned or minted
+            let mut messages: Vec<CosmosMsg> = vec![];
+            if config.respec_fee > refund {
+                let fee = config.respec_fee - refund;
+                let query = Loot20QueryWithPermit::Balance {};
+                let query_with_permit = Loot20QueryMsg::WithPermit { query, permit: loot_permit };
+                let balance = get_loot_balance(deps.as_ref(), query_with_permit)?;
+                if balance < fee {
+                    return Err(ContractError::InsufficientLoot { needed: fee, have: balance });
+                }
+                messages.push(burn_loot(info.sender.to_string(), deps.as_ref(), fee, info.funds)?);
+            } else if refund > config.respec_fee {
+                let payout = Uint128::from(refund - config.respec_fee);
+                messages.push(mint_loot(deps.as_ref(), info.sender.to_string(), payout, info.funds)?);
+            }
+            save_pet(&mut pet_state, deps.storage)?;
+            messages.extend(sync_pet_metadata(deps.storage, &pet_state)?);
+
+            Ok(Response::default()
+                .add_attribute("action", "respec_pet")
+                .add_attribute("pet_id", pet_id)
+                .add_attribute("fee", config.respec_fee.to_string())
+                .add_attribute("refund", refund.to_string())
+                .add_messages(messages))
+        }
+        ExecuteMsg::SpendStatPoints { pet_id, stat, points, pet_permit } => {
+            // check that we own the pet
+            let addr = info.sender.to_string();
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit
+            };
+            let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+            if owner != addr {
+                return Err(ContractError::NotPetOwner);
+            }
+
+            let mut pet_state = match PETS.get(deps.storage, &pet_id) {
+                Some(pet_state) => pet_state,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+
+            pet_state.spend_stat_points(stat, points, &load_upgrade_config(deps.storage)?)?;
+            save_pet(&mut pet_state, deps.storage)?;
+            let metadata_msg = sync_pet_metadata(deps.storage, &pet_state)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "spend_stat_points")
+                .add_attribute("pet_id", pet_id)
+                .add_messages(metadata_msg))
+        }
+        ExecuteMsg::RenamePet { pet_id, name, pet_permit } => {
+            // check that we own the pet
+            let addr = info.sender.to_string();
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit
+            };
+            let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+            if owner != addr {
+                return Err(ContractError::NotPetOwner);
+            }
+
+            let mut pet_state = match PETS.get(deps.storage, &pet_id) {
+                Some(pet_state) => pet_state,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+
+            if let Some(name) = &name {
+                validate_pet_name(name)?;
+            }
+            pet_state.name = name;
+            save_pet(&mut pet_state, deps.storage)?;
+            let metadata_msg = sync_pet_metadata(deps.storage, &pet_state)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "rename_pet")
+                .add_attribute("pet_id", pet_id)
+                .add_messages(metadata_msg))
+        }
+        ExecuteMsg::ResyncPets { pet_ids } => {
+            if pet_ids.len() > MAX_PETS_INFO as usize {
+                return Err(ContractError::TooManyPets { max: MAX_PETS_INFO });
+            }
+
+            // the metadata is derived from the pets, so anyone may bring tokens back in line
+            let mut messages: Vec<CosmosMsg> = vec![];
+            for pet_id in pet_ids {
+                let pet_state = match PETS.get(deps.storage, &pet_id) {
+                    Some(pet_state) => pet_state,
+                    None => return Err(ContractError::PetNotFound { pet_id })
+                };
+                messages.extend(sync_pet_metadata(deps.storage, &pet_state)?);
+            }
+
+            Ok(Response::default()
+                .add_attribute("action", "resync_pets")
+                .add_attribute("resynced", messages.len().to_string())
+                .add_messages(messages))
         }
-        ExecuteMsg::SendPetOnQuest { pet_id, quest_type,
+        ExecuteMsg::BreedPets { parent_a, parent_b, entropy,
+            permit: _, pet_permit, loot_permit } => {
+            if parent_a == parent_b {
+                return Err(ContractError::CannotBreedSelf);
+            }
+
+            // check that we own both parents
+            let addr = info.sender.to_string();
+            for pet_id in [&parent_a, &parent_b] {
+                let owner_msg = Pet721QueryMsg::WithPermit {
+                    query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                    permit: pet_permit.clone()
+                };
+                let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+                if owner != addr {
+                    return Err(ContractError::NotPetOwner);
+                }
+            }
+
+            // check if we have the funds
+            let config = load_breeding_config(deps.storage)?;
+            let mut messages: Vec<CosmosMsg> = vec![];
+            if config.fee > 0 {
+                let query = Loot20QueryWithPermit::Balance {};
+                let query_with_permit = Loot20QueryMsg::WithPermit { query, permit: loot_permit };
+                let balance = get_loot_balance(deps.as_ref(), query_with_permit)?;
+                if balance < config.fee {
+                    return Err(ContractError::InsufficientLoot { needed: config.fee, have: balance });
+                }
+                messages.push(burn_loot(addr.clone(), deps.as_ref(), config.fee, info.funds)?);
+            }
+
+            let token_id = next_pet_id(deps.storage)?;
+            let canonical = deps.api.addr_canonicalize(&addr)?;
+            let entropy = block_entropy(deps.storage, &env, &canonical, entropy)?;
+            let rng = draw_rng(deps.storage, &entropy)?;
+            let child = breed_pets(token_id.clone(), &parent_a, &parent_b, &env.block, deps.storage, rng)?;
+            let metadata = PetTokenMetadata::from_pet(&child, &load_privacy_policy(deps.storage)?);
+            record_token_metadata(&token_id, &metadata, deps.storage)?;
+
+            let nft_msg = Pet721ExecuteMsg::MintNft {
+                token_id: token_id.clone(),
+                owner: addr,
+                amount: Uint128::new(1),
+                memo: None,
+                decoys: None,
+                entropy: None,
+                padding: None,
+                public_metadata: Some(metadata.public_metadata),
+                private_metadata: Some(metadata.private_metadata),
+            };
+            let binary = Binary::from(to_binary(&nft_msg)?);
+            let contract_data = PET721_DATA.load(deps.storage)?;
+            messages.push(handle_pet_nft_execute(contract_data, binary, vec![]));
+
+            Ok(Response::default()
+                .add_attribute("action", "breed_pets")
+                .add_attribute("pet_id", token_id)
+                .add_messages(messages))
+        }
+        ExecuteMsg::SendPetOnQuest { pet_id, quest_type, entropy,
             permit: _, pet_permit, loot_permit: _ } => {
             // check that we own the pet
             let addr = info.sender.to_string();
@@ -172,37 +405,39 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
             if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to send it on a quest",
-                ));
+                return Err(ContractError::NotPetOwner);
             }
 
             // check that the pet is available
             let pet_availability = check_pet_availability(pet_id.clone(), deps.storage);
             if !pet_availability {
-                return Err(StdError::generic_err(
-                    "Pet is already on a quest",
-                ));
+                return Err(ContractError::PetOnQuest);
             }
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+            spend_pet_energy(pet_id.clone(), QUEST_ENERGY_COST, env.block.time, deps.storage)?;
 
             // check that the quest is available
             let address = deps.api.addr_canonicalize(info.sender.as_str())?;
             let quest_available = check_quest_availability(
-                address.clone(), quest_type.clone(), deps.storage, env.block.time);
+                address.clone(), quest_type.clone(), deps.storage, env.block.time)?;
             if !quest_available {
-                return Err(StdError::generic_err(
-                    "Quest is not available yet",
-                ));
+                return Err(ContractError::QuestNotAvailable);
             }
 
+            // roll the outcome now, so that it cannot be influenced before claiming
+            let entropy = block_entropy(deps.storage, &env, &address, entropy)?;
+            let mut rng = draw_rng(deps.storage, &entropy)?;
+            let outcome = calculate_outcome(
+                address.clone(), pet_id.clone(), quest_type.clone(), deps.storage, roll_quest_die(&mut rng))?;
+
             // update storage to reflect that pet is on quest and quest has been started
             update_quest_after_starting_explore(
-                pet_id.clone(), address.clone(), quest_type.clone(), deps.storage, env.block.time)?;
+                pet_id.clone(), address.clone(), quest_type.clone(), deps.storage, env.block.time, outcome)?;
             update_pet_on_quest(address, pet_id, deps.storage, Some(quest_type))?;
 
             Ok(Response::default())
         }
-        ExecuteMsg::ClaimQuestRewards { quest_type} => {
+        ExecuteMsg::ClaimQuestRewards { quest_type, entropy } => {
             let addr_string = info.sender.to_string();
 
             // check that quest is awaiting claiming
@@ -210,33 +445,28 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             let quest_awaiting_claim = check_quest_awaiting_claim(
                 address.clone(), quest_type.clone(), deps.storage, env.block.time);
             if !quest_awaiting_claim {
-                return Err(StdError::generic_err(
-                    "Quest is not ready to be claimed yet",
-                ));
+                return Err(ContractError::QuestNotClaimable);
             }
             // get quest
-            let quest = get_quest(address.clone(), quest_type.clone(), deps.storage);
-            let quest = match quest {
-                Ok(quest) => quest,
-                _ => return Err(StdError::generic_err(
-                    "Could not find given quest for pet",
-                ))
-            };
+            let quest = get_quest(address.clone(), quest_type.clone(), deps.storage)?;
 
             // get pet_id
             let pet_id = match quest.pet_id {
                 Some(pet_id) => pet_id,
                 None => {
                     // if this happens, there's a bug
-                    return Err(StdError::generic_err(
-                        "Quest did not have a pet attached to it",
-                    ))
+                    return Err(ContractError::QuestHasNoPet)
                 }
             };
 
-            // calculate outcome
-            let outcome: QuestOutcome = calculate_outcome(
-                address.clone(), pet_id.clone(), quest_type.clone(), deps.storage)?;
+            // quests started before outcomes were rolled on start are rolled now
+            let entropy = block_entropy(deps.storage, &env, &address, entropy)?;
+            let mut rng = draw_rng(deps.storage, &entropy)?;
+            let outcome: QuestOutcome = match quest.outcome.clone() {
+                Some(outcome) => outcome,
+                None => calculate_outcome(
+                    address.clone(), pet_id.clone(), quest_type.clone(), deps.storage, roll_quest_die(&mut rng))?
+            };
 
             // calculate winnings
             let loot: Loot = calculate_loot(
@@ -250,33 +480,39 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             // update storage for quest history
             let quest_history = QuestHistory {
                 pet_id: pet_id.clone(),
-                quest_type: quest.quest_type.to_string(),
-                time_started: quest.finished_exploring.unwrap().minus_seconds(QUEST_EXPLORE_TIME_SECONDS),
+                quest_type: quest.quest_type.clone(),
+                time_started: match quest.started_exploring {
+                    Some(started_exploring) => started_exploring,
+                    None => {
+                        // quests started before the start time was stored
+                        let definition = get_quest_definition(&quest_type, deps.storage)?;
+                        quest.finished_exploring.unwrap().minus_seconds(definition.explore_seconds)
+                    }
+                },
                 time_ended: quest.finished_exploring.unwrap(),
                 loot_collected: loot_collected.clone(),
-                outcome: outcome.clone().to_string()
+                outcome: outcome.clone()
             };
             insert_quest_history(address.clone(), quest_history, deps.storage)?;
 
-            // update storage to show that pet is available
+            // award the pet its experience and update storage to show that pet is available,
+            // the token only changes when the pet levels up
+            let pet_state = update_pet_progression(pet_id.clone(), &outcome, deps.storage)?;
+            let metadata_msg = sync_pet_metadata(deps.storage, &pet_state)?;
             remove_pet_on_quest(pet_id.clone(), deps.storage)?;
 
-            let config = CONFIG.load(deps.storage)?;
-            let seed = generate_seed(
-                &info.sender,
-                env.block.time.seconds(),
-                config.entropy.as_bytes()
-            );
-            let rng = SmallRng::seed_from_u64(seed);
             update_quest_after_claiming(address.clone(), quest_type.clone(), deps.storage, rng, outcome)?;
 
             // mint loot tokens
-            let mint_msg = mint_loot(deps.as_ref(), addr_string, loot_collected, info.funds)?;
+            let mint_msg = mint_loot(deps.as_ref(), addr_string, Uint128::from(loot_collected), info.funds)?;
             Ok(Response::default()
                 .add_attribute("action", "claim_rewards")
-                .add_message(mint_msg))
+                .add_attribute("pet_level", pet_state.progression.level.to_string())
+                .add_attribute("pet_xp", pet_state.progression.xp.to_string())
+                .add_message(mint_msg)
+                .add_messages(metadata_msg))
         }
-        ExecuteMsg::BattlePet { pet_id, other_pet_id, wager, permit: _,
+        ExecuteMsg::BattlePet { pet_id, other_pet_id, wager, expiration, permit: _,
             pet_permit, loot_permit } => {
             // check that we own the pet
             let addr = info.sender.to_string();
@@ -286,11 +522,40 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
             if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to send it on a quest",
-                ));
+                return Err(ContractError::NotPetOwner);
             };
 
+            // both pets must be free to fight and not already challenged by each other, the
+            // challenged pet is only locked once its owner accepts
+            if pet_id == other_pet_id {
+                return Err(ContractError::CannotBattleSelf);
+            }
+            if wager < MIN_BATTLE_WAGER {
+                return Err(ContractError::WagerTooLow { min: MIN_BATTLE_WAGER });
+            }
+            for id in [&pet_id, &other_pet_id] {
+                if PETS.get(deps.storage, id).is_none() {
+                    return Err(ContractError::PetNotFound { pet_id: id.clone() });
+                }
+                if !check_pet_availability(id.clone(), deps.storage) {
+                    return Err(ContractError::PetOnQuest);
+                }
+            }
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+            let pending = get_pending_battles(&pet_id, &env.block, deps.storage);
+            if let Some(battle) = pending.iter()
+                .find(|battle| battle.pet_id == other_pet_id || battle.other_pet_id == other_pet_id) {
+                return Err(ContractError::BattleAlreadyPending { id: battle.id });
+            }
+            let other_pending = get_pending_battles(&other_pet_id, &env.block, deps.storage);
+            for (id, count) in [(&pet_id, pending.len()), (&other_pet_id, other_pending.len())] {
+                if count >= MAX_PENDING_BATTLES_PER_PET as usize {
+                    return Err(ContractError::TooManyPendingBattles {
+                        pet_id: id.clone(), max: MAX_PENDING_BATTLES_PER_PET });
+                }
+            }
+            spend_pet_energy(pet_id.clone(), BATTLE_ENERGY_COST, env.block.time, deps.storage)?;
+
             // remove funds from the user
             // check if we have the funds
             let query = Loot20QueryWithPermit::Balance {};
@@ -299,13 +564,17 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
 
             // early return if balance is insufficient
             if balance < wager {
-                return Err(StdError::generic_err(
-                    "You do not have sufficient funds to upgrade this pet",
-                ));
+                return Err(ContractError::InsufficientLoot { needed: wager, have: balance });
+            }
+
+            let expiration = expiration.unwrap_or_else(|| Expiration::AtTime(
+                env.block.time.plus_seconds(DEFAULT_BATTLE_EXPIRY_SECONDS).seconds()));
+            if expiration.is_expired(&env.block) || !expiration.is_within(&env.block, MAX_BATTLE_EXPIRY_SECONDS) {
+                return Err(ContractError::InvalidExpiration);
             }
 
-            // burn the funds
-            let burn_msg = burn_loot(info.sender.to_string(), deps.as_ref(), wager, info.funds)?;
+            // hold the wager in escrow until the battle is settled
+            let escrow_msg = escrow_loot(info.sender.to_string(), deps.as_ref(), &env, Uint128::from(wager), info.funds)?;
 
             // store two sets of data
             let next_battle_id = BATTLE_COUNTER.may_load(deps.storage)?.unwrap_or(0);
@@ -318,8 +587,10 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
                 pet_id: pet_id.clone(),
                 other_pet_id: other_pet_id.clone(),
                 wager,
-                status: "pending".to_string(),
+                status: BattleStatus::Pending,
                 outcome: None,
+                escrowed: true,
+                expiration,
             };
             BATTLES.insert(deps.storage, &next_battle_id, &battle_info)?;
 
@@ -329,32 +600,53 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             // store some data
             Ok(Response::default()
                 .add_attribute("action", "battle_pet")
-                .add_message(burn_msg)
+                .add_message(escrow_msg)
             )
         }
-        ExecuteMsg::AcceptBattle { battle_id, permit: _, loot_permit, pet_permit} => {
+        ExecuteMsg::AcceptBattle { battle_id, entropy, permit: _, loot_permit, pet_permit} => {
             let battle = match BATTLES.get(deps.storage, &battle_id) {
                 Some(battle) => battle,
-                None => {
-                    return Err(StdError::generic_err(
-                        "Could not find a battle for the given id",
-                    ));
-                }
+                None => return Err(ContractError::BattleNotFound { id: battle_id })
             };
+            if battle.status != BattleStatus::Pending {
+                return Err(ContractError::BattleNotPending { id: battle_id });
+            }
+            if battle.is_expired(&env.block) {
+                return Err(ContractError::BattleExpired { id: battle_id });
+            }
 
             // check that we own the pet
-            let pet_id = battle.other_pet_id;
+            let pet_id = battle.other_pet_id.clone();
             let addr = info.sender.to_string();
             let owner_msg = Pet721QueryMsg::WithPermit {
                 query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
-                permit: pet_permit
+                permit: pet_permit.clone()
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
             if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to accept its battle",
-                ));
+                return Err(ContractError::NotPetOwner);
+            };
+            if !check_pet_availability(pet_id.clone(), deps.storage) {
+                return Err(ContractError::PetOnQuest);
+            }
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+
+            // a challenge made with a pet that was transferred since is cancelled instead, pet
+            // owners are public so the permit of either player can see the challenger's owner
+            let initiator = deps.api.addr_humanize(&battle.initiator_address)?.to_string();
+            let challenger_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: battle.pet_id.clone(), include_expired: None },
+                permit: pet_permit
             };
+            if get_pet_owner(deps.as_ref(), challenger_msg)? != initiator {
+                let refund_msg = refund_battle(deps, &battle, info.funds)?;
+                return Ok(Response::default()
+                    .add_attribute("action", "cancel_battle_pet")
+                    .add_attribute("reason", "pet_transferred")
+                    .add_message(refund_msg)
+                );
+            }
+            spend_pet_energy(pet_id.clone(), BATTLE_ENERGY_COST, env.block.time, deps.storage)?;
 
             // remove funds from the user
             // check if we have the funds
@@ -365,15 +657,26 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             // early return if balance is insufficient
             let wager = battle.wager;
             if balance < wager {
-                return Err(StdError::generic_err(
-                    "You do not have sufficient funds to upgrade this pet",
-                ));
+                return Err(ContractError::InsufficientLoot { needed: wager, have: balance });
             }
 
-            // burn the funds
-            let burn_msg = burn_loot(info.sender.to_string(), deps.as_ref(), wager, info.funds)?;
+            // hold the wager in escrow until the battle is claimed, challenges made before
+            // escrow burned the first wager so the second one is burned as well
+            let wager_msg = if battle.escrowed {
+                escrow_loot(info.sender.to_string(), deps.as_ref(), &env, Uint128::from(wager), info.funds)?
+            } else {
+                burn_loot(info.sender.to_string(), deps.as_ref(), wager, info.funds)?
+            };
+
+            // fight the rounds with the contract rng, the log is kept for the battle details
+            let address = deps.api.addr_canonicalize(info.sender.as_str())?;
+            let entropy = block_entropy(deps.storage, &env, &address, entropy)?;
+            let mut rng = draw_rng(deps.storage, &entropy)?;
+            let (outcome, log) = battle_pets(battle.pet_id.clone(), pet_id.clone(), deps.storage, &mut rng)?;
+            let rounds = log.rounds;
+            BATTLE_LOGS.insert(deps.storage, &battle_id, &log)?;
+            update_battle_records(battle.pet_id.clone(), pet_id.clone(), &outcome, deps.storage)?;
 
-            let outcome = battle_pets(battle.pet_id.clone(), pet_id.clone(), deps.storage)?;
             // update the battle
             let updated_battle = BattleInfo {
                 id: battle_id,
@@ -381,25 +684,27 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
                 pet_id: battle.pet_id,
                 other_pet_id: pet_id,
                 wager,
-                status: "accepted".to_string(),
+                status: BattleStatus::Accepted,
                 outcome: Some(outcome),
+                escrowed: battle.escrowed,
+                expiration: battle.expiration,
             };
             BATTLES.insert(deps.storage, &battle_id, &updated_battle)?;
 
             Ok(Response::default()
                 .add_attribute("action", "accept_battle_pet")
-                .add_message(burn_msg)
+                .add_attribute("rounds", rounds.to_string())
+                .add_message(wager_msg)
             )
         },
         ExecuteMsg::DeclineBattle { battle_id, permit: _, pet_permit} => {
             let battle = match BATTLES.get(deps.storage, &battle_id) {
                 Some(battle) => battle,
-                None => {
-                    return Err(StdError::generic_err(
-                        "Could not find a battle for the given id",
-                    ));
-                }
+                None => return Err(ContractError::BattleNotFound { id: battle_id })
             };
+            if battle.status != BattleStatus::Pending {
+                return Err(ContractError::BattleNotPending { id: battle_id });
+            }
 
             // check that we own the pet
             let pet_id = battle.other_pet_id.clone();
@@ -410,40 +715,25 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
             if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to decline its battle",
-                ));
+                return Err(ContractError::NotPetOwner);
             };
 
-            BATTLES.remove(deps.storage, &battle_id)?;
-            remove_battle_for_pet(battle.pet_id, battle_id, deps.storage)?;
-            remove_battle_for_pet(battle.other_pet_id, battle_id, deps.storage)?;
-
             // return wager
-            let initiator_address = match deps.api.addr_humanize(&battle.initiator_address) {
-                Ok(address) => address.to_string(),
-                Err(_) => {
-                    return Err(StdError::generic_err(
-                        "Unable to parse the initiator address for returning wager",
-                    ));
-                }
-            };
-            let mint_msg = mint_loot(deps.as_ref(), initiator_address, battle.wager as u16, info.funds)?;
+            let refund_msg = refund_battle(deps, &battle, info.funds)?;
 
             Ok(Response::default()
                 .add_attribute("action", "decline_battle_pet")
-                .add_message(mint_msg)
+                .add_message(refund_msg)
             )
         },
         ExecuteMsg::CancelBattle { battle_id, permit: _, pet_permit} => {
             let battle = match BATTLES.get(deps.storage, &battle_id) {
                 Some(battle) => battle,
-                None => {
-                    return Err(StdError::generic_err(
-                        "Could not find a battle for the given id",
-                    ));
-                }
+                None => return Err(ContractError::BattleNotFound { id: battle_id })
             };
+            if battle.status != BattleStatus::Pending {
+                return Err(ContractError::BattleNotPending { id: battle_id });
+            }
 
             // check that we own the pet
             let pet_id = battle.pet_id.clone();
@@ -453,40 +743,24 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
                 permit: pet_permit
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
-            if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to cancel its battle",
-                ));
+            let initiator = deps.api.addr_humanize(&battle.initiator_address)?.to_string();
+            // the initiator can still cancel after transferring the pet
+            if owner != addr && initiator != addr {
+                return Err(ContractError::NotPetOwner);
             };
 
-            BATTLES.remove(deps.storage, &battle_id)?;
-            remove_battle_for_pet(battle.pet_id, battle_id, deps.storage)?;
-            remove_battle_for_pet(battle.other_pet_id, battle_id, deps.storage)?;
-
             // return wager
-            let initiator_address = match deps.api.addr_humanize(&battle.initiator_address) {
-                Ok(address) => address.to_string(),
-                Err(_) => {
-                    return Err(StdError::generic_err(
-                        "Unable to parse the initiator address for returning wager",
-                    ));
-                }
-            };
-            let mint_msg = mint_loot(deps.as_ref(), initiator_address, battle.wager as u16, info.funds)?;
+            let refund_msg = refund_battle(deps, &battle, info.funds)?;
 
             Ok(Response::default()
                 .add_attribute("action", "cancel_battle_pet")
-                .add_message(mint_msg)
+                .add_message(refund_msg)
             )
         }
         ExecuteMsg::ClaimBattle { battle_id, pet_id, pet_permit } => {
             let battle = match BATTLES.get(deps.storage, &battle_id) {
                 Some(battle) => battle,
-                None => {
-                    return Err(StdError::generic_err(
-                        "Could not find a battle for the given id",
-                    ));
-                }
+                None => return Err(ContractError::BattleNotFound { id: battle_id })
             };
 
             // check that we own the pet
@@ -497,30 +771,27 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
             };
             let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
             if owner != addr {
-                return Err(StdError::generic_err(
-                    "You must be the owner of this pet to collect its battle claim",
-                ));
-            };
-
-            // check if they won
-            let did_win = match battle.outcome {
-                Some(true) => battle.pet_id == pet_id,
-                Some(false) => battle.other_pet_id == pet_id,
-                _ => {
-                    return Err(StdError::generic_err(
-                        "Battle is not ready to be claimed",
-                    ));
-                }
+                return Err(ContractError::NotPetOwner);
             };
 
+            // each pet can claim a battle once, claiming removes it from the pet's battles
+            let pet_battles = PET_BATTLES.get(deps.storage, &pet_id).unwrap_or_default();
+            if !pet_battles.contains(&battle_id) {
+                return Err(ContractError::BattleNotClaimable);
+            }
+
+            if battle.outcome.is_none() {
+                return Err(ContractError::BattleNotClaimable);
+            }
+
             let mut res = Response::default().add_attribute("action", "claim_battle_pet");
 
-            // if they won, award them 2x the wager
-            if did_win {
+            // winners are paid both wagers and a draw returns each pet its own wager
+            let payout = battle.payout(&pet_id)?;
+            if !payout.is_zero() {
                 let addr_string = info.sender.to_string();
-                let mint_msg = mint_loot(deps.as_ref(), addr_string,
-                                         battle.wager.mul(2) as u16, info.funds)?;
-                res = res.add_message(mint_msg);
+                let payout_msg = release_wager(deps.as_ref(), &battle, addr_string, payout, info.funds)?;
+                res = res.add_message(payout_msg);
             };
 
             // remove the battle from their pet
@@ -528,73 +799,686 @@ pub(crate) fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMs
 
             Ok(res)
         }
-    }
-}
+        ExecuteMsg::ReclaimExpiredBattle { battle_id } => {
+            let battle = match BATTLES.get(deps.storage, &battle_id) {
+                Some(battle) => battle,
+                None => return Err(ContractError::BattleNotFound { id: battle_id })
+            };
+            if !battle.is_expired(&env.block) {
+                return Err(ContractError::BattleNotExpired { id: battle_id });
+            }
 
-#[entry_point]
-pub(crate) fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    let msg_to_send = msg.clone();
-    match msg {
-        QueryMsg::AllPets { .. } => {
-            // query pet contract for ids
-            let contract_data = PET721_DATA.load(deps.storage)?;
-            let msg = Pet721QueryMsg::from_query_message(msg);
-            let answer = handle_pet721_query(deps, contract_data, msg)?;
-            Ok(to_binary(&answer)?)
-        }
-        QueryMsg::AllLoot { .. } => {
-            // query loot contract for ids
-            let contract_data = LOOT20_DATA.load(deps.storage)?;
-            let msg = Loot20QueryMsg::from_query_message(msg);
-            let answer = handle_loot20_query(deps, contract_data, msg)?;
-            Ok(to_binary(&answer)?)
+            // return wager
+            let refund_msg = refund_battle(deps, &battle, info.funds)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "reclaim_expired_battle")
+                .add_message(refund_msg)
+            )
         }
-        QueryMsg::WithPermits { query, permit,
+        ExecuteMsg::EnterMatchmaking { pet_id, wager, entropy, permit: _,
             loot_permit, pet_permit } => {
-            permit_queries(deps, env, msg_to_send, query, permit, loot_permit, pet_permit)
-        }
-    }
-}
+            // check that we own the pet
+            let addr = info.sender.to_string();
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit.clone()
+            };
+            let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+            if owner != addr {
+                return Err(ContractError::NotPetOwner);
+            };
 
-pub(crate) fn permit_queries(
-    deps: Deps,
-    env: Env,
-    msg: QueryMsg,
-    query: QueryWithPermits,
-    permit: Permit,
-    _loot_permit: Permit,
-    _pet_permit: Permit
-) -> StdResult<Binary> {
-    let addr = secret_toolkit::permit::validate(
-        deps,
-        "revoked_permits",
-        &permit,
-        env.contract.address.to_string(),
-        None,
-    )?;
+            let pet = match PETS.get(deps.storage, &pet_id) {
+                Some(pet) => pet,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+            if MATCHMAKING_PETS.contains(deps.storage, &pet_id) {
+                return Err(ContractError::AlreadyInMatchmaking { pet_id });
+            }
+            if wager < MIN_BATTLE_WAGER {
+                return Err(ContractError::WagerTooLow { min: MIN_BATTLE_WAGER });
+            }
+            if !check_pet_availability(pet_id.clone(), deps.storage) {
+                return Err(ContractError::PetOnQuest);
+            }
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+            spend_pet_energy(pet_id.clone(), BATTLE_ENERGY_COST, env.block.time, deps.storage)?;
 
-    // permit validated, process query
-    match query {
-        QueryWithPermits::MyPets { .. } => {
-            // query NFT contract
-            let contract_data = PET721_DATA.load(deps.storage)?;
-            let msg = Pet721QueryMsg::from_query_message(msg);
-            let answer = handle_pet721_query(deps, contract_data, msg)?;
+            // check if we have the funds
+            let query = Loot20QueryWithPermit::Balance {};
+            let query_with_permit = Loot20QueryMsg::WithPermit { query, permit: loot_permit };
+            let balance = get_loot_balance(deps.as_ref(), query_with_permit)?;
+            if balance < wager {
+                return Err(ContractError::InsufficientLoot { needed: wager, have: balance });
+            }
 
-            let mut pet_data: Vec<PetState> = vec![];
-            if let Pet721QueryAnswer::TokenList{ tokens: pet_ids} = answer.clone() {
-                for pet_id in pet_ids {
-                    let pet_state: Option<PetState> = PETS.get(deps.storage, &pet_id.clone());
-                    if pet_state.is_none() {
-                        return Err(StdError::generic_err(
-                            format!("Could not find any stat data for {pet_id}"),
-                        ));
-                    }
-                    pet_data.push(pet_state.unwrap())
+            // hold the wager in escrow until the battle is claimed or the pet leaves the queue
+            let mut messages = vec![
+                escrow_loot(addr.clone(), deps.as_ref(), &env, Uint128::from(wager), info.funds)?
+            ];
+            let address = deps.api.addr_canonicalize(&addr)?;
+            let entry = MatchmakingEntry {
+                pet_id: pet_id.clone(),
+                owner: address.clone(),
+                wager,
+                rating: pet.record.rating,
+            };
+
+            // pair with the longest waiting compatible pet, queued pets that were transferred
+            // since are taken out of the queue and refunded to the player that queued them
+            let mut opponent: Option<MatchmakingEntry> = None;
+            for candidate in get_matchmaking_candidates(&entry, deps.storage) {
+                let candidate_owner = deps.api.addr_humanize(&candidate.owner)?.to_string();
+                let owner_msg = Pet721QueryMsg::WithPermit {
+                    query: OwnerOf { token_id: candidate.pet_id.clone(), include_expired: None },
+                    permit: pet_permit.clone()
+                };
+                leave_matchmaking(&candidate.pet_id, deps.storage)?;
+                if get_pet_owner(deps.as_ref(), owner_msg)? == candidate_owner {
+                    opponent = Some(candidate);
+                    break;
                 }
+                messages.push(transfer_loot(deps.as_ref(), candidate_owner, Uint128::from(candidate.wager), vec![])?);
             }
-            let answer = QueryAnswer::Pets { pets: pet_data };
-            Ok(to_binary(&answer)?)
+
+            let opponent = match opponent {
+                Some(opponent) => opponent,
+                None => {
+                    enter_matchmaking(entry, deps.storage)?;
+                    return Ok(Response::default()
+                        .add_attribute("action", "enter_matchmaking")
+                        .add_attribute("matchmaking", "queued")
+                        .add_messages(messages)
+                    );
+                }
+            };
+
+            // the waiting pet is the challenger and the battle is fought right away
+            let entropy = block_entropy(deps.storage, &env, &address, entropy)?;
+            let mut rng = draw_rng(deps.storage, &entropy)?;
+            let (outcome, log) = battle_pets(opponent.pet_id.clone(), pet_id.clone(), deps.storage, &mut rng)?;
+            let rounds = log.rounds;
+            update_battle_records(opponent.pet_id.clone(), pet_id.clone(), &outcome, deps.storage)?;
+
+            let battle_id = BATTLE_COUNTER.may_load(deps.storage)?.unwrap_or(0);
+            BATTLE_COUNTER.save(deps.storage, &(battle_id + 1))?;
+            let battle_info = BattleInfo {
+                id: battle_id,
+                initiator_address: opponent.owner,
+                pet_id: opponent.pet_id.clone(),
+                other_pet_id: pet_id.clone(),
+                wager,
+                status: BattleStatus::Accepted,
+                outcome: Some(outcome),
+                escrowed: true,
+                expiration: Expiration::Never,
+            };
+            BATTLES.insert(deps.storage, &battle_id, &battle_info)?;
+            BATTLE_LOGS.insert(deps.storage, &battle_id, &log)?;
+
+            insert_battle_for_pet(opponent.pet_id, battle_id, deps.storage)?;
+            insert_battle_for_pet(pet_id, battle_id, deps.storage)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "enter_matchmaking")
+                .add_attribute("matchmaking", "matched")
+                .add_attribute("battle_id", battle_id.to_string())
+                .add_attribute("rounds", rounds.to_string())
+                .add_messages(messages)
+            )
+        }
+        ExecuteMsg::LeaveMatchmaking { pet_id, pet_permit } => {
+            // check that we own the pet
+            let addr = info.sender.to_string();
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit
+            };
+            let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+            if owner != addr {
+                return Err(ContractError::NotPetOwner);
+            };
+
+            // the wager goes back to the player that queued the pet
+            let entry = match leave_matchmaking(&pet_id, deps.storage)? {
+                Some(entry) => entry,
+                None => return Err(ContractError::NotInMatchmaking { pet_id })
+            };
+            let recipient = deps.api.addr_humanize(&entry.owner)?.to_string();
+            let refund_msg = transfer_loot(deps.as_ref(), recipient, Uint128::from(entry.wager), info.funds)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "leave_matchmaking")
+                .add_message(refund_msg)
+            )
+        }
+        ExecuteMsg::CreateTournament { name, entry_fee, max_entrants, registration, prize_split } => {
+            if name.trim().is_empty() {
+                return Err(ContractError::InvalidTournament { reason: "name must not be empty".to_string() });
+            }
+            if !(MIN_TOURNAMENT_ENTRANTS..=MAX_TOURNAMENT_ENTRANTS).contains(&max_entrants) {
+                return Err(ContractError::InvalidTournament { reason: format!(
+                    "max_entrants must be between {} and {}", MIN_TOURNAMENT_ENTRANTS, MAX_TOURNAMENT_ENTRANTS) });
+            }
+            let prize_split = prize_split.unwrap_or_else(|| DEFAULT_PRIZE_SPLIT.to_vec());
+            if prize_split.is_empty() || prize_split.len() > MAX_PRIZE_PLACES {
+                return Err(ContractError::InvalidTournament { reason: format!(
+                    "prize_split must pay between 1 and {} places", MAX_PRIZE_PLACES) });
+            }
+            if prize_split.iter().map(|share| u32::from(*share)).sum::<u32>() != 100 {
+                return Err(ContractError::InvalidTournament { reason: "prize_split must add up to 100".to_string() });
+            }
+
+            let registration = registration.unwrap_or_else(|| Expiration::AtTime(
+                env.block.time.plus_seconds(DEFAULT_TOURNAMENT_REGISTRATION_SECONDS).seconds()));
+            if registration.is_expired(&env.block) || !registration.is_within(&env.block, MAX_TOURNAMENT_REGISTRATION_SECONDS) {
+                return Err(ContractError::InvalidExpiration);
+            }
+
+            let tournament_id = TOURNAMENT_COUNTER.may_load(deps.storage)?.unwrap_or(0);
+            TOURNAMENT_COUNTER.save(deps.storage, &(tournament_id + 1))?;
+            let tournament = Tournament {
+                id: tournament_id,
+                name,
+                creator: deps.api.addr_canonicalize(info.sender.as_str())?,
+                entry_fee,
+                max_entrants,
+                registration,
+                prize_split,
+                status: TournamentStatus::Registration,
+                entrants: vec![],
+                matches: vec![],
+                placements: vec![],
+            };
+            TOURNAMENTS.insert(deps.storage, &tournament_id, &tournament)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "create_tournament")
+                .add_attribute("tournament_id", tournament_id.to_string()))
+        }
+        ExecuteMsg::JoinTournament { tournament_id, pet_id, entropy, permit: _,
+            loot_permit, pet_permit } => {
+            let mut tournament = match TOURNAMENTS.get(deps.storage, &tournament_id) {
+                Some(tournament) => tournament,
+                None => return Err(ContractError::TournamentNotFound { id: tournament_id })
+            };
+            if tournament.status != TournamentStatus::Registration || tournament.registration.is_expired(&env.block) {
+                return Err(ContractError::TournamentClosed { id: tournament_id });
+            }
+            if tournament.is_full() {
+                return Err(ContractError::TournamentFull { id: tournament_id, max: tournament.max_entrants });
+            }
+
+            // check that we own the pet
+            let addr = info.sender.to_string();
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit
+            };
+            let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+            if owner != addr {
+                return Err(ContractError::NotPetOwner);
+            };
+
+            // registered pets are locked, so a pet can only be in one tournament at a time
+            if PETS.get(deps.storage, &pet_id).is_none() {
+                return Err(ContractError::PetNotFound { pet_id });
+            }
+            if !check_pet_availability(pet_id.clone(), deps.storage) {
+                return Err(ContractError::PetOnQuest);
+            }
+            check_pet_free(&pet_id, &env.block, deps.storage)?;
+            spend_pet_energy(pet_id.clone(), BATTLE_ENERGY_COST, env.block.time, deps.storage)?;
+
+            // check if we have the funds
+            let query = Loot20QueryWithPermit::Balance {};
+            let query_with_permit = Loot20QueryMsg::WithPermit { query, permit: loot_permit };
+            let balance = get_loot_balance(deps.as_ref(), query_with_permit)?;
+            let entry_fee = tournament.entry_fee;
+            if balance < entry_fee {
+                return Err(ContractError::InsufficientLoot { needed: entry_fee, have: balance });
+            }
+
+            // the entry fee is held in escrow until the bracket is played
+            let mut messages: Vec<CosmosMsg> = vec![];
+            if entry_fee > 0 {
+                messages.push(escrow_loot(addr, deps.as_ref(), &env, Uint128::from(entry_fee), info.funds)?);
+            }
+            let address = deps.api.addr_canonicalize(info.sender.as_str())?;
+            tournament.entrants.push(TournamentEntrant { pet_id: pet_id.clone(), owner: address.clone() });
+            TOURNAMENT_PETS.insert(deps.storage, &pet_id, &tournament_id)?;
+
+            let mut res = Response::default()
+                .add_attribute("action", "join_tournament")
+                .add_attribute("tournament_id", tournament_id.to_string());
+            if tournament.is_full() {
+                let entropy = block_entropy(deps.storage, &env, &address, entropy)?;
+                let mut rng = draw_rng(deps.storage, &entropy)?;
+                messages.extend(play_tournament(deps.branch(), &mut tournament, &mut rng)?);
+                res = res.add_attribute("tournament", "played");
+            }
+            TOURNAMENTS.insert(deps.storage, &tournament_id, &tournament)?;
+
+            Ok(res.add_messages(messages))
+        }
+        ExecuteMsg::LeaveTournament { tournament_id, pet_id, pet_permit } => {
+            let mut tournament = match TOURNAMENTS.get(deps.storage, &tournament_id) {
+                Some(tournament) => tournament,
+                None => return Err(ContractError::TournamentNotFound { id: tournament_id })
+            };
+            if tournament.status != TournamentStatus::Registration {
+                return Err(ContractError::TournamentClosed { id: tournament_id });
+            }
+
+            // check that we own the pet
+            let addr = info.sender.to_string();
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit
+            };
+            let owner = get_pet_owner(deps.as_ref(), owner_msg)?;
+            if owner != addr {
+                return Err(ContractError::NotPetOwner);
+            };
+
+            // the entry fee goes back to the player that registered the pet
+            let index = match tournament.entrants.iter().position(|entrant| entrant.pet_id == pet_id) {
+                Some(index) => index,
+                None => return Err(ContractError::NotInTournament { pet_id })
+            };
+            let entrant = tournament.entrants.remove(index);
+            TOURNAMENT_PETS.remove(deps.storage, &pet_id)?;
+            TOURNAMENTS.insert(deps.storage, &tournament_id, &tournament)?;
+
+            let mut messages: Vec<CosmosMsg> = vec![];
+            if tournament.entry_fee > 0 {
+                let recipient = deps.api.addr_humanize(&entrant.owner)?.to_string();
+                messages.push(transfer_loot(deps.as_ref(), recipient, Uint128::from(tournament.entry_fee), info.funds)?);
+            }
+
+            Ok(Response::default()
+                .add_attribute("action", "leave_tournament")
+                .add_attribute("tournament_id", tournament_id.to_string())
+                .add_messages(messages))
+        }
+        ExecuteMsg::PlayTournament { tournament_id, entropy } => {
+            let mut tournament = match TOURNAMENTS.get(deps.storage, &tournament_id) {
+                Some(tournament) => tournament,
+                None => return Err(ContractError::TournamentNotFound { id: tournament_id })
+            };
+            if tournament.status != TournamentStatus::Registration {
+                return Err(ContractError::TournamentClosed { id: tournament_id });
+            }
+            if !tournament.registration.is_expired(&env.block) && !tournament.is_full() {
+                return Err(ContractError::TournamentRegistrationOpen { id: tournament_id });
+            }
+
+            let address = deps.api.addr_canonicalize(info.sender.as_str())?;
+            let entropy = block_entropy(deps.storage, &env, &address, entropy)?;
+            let mut rng = draw_rng(deps.storage, &entropy)?;
+            let messages = play_tournament(deps.branch(), &mut tournament, &mut rng)?;
+            TOURNAMENTS.insert(deps.storage, &tournament_id, &tournament)?;
+
+            let status = match tournament.status {
+                TournamentStatus::Cancelled => "cancelled",
+                _ => "played"
+            };
+            Ok(Response::default()
+                .add_attribute("action", "play_tournament")
+                .add_attribute("tournament", status)
+                .add_messages(messages))
+        }
+        ExecuteMsg::UpdateConfig { max_stats, entropy } => {
+            let mut config = check_admin(deps.storage, &info)?;
+
+            if let Some(max_stats) = max_stats {
+                if max_stats < MIN_MAX_STATS || max_stats > MAX_STAT_VALUE {
+                    return Err(ContractError::InvalidMaxStats { min: MIN_MAX_STATS, max: MAX_STAT_VALUE });
+                }
+                config.max_stats = max_stats;
+            }
+            if let Some(entropy) = entropy {
+                mix_prng_entropy(deps.storage, entropy.as_bytes())?;
+                config.entropy = entropy;
+            }
+            CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "update_config"))
+        }
+        ExecuteMsg::SetLootContract { contract } => {
+            check_admin(deps.storage, &info)?;
+            deps.api.addr_validate(&contract.addr)?;
+            LOOT20_DATA.save(deps.storage, &contract)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_loot_contract")
+                .add_attribute("contract", contract.addr))
+        }
+        ExecuteMsg::SetPetContract { contract } => {
+            check_admin(deps.storage, &info)?;
+            deps.api.addr_validate(&contract.addr)?;
+            PET721_DATA.save(deps.storage, &contract)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_pet_contract")
+                .add_attribute("contract", contract.addr))
+        }
+        ExecuteMsg::ChangeAdmin { address } => {
+            let mut config = check_admin(deps.storage, &info)?;
+            config.admin = deps.api.addr_validate(&address)?;
+            CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "change_admin")
+                .add_attribute("admin", address))
+        }
+        ExecuteMsg::AddQuest { quest } => {
+            check_admin(deps.storage, &info)?;
+            quest.validate()?;
+            if QUEST_CATALOGUE.get(deps.storage, &quest.id).is_some() {
+                return Err(ContractError::QuestAlreadyExists { id: quest.id });
+            }
+            QUEST_CATALOGUE.insert(deps.storage, &quest.id, &quest)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "add_quest")
+                .add_attribute("quest_id", quest.id))
+        }
+        ExecuteMsg::UpdateQuest { quest } => {
+            check_admin(deps.storage, &info)?;
+            quest.validate()?;
+            get_quest_definition(&quest.id, deps.storage)?;
+            QUEST_CATALOGUE.insert(deps.storage, &quest.id, &quest)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "update_quest")
+                .add_attribute("quest_id", quest.id))
+        }
+        ExecuteMsg::SetContractStatus { level } => {
+            check_admin(deps.storage, &info)?;
+            CONTRACT_STATUS.save(deps.storage, &level)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_contract_status"))
+        }
+        ExecuteMsg::SetProgressionConfig { config } => {
+            check_admin(deps.storage, &info)?;
+            config.validate()?;
+            PROGRESSION_CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_progression_config"))
+        }
+        ExecuteMsg::SetTieBreaker { tie_breaker } => {
+            check_admin(deps.storage, &info)?;
+            TIE_BREAKER.save(deps.storage, &tie_breaker)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_tie_breaker"))
+        }
+        ExecuteMsg::SetPrivacyPolicy { policy } => {
+            check_admin(deps.storage, &info)?;
+            PRIVACY_POLICY.save(deps.storage, &policy)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_privacy_policy"))
+        }
+        ExecuteMsg::SetUpgradeConfig { config } => {
+            check_admin(deps.storage, &info)?;
+            config.validate()?;
+            UPGRADE_CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_upgrade_config"))
+        }
+        ExecuteMsg::SetSpeciesConfig { config } => {
+            check_admin(deps.storage, &info)?;
+            config.validate()?;
+            SPECIES_CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_species_config"))
+        }
+        ExecuteMsg::SetBreedingConfig { config } => {
+            check_admin(deps.storage, &info)?;
+            config.validate()?;
+            BREEDING_CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "set_breeding_config"))
+        }
+    }
+}
+
+#[entry_point]
+pub(crate) fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let msg_to_send = msg.clone();
+    match msg {
+        QueryMsg::ContractInfo { } => {
+            let info = CONTRACT_INFO.load(deps.storage)?;
+            let answer = QueryAnswer::ContractInfo {
+                contract: info.contract,
+                version: info.version,
+                storage_version: info.storage_version,
+            };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::QuestCatalogue { } => {
+            let answer = QueryAnswer::QuestCatalogue { quests: get_quest_catalogue(deps.storage)? };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::Config { } => {
+            let config = CONFIG.load(deps.storage)?;
+            let answer = QueryAnswer::Config {
+                admin: config.admin,
+                max_stats: config.max_stats,
+                status: load_contract_status(deps.storage)?,
+                loot_contract: LOOT20_DATA.load(deps.storage)?,
+                pet_contract: PET721_DATA.load(deps.storage)?,
+                progression: load_progression_config(deps.storage)?,
+                tie_breaker: load_tie_breaker(deps.storage)?,
+                privacy: load_privacy_policy(deps.storage)?,
+                upgrades: load_upgrade_config(deps.storage)?,
+                breeding: load_breeding_config(deps.storage)?,
+                species: load_species_config(deps.storage)?,
+            };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::PetEnergy { pet_id } => {
+            if !load_privacy_policy(deps.storage)?.energy {
+                return Err(ContractError::PetFieldPrivate { field: "energy".to_string() });
+            }
+            let pet = match PETS.get(deps.storage, &pet_id) {
+                Some(pet) => pet,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+            let answer = QueryAnswer::PetEnergy {
+                energy: pet.energy_at(env.block.time),
+                max_energy: pet.max_energy(),
+                seconds_until_full: pet.seconds_until_full(env.block.time),
+                pet_id,
+            };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::PetInfo { pet_id } => {
+            let pet = match PETS.get(deps.storage, &pet_id) {
+                Some(pet) => pet,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+            let policy = load_privacy_policy(deps.storage)?;
+            let answer = QueryAnswer::PetInfo { pet: pet.public_info(&policy, env.block.time) };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::PetsInfo { pet_ids } => {
+            if pet_ids.len() > MAX_PETS_INFO as usize {
+                return Err(ContractError::TooManyPets { max: MAX_PETS_INFO });
+            }
+            let policy = load_privacy_policy(deps.storage)?;
+            let pets = pet_ids.iter()
+                .filter_map(|pet_id| PETS.get(deps.storage, pet_id))
+                .map(|pet| pet.public_info(&policy, env.block.time))
+                .collect();
+            let answer = QueryAnswer::PetsInfo { pets };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::RaritySupply { } => {
+            let answer = QueryAnswer::RaritySupply { supply: get_rarity_supply(deps.storage)? };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::PetLineage { pet_id } => {
+            if !load_privacy_policy(deps.storage)?.lineage {
+                return Err(ContractError::PetFieldPrivate { field: "lineage".to_string() });
+            }
+            let pet = match PETS.get(deps.storage, &pet_id) {
+                Some(pet) => pet,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+            let config = load_breeding_config(deps.storage)?;
+            let answer = QueryAnswer::PetLineage {
+                breeding_cooldown: pet.breeding_cooldown(&config, env.block.time),
+                lineage: pet.lineage,
+                pet_id,
+            };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::PetBattles { pet_id } => {
+            if !load_privacy_policy(deps.storage)?.open_challenges {
+                return Err(ContractError::PetFieldPrivate { field: "open_challenges".to_string() });
+            }
+            if !PETS.contains(deps.storage, &pet_id) {
+                return Err(ContractError::PetNotFound { pet_id });
+            }
+            // only the challenges the pet can accept, its own ones are in `MyBattles`
+            let battles = get_pending_battles(&pet_id, &env.block, deps.storage)
+                .into_iter()
+                .filter(|battle| battle.other_pet_id == pet_id)
+                .map(|battle| battle.without_initiator(&env.block))
+                .collect();
+            let answer = QueryAnswer::Battles { battles };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::Leaderboard { limit, start_after } => {
+            let start_after = match start_after {
+                Some(pet_id) => match get_leaderboard_rating(&pet_id, deps.storage) {
+                    Some(rating) => Some(RatedPet { pet_id, rating }),
+                    None => return Err(ContractError::PetNotFound { pet_id })
+                },
+                None => None
+            };
+            let limit = limit.unwrap_or(DEFAULT_LEADERBOARD_LIMIT).min(MAX_LEADERBOARD_LIMIT) as usize;
+
+            let policy = load_privacy_policy(deps.storage)?;
+            let mut entries: Vec<LeaderboardEntry> = vec![];
+            for (rank, rated) in get_leaderboard_page(start_after, limit, deps.storage)? {
+                if let Some(pet) = PETS.get(deps.storage, &rated.pet_id) {
+                    entries.push(LeaderboardEntry {
+                        rank,
+                        pet_id: rated.pet_id,
+                        record: pet.public_info(&policy, env.block.time).record,
+                    });
+                }
+            }
+            let answer = QueryAnswer::Leaderboard { entries };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::Tournament { tournament_id } => {
+            let tournament = match TOURNAMENTS.get(deps.storage, &tournament_id) {
+                Some(tournament) => tournament,
+                None => return Err(ContractError::TournamentNotFound { id: tournament_id })
+            };
+            let answer = QueryAnswer::Tournament { tournament: tournament.info()? };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::TournamentEntrant { tournament_id, pet_id } => {
+            let tournament = match TOURNAMENTS.get(deps.storage, &tournament_id) {
+                Some(tournament) => tournament,
+                None => return Err(ContractError::TournamentNotFound { id: tournament_id })
+            };
+            let progress = match tournament.progress(&pet_id) {
+                Some(progress) => progress,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+            let answer = QueryAnswer::TournamentEntrant { progress };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::PetProgression { pet_id } => {
+            if !load_privacy_policy(deps.storage)?.progression {
+                return Err(ContractError::PetFieldPrivate { field: "progression".to_string() });
+            }
+            let pet = match PETS.get(deps.storage, &pet_id) {
+                Some(pet) => pet,
+                None => return Err(ContractError::PetNotFound { pet_id })
+            };
+            let config = load_progression_config(deps.storage)?;
+            let answer = QueryAnswer::PetProgression {
+                next_level_xp: config.next_level_xp(pet.progression.level),
+                pet_id,
+                progression: pet.progression,
+            };
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::AllPets { .. } => {
+            // query pet contract for ids
+            let contract_data = PET721_DATA.load(deps.storage)?;
+            let msg = Pet721QueryMsg::from_query_message(msg);
+            let answer = handle_pet721_query(deps, contract_data, msg)?;
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::AllLoot { .. } => {
+            // query loot contract for ids
+            let contract_data = LOOT20_DATA.load(deps.storage)?;
+            let msg = Loot20QueryMsg::from_query_message(msg);
+            let answer = handle_loot20_query(deps, contract_data, msg)?;
+            Ok(to_binary(&answer)?)
+        }
+        QueryMsg::WithPermits { query, permit,
+            loot_permit, pet_permit } => {
+            permit_queries(deps, env, msg_to_send, query, permit, loot_permit, pet_permit)
+        }
+    }
+}
+
+pub(crate) fn permit_queries(
+    deps: Deps,
+    env: Env,
+    msg: QueryMsg,
+    query: QueryWithPermits,
+    permit: Permit,
+    _loot_permit: Permit,
+    pet_permit: Permit
+) -> Result<Binary, ContractError> {
+    let addr = secret_toolkit::permit::validate(
+        deps,
+        "revoked_permits",
+        &permit,
+        env.contract.address.to_string(),
+        None,
+    )?;
+
+    // permit validated, process query
+    match query {
+        QueryWithPermits::MyPets { .. } => {
+            // query NFT contract
+            let contract_data = PET721_DATA.load(deps.storage)?;
+            let msg = Pet721QueryMsg::from_query_message(msg);
+            let answer = handle_pet721_query(deps, contract_data, msg)?;
+
+            let mut pet_data: Vec<PetState> = vec![];
+            if let Pet721QueryAnswer::TokenList{ tokens: pet_ids} = answer.clone() {
+                // released pets keep their token but lose their state, they are left out.
+                // Pets are derived again, the upgrade curves may have changed since they were saved
+                let config = load_upgrade_config(deps.storage)?;
+                for pet_id in pet_ids {
+                    if let Some(mut pet_state) = PETS.get(deps.storage, &pet_id) {
+                        pet_state.derive(&config);
+                        pet_data.push(pet_state);
+                    }
+                }
+            }
+            let answer = QueryAnswer::Pets { pets: pet_data };
+            Ok(to_binary(&answer)?)
         }
         QueryWithPermits::MyBalance { .. } => {
             // query loot contract
@@ -606,16 +1490,14 @@ pub(crate) fn permit_queries(
         QueryWithPermits::MyQuests { } => {
             // if we have quests, then return the quest data
             let address = deps.api.addr_canonicalize(addr.as_str())?;
-            let quests: Option<Vec<Quest>> = QUESTS.get(deps.storage, &address);
-            if quests.is_none() {
-                return Err(StdError::generic_err(
-                    "Could not find any quest data".to_string(),
-                ));
-            }
+            let quests = match load_player_quests(&address, deps.storage)? {
+                Some(quests) => quests,
+                None => return Err(ContractError::QuestsNotFound)
+            };
 
             let mut quests_summary: Vec<QuestSummary> = vec![];
-            for quest in quests.unwrap() {
-                let mut outcome: Option<String> = None;
+            for quest in quests {
+                let mut outcome: Option<QuestOutcome> = None;
                 let status: String;
 
                 if quest.finished_exploring.is_some() &&
@@ -623,10 +1505,10 @@ pub(crate) fn permit_queries(
                     status = "in_progress".to_string();
                 }
                 else if quest.awaiting_claiming && quest.pet_id.is_some() {
+                    // the outcome was rolled when the quest started, so showing it cannot be
+                    // used to pick when to claim
                     status = "claimable".to_string();
-                    let outcome_ = calculate_outcome(
-                        address.clone(), quest.clone().pet_id.unwrap(), quest.clone().quest_type, deps.storage)?;
-                    outcome = Some(outcome_.to_string());
+                    outcome = quest.outcome.clone();
                 }
                 else if quest.finished_cooldown.is_some() &&
                     quest.finished_cooldown.unwrap() > Timestamp::from_seconds(env.block.time.seconds()) {
@@ -653,10 +1535,36 @@ pub(crate) fn permit_queries(
             };
             Ok(to_binary(&msg_answer)?)
         }
-        QueryWithPermits::MyQuestHistory { } => {
-            // get pet quest history
+        QueryWithPermits::QuestOdds { pet_id, quest_type } => {
+            let address = deps.api.addr_canonicalize(addr.as_str())?;
+            let check = get_quest_check(&address, pet_id, &quest_type, deps.storage)?;
+            let answer = QueryAnswer::QuestOdds {
+                odds: check.odds(),
+                check,
+            };
+            Ok(to_binary(&answer)?)
+        }
+        QueryWithPermits::MyQuestHistory { page, page_size, quest_type, outcome } => {
             let address = deps.api.addr_canonicalize(addr.as_str())?;
-            let quest_history = get_quest_history(address.clone(), deps.storage)?;
+            let filter = QuestHistoryFilter { quest_type, outcome };
+            let quest_history = get_player_quest_history(
+                &address, &filter, page.unwrap_or(0), history_page_size(page_size), deps.storage)?;
+            let msg_answer = QueryAnswer::History { quest_history };
+            Ok(to_binary(&msg_answer)?)
+        }
+        QueryWithPermits::PetQuestHistory { pet_id, page, page_size, quest_type, outcome } => {
+            // only the owner may see what the pet did
+            let owner_msg = Pet721QueryMsg::WithPermit {
+                query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                permit: pet_permit
+            };
+            if get_pet_owner(deps, owner_msg)? != addr {
+                return Err(ContractError::NotPetOwner);
+            }
+
+            let filter = QuestHistoryFilter { quest_type, outcome };
+            let quest_history = get_pet_quest_history(
+                &pet_id, &filter, page.unwrap_or(0), history_page_size(page_size), deps.storage)?;
             let msg_answer = QueryAnswer::History { quest_history };
             Ok(to_binary(&msg_answer)?)
         }
@@ -683,16 +1591,7 @@ pub(crate) fn permit_queries(
                             for battle_id in battle_ids {
                                 match BATTLES.get(deps.storage, &battle_id) {
                                     Some(battle_info) => {
-                                        // could make a ::toBattleInfoWithoutInitiator method
-                                        let battle_info_without_initiator = BattleInfoWithoutInitiator {
-                                            id: battle_info.id,
-                                            pet_id: battle_info.pet_id,
-                                            other_pet_id: battle_info.other_pet_id,
-                                            wager: battle_info.wager,
-                                            status: battle_info.status,
-                                            outcome: battle_info.outcome,
-                                        };
-                                        battles.push(battle_info_without_initiator)
+                                        battles.push(battle_info.without_initiator(&env.block))
                                     },
                                     _ => {}
                                 }
@@ -707,7 +1606,120 @@ pub(crate) fn permit_queries(
             let answer = QueryAnswer::Battles { battles };
             Ok(to_binary(&answer)?)
         }
+        QueryWithPermits::BattleDetails { battle_id } => {
+            let battle = match BATTLES.get(deps.storage, &battle_id) {
+                Some(battle) => battle,
+                None => return Err(ContractError::BattleNotFound { id: battle_id })
+            };
+
+            // only the owners of the two pets may see the battle, the nft contract may refuse
+            // owner queries for tokens that the permit does not own
+            let owns_pet = |pet_id: &String| {
+                let owner_msg = Pet721QueryMsg::WithPermit {
+                    query: OwnerOf { token_id: pet_id.clone(), include_expired: None },
+                    permit: pet_permit.clone()
+                };
+                matches!(get_pet_owner(deps, owner_msg), Ok(owner) if owner == addr)
+            };
+            if !owns_pet(&battle.pet_id) && !owns_pet(&battle.other_pet_id) {
+                return Err(ContractError::NotPetOwner);
+            }
+
+            let answer = QueryAnswer::BattleDetails {
+                log: BATTLE_LOGS.get(deps.storage, &battle_id),
+                battle: battle.without_initiator(&env.block),
+            };
+            Ok(to_binary(&answer)?)
+        }
+    }
+}
+
+/// takes the next token id, bred pets share the ids of minted ones
+fn next_pet_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let next_pet_id = PET_COUNTER.may_load(storage)?.unwrap_or(0);
+    PET_COUNTER.save(storage, &(next_pet_id + 1))?;
+    Ok(format!("PET_{}", next_pet_id))
+}
+
+/// the SetMetadata message that brings the pet's token in line with the pet, none if the
+/// token already shows it
+fn sync_pet_metadata(
+    storage: &mut dyn Storage,
+    pet: &PetState
+) -> Result<Option<CosmosMsg>, ContractError> {
+    let metadata = PetTokenMetadata::from_pet(pet, &load_privacy_policy(storage)?);
+    if !record_token_metadata(&pet.pet_id, &metadata, storage)? {
+        return Ok(None);
+    }
+
+    let nft_msg = Pet721ExecuteMsg::SetMetadata {
+        token_id: pet.pet_id.clone(),
+        public_metadata: Some(metadata.public_metadata),
+        private_metadata: Some(metadata.private_metadata),
+        padding: None,
+    };
+    let binary = Binary::from(to_binary(&nft_msg)?);
+    let contract_data = PET721_DATA.load(storage)?;
+    Ok(Some(handle_pet_nft_execute(contract_data, binary, vec![])))
+}
+
+fn history_page_size(page_size: Option<u32>) -> u32 {
+    page_size.unwrap_or(DEFAULT_QUEST_HISTORY_PAGE_SIZE).min(MAX_QUEST_HISTORY_PAGE_SIZE)
+}
+
+fn check_admin(
+    storage: &dyn Storage,
+    info: &MessageInfo
+) -> Result<Config, ContractError> {
+    let config = CONFIG.load(storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized);
     }
+    Ok(config)
+}
+
+fn load_contract_status(storage: &dyn Storage) -> Result<ContractStatusLevel, ContractError> {
+    Ok(CONTRACT_STATUS.may_load(storage)?.unwrap_or(ContractStatusLevel::NormalRun))
+}
+
+/// rejects the message if the contract status stops it, admin messages are never stopped
+/// so that a stopped contract can always be resumed
+fn check_contract_status(
+    storage: &dyn Storage,
+    msg: &ExecuteMsg
+) -> Result<(), ContractError> {
+    let status = load_contract_status(storage)?;
+    let stopped = match msg {
+        ExecuteMsg::UpdateConfig { .. } |
+        ExecuteMsg::SetLootContract { .. } |
+        ExecuteMsg::SetPetContract { .. } |
+        ExecuteMsg::ChangeAdmin { .. } |
+        ExecuteMsg::AddQuest { .. } |
+        ExecuteMsg::UpdateQuest { .. } |
+        ExecuteMsg::SetContractStatus { .. } |
+        ExecuteMsg::SetProgressionConfig { .. } |
+        ExecuteMsg::SetTieBreaker { .. } |
+        ExecuteMsg::SetPrivacyPolicy { .. } |
+        ExecuteMsg::SetUpgradeConfig { .. } |
+        ExecuteMsg::SetBreedingConfig { .. } |
+        ExecuteMsg::SetSpeciesConfig { .. } => false,
+        _ if status == ContractStatusLevel::StopAll => true,
+        ExecuteMsg::SendPetOnQuest { .. } |
+        ExecuteMsg::ClaimQuestRewards { .. } => status == ContractStatusLevel::StopQuests,
+        ExecuteMsg::BattlePet { .. } |
+        ExecuteMsg::AcceptBattle { .. } |
+        ExecuteMsg::EnterMatchmaking { .. } |
+        ExecuteMsg::CreateTournament { .. } |
+        ExecuteMsg::JoinTournament { .. } |
+        ExecuteMsg::PlayTournament { .. } |
+        ExecuteMsg::ClaimBattle { .. } => status == ContractStatusLevel::StopBattles,
+        _ => false,
+    };
+
+    if stopped {
+        return Err(ContractError::ContractStopped);
+    }
+    Ok(())
 }
 
 fn handle_pet_nft_execute(
@@ -767,13 +1779,13 @@ fn handle_loot20_execute(
 fn get_pet_owner(
     deps: Deps,
     msg: Pet721QueryMsg
-) -> Result<String, StdError> {
+) -> Result<String, ContractError> {
     let contract_data = PET721_DATA.load(deps.storage)?;
     let owner = handle_pet721_query(deps, contract_data, msg)?;
 
     match owner {
         Pet721QueryAnswer::OwnerOf { owner, .. } => Ok(owner.to_string()),
-        _ => Err(StdError::generic_err("Unexpected response type when querying owner")),
+        _ => Err(ContractError::UnexpectedResponse),
     }
 }
 
@@ -789,46 +1801,100 @@ fn get_loot_balance(
     }
 }
 
-fn upgrade_pets_stat(
-    deps_mut: DepsMut,
-    pet_id: String,
-    stat: Stat
-) -> Result<(), StdError> {
-    // get current state
-    let pet_state: Option<PetState> = PETS.get(deps_mut.storage, &pet_id.clone());
-    let mut pet_state = match pet_state {
-        Some(pet_state) => pet_state,
-        None => return Err(StdError::generic_err("Cannot find pet data"))
+fn mint_loot(
+    deps: Deps,
+    recipient: String,
+    amount: Uint128,
+    funds: Vec<Coin>
+) -> Result<CosmosMsg, StdError> {
+    let msg: Loot20ExecuteMsg = Loot20ExecuteMsg::Mint {
+        recipient,
+        amount,
+        memo: None,
+        decoys: None,
+        entropy: None,
+        padding: None,
     };
-    let current_value = pet_state.current.get_stat(stat.clone());
+    let binary= Binary::from(to_binary(&msg)?);
 
-    // update the stat and save it
-    pet_state.current.set_stat(stat.clone(), current_value + 1);
-    PETS.insert(deps_mut.storage, &pet_id, &pet_state)?;
-    Ok(())
+    let contract_data = LOOT20_DATA.load(deps.storage)?;
+    let cosmos_msg = handle_loot20_execute(contract_data, binary, funds);
+    Ok(cosmos_msg)
 }
 
-fn mint_loot(
+/// moves loot from the owner into the game contract, using the allowance given to the game
+fn escrow_loot(
+    owner: String,
     deps: Deps,
+    env: &Env,
+    amount: Uint128,
+    funds: Vec<Coin>
+) -> Result<CosmosMsg, StdError> {
+    let msg: Loot20ExecuteMsg = Loot20ExecuteMsg::TransferFrom {
+        owner,
+        recipient: env.contract.address.to_string(),
+        amount,
+        memo: None,
+        decoys: None,
+        entropy: None,
+        padding: None,
+    };
+    let binary= Binary::from(to_binary(&msg)?);
+    let contract_data = LOOT20_DATA.load(deps.storage)?;
+    let cosmos_msg = handle_loot20_execute(contract_data, binary, funds);
+    Ok(cosmos_msg)
+}
+
+/// pays loot out of the battle's escrow. Battles created before wagers were escrowed had
+/// their wagers burned, so those are minted again instead
+fn release_wager(
+    deps: Deps,
+    battle: &BattleInfo,
     recipient: String,
-    amount: u16,
+    amount: Uint128,
     funds: Vec<Coin>
 ) -> Result<CosmosMsg, StdError> {
-    let msg: Loot20ExecuteMsg = Loot20ExecuteMsg::Mint {
+    if !battle.escrowed {
+        return mint_loot(deps, recipient, amount, funds);
+    }
+    transfer_loot(deps, recipient, amount, funds)
+}
+
+/// pays loot the game contract holds
+fn transfer_loot(
+    deps: Deps,
+    recipient: String,
+    amount: Uint128,
+    funds: Vec<Coin>
+) -> Result<CosmosMsg, StdError> {
+    let msg: Loot20ExecuteMsg = Loot20ExecuteMsg::Transfer {
         recipient,
-        amount: Uint128::from(amount),
+        amount,
         memo: None,
         decoys: None,
         entropy: None,
         padding: None,
     };
     let binary= Binary::from(to_binary(&msg)?);
-
     let contract_data = LOOT20_DATA.load(deps.storage)?;
     let cosmos_msg = handle_loot20_execute(contract_data, binary, funds);
     Ok(cosmos_msg)
 }
 
+/// removes a battle that was never fought and returns the wager to its initiator
+fn refund_battle(
+    deps: DepsMut,
+    battle: &BattleInfo,
+    funds: Vec<Coin>
+) -> Result<CosmosMsg, ContractError> {
+    BATTLES.remove(deps.storage, &battle.id)?;
+    remove_battle_for_pet(battle.pet_id.clone(), battle.id, deps.storage)?;
+    remove_battle_for_pet(battle.other_pet_id.clone(), battle.id, deps.storage)?;
+
+    let initiator_address = deps.api.addr_humanize(&battle.initiator_address)?.to_string();
+    Ok(release_wager(deps.as_ref(), battle, initiator_address, Uint128::from(battle.wager), funds)?)
+}
+
 fn burn_loot(
     owner: String,
     deps: Deps,
@@ -852,62 +1918,74 @@ fn burn_loot(
 fn battle_pets(
     pet_id: String,
     other_pet_id: String,
-    storage: & dyn Storage
-) -> Result<bool, StdError> {
+    storage: & dyn Storage,
+    rng: &mut SmallRng
+) -> Result<(BattleOutcome, BattleLog), ContractError> {
     let pet = match PETS.get(storage, &pet_id) {
         Some(pet) => pet,
-        None => {
-            return Err(StdError::generic_err(
-                "Could not find any quest data".to_string(),
-            ));
-        }
+        None => return Err(ContractError::PetNotFound { pet_id })
     };
 
     let other_pet = match PETS.get(storage, &other_pet_id) {
         Some(pet) => pet,
-        None => {
-            return Err(StdError::generic_err(
-                "Could not find any quest data".to_string(),
-            ));
-        }
+        None => return Err(ContractError::PetNotFound { pet_id: other_pet_id })
     };
 
-    Ok(
-        {
-            let mut pet_wins = 0;
-            let mut other_wins = 0;
-
-            if pet.current.strength > other_pet.current.strength {
-                pet_wins += 1;
-            } else if pet.current.strength < other_pet.current.strength {
-                other_wins += 1;
-            }
-
-            if pet.current.stamina > other_pet.current.stamina {
-                pet_wins += 1;
-            } else if pet.current.stamina < other_pet.current.stamina {
-                other_wins += 1;
-            }
+    let tie_breaker = load_tie_breaker(storage)?;
+    Ok(simulate_battle(&pet_id, &pet.current, &other_pet_id, &other_pet.current, &tie_breaker, rng))
+}
 
-            if pet.current.intelligence > other_pet.current.intelligence {
-                pet_wins += 1;
-            } else if pet.current.intelligence < other_pet.current.intelligence {
-                other_wins += 1;
-            }
+/// fights the bracket of the tournament, updating the ratings of the pets, and pays the prizes
+/// to the players that registered the pets. Tournaments with too few entrants are cancelled
+/// and their entry fees refunded
+fn play_tournament(
+    deps: DepsMut,
+    tournament: &mut Tournament,
+    rng: &mut SmallRng
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    for entrant in &tournament.entrants {
+        TOURNAMENT_PETS.remove(deps.storage, &entrant.pet_id)?;
+    }
 
-            if pet.current.health > other_pet.current.health {
-                pet_wins += 1;
-            } else if pet.current.health < other_pet.current.health {
-                other_wins += 1;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if tournament.entrants.len() < MIN_TOURNAMENT_ENTRANTS as usize {
+        tournament.status = TournamentStatus::Cancelled;
+        if tournament.entry_fee > 0 {
+            for entrant in &tournament.entrants {
+                let recipient = deps.api.addr_humanize(&entrant.owner)?.to_string();
+                messages.push(transfer_loot(deps.as_ref(), recipient, Uint128::from(tournament.entry_fee), vec![])?);
             }
+        }
+        return Ok(messages);
+    }
 
-            if pet.current.luck > other_pet.current.luck {
-                pet_wins += 1;
-            } else if pet.current.luck < other_pet.current.luck {
-                other_wins += 1;
-            }
+    let mut entrants = vec![];
+    for entrant in &tournament.entrants {
+        let pet = match PETS.get(deps.storage, &entrant.pet_id) {
+            Some(pet) => pet,
+            None => return Err(ContractError::PetNotFound { pet_id: entrant.pet_id.clone() })
+        };
+        entrants.push((entrant.pet_id.clone(), pet.current));
+    }
+    let tie_breaker = load_tie_breaker(deps.storage)?;
+    let matches = resolve_bracket(entrants, &tie_breaker, rng);
+    for m in &matches {
+        if let (Some(other_pet_id), Some(outcome)) = (&m.other_pet_id, &m.outcome) {
+            update_battle_records(m.pet_id.clone(), other_pet_id.clone(), outcome, deps.storage)?;
+        }
+    }
 
-            pet_wins > other_wins
+    let placements = place_entrants(
+        &matches, tournament.entrants.len() as u32, tournament.prize_pool()?, &tournament.prize_split);
+    for placement in placements.iter().filter(|placement| placement.prize > 0) {
+        if let Some(entrant) = tournament.entrants.iter().find(|entrant| entrant.pet_id == placement.pet_id) {
+            let recipient = deps.api.addr_humanize(&entrant.owner)?.to_string();
+            messages.push(transfer_loot(deps.as_ref(), recipient, Uint128::from(placement.prize), vec![])?);
         }
-    )
+    }
+
+    tournament.status = TournamentStatus::Finished;
+    tournament.matches = matches;
+    tournament.placements = placements;
+    Ok(messages)
 }
\ No newline at end of file
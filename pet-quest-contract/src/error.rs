@@ -0,0 +1,85 @@
+use std::fmt;
+use cosmwasm_std::{to_vec, StdError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// errors returned by every execute and query of the game contract. Each error is reported
+/// as json tagged with a stable `code`, e.g. `{"code":"battle_not_found","id":4}`, so that
+/// clients can match on the code instead of the message text
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ContractError {
+    /// errors raised by cosmwasm, secret-toolkit or the other contracts
+    Std { message: String },
+    Unauthorized,
+    ContractStopped,
+    InvalidMaxStats { min: u16, max: u16 },
+    NotPetOwner,
+    PetNotFound { pet_id: String },
+    PetOnQuest,
+    PetInBattle { pet_id: String },
+    PetFieldPrivate { field: String },
+    TooManyPets { max: u32 },
+    PetExhausted { energy: u16, needed: u16 },
+    QuestsNotFound,
+    QuestNotFound,
+    QuestNotAvailable,
+    QuestNotClaimable,
+    QuestHasNoPet,
+    QuestAlreadyExists { id: String },
+    InvalidQuestDefinition { reason: String },
+    StatMaxed,
+    InsufficientStatPoints { needed: u16, have: u16 },
+    InvalidProgressionConfig { reason: String },
+    InsufficientLoot { needed: u64, have: u64 },
+    InvalidUpgrade { reason: String },
+    InvalidUpgradeConfig { reason: String },
+    CannotBreedSelf,
+    BreedingCooldown { pet_id: String, seconds_left: u64 },
+    InvalidBreedingConfig { reason: String },
+    InvalidSpeciesConfig { reason: String },
+    SupplyExhausted,
+    InvalidPetName { reason: String },
+    BattleNotFound { id: u64 },
+    BattleNotClaimable,
+    BattleNotPending { id: u64 },
+    BattleAlreadyPending { id: u64 },
+    CannotBattleSelf,
+    WagerTooLow { min: u64 },
+    TooManyPendingBattles { pet_id: String, max: u32 },
+    AlreadyInMatchmaking { pet_id: String },
+    NotInMatchmaking { pet_id: String },
+    TournamentNotFound { id: u64 },
+    TournamentClosed { id: u64 },
+    TournamentFull { id: u64, max: u32 },
+    TournamentRegistrationOpen { id: u64 },
+    NotInTournament { pet_id: String },
+    InvalidTournament { reason: String },
+    BattleExpired { id: u64 },
+    BattleNotExpired { id: u64 },
+    InvalidExpiration,
+    InvalidEntropyCommitment,
+    EntropyNotCommitted,
+    EntropyNotRevealed,
+    EntropyRevealedTooEarly,
+    EntropyMismatch,
+    UnexpectedResponse,
+    MigrationContractMismatch { contract: String },
+    UnsupportedStorageVersion { version: u16 },
+    UnknownLegacyValue { value: String },
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match to_vec(self) {
+            Ok(json) => write!(f, "{}", String::from_utf8_lossy(&json)),
+            Err(_) => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl From<StdError> for ContractError {
+    fn from(err: StdError) -> Self {
+        ContractError::Std { message: err.to_string() }
+    }
+}
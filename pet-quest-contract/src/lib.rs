@@ -1,8 +1,30 @@
 #![deny(clippy::float_arithmetic)]
 
 pub mod contract;
+pub mod error;
+mod migrate;
 pub mod msg;
 pub mod state;
+pub mod battle;
+pub mod tournament;
+pub mod species;
 mod randomness;
 mod loot20;
 mod pet721;
+#[cfg(any(test, feature = "multitest"))]
+pub mod multitest;
+mod unittest_admin;
+mod unittest_battles;
+mod unittest_breeding;
+mod unittest_energy;
+mod unittest_flows;
+mod unittest_leaderboard;
+mod unittest_matchmaking;
+mod unittest_metadata;
+mod unittest_migrate;
+mod unittest_progression;
+mod unittest_queries;
+mod unittest_quests;
+mod unittest_species;
+mod unittest_tournaments;
+mod unittest_upgrades;
@@ -58,6 +58,23 @@ pub(crate) enum Loot20ExecuteMsg {
         entropy: Option<Binary>,
         padding: Option<String>,
     },
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+        memo: Option<String>,
+        decoys: Option<Vec<Addr>>,
+        entropy: Option<Binary>,
+        padding: Option<String>,
+    },
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+        memo: Option<String>,
+        decoys: Option<Vec<Addr>>,
+        entropy: Option<Binary>,
+        padding: Option<String>,
+    },
     BurnFrom {
         owner: String,
         amount: Uint128,
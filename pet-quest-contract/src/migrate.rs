@@ -0,0 +1,400 @@
+use cosmwasm_std::{Env, StdResult, Storage};
+use crate::error::ContractError;
+use crate::state::{ContractInfo, CONTRACT_INFO};
+
+pub(crate) static CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub(crate) static CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// bumped whenever a stored layout changes, together with a new migration step below
+pub(crate) static STORAGE_VERSION: u16 = 4;
+
+/// records the contract name, version and the storage version written by this code
+pub(crate) fn set_contract_info(storage: &mut dyn Storage) -> StdResult<()> {
+    let info = ContractInfo {
+        contract: CONTRACT_NAME.to_string(),
+        version: CONTRACT_VERSION.to_string(),
+        storage_version: STORAGE_VERSION,
+    };
+    CONTRACT_INFO.save(storage, &info)
+}
+
+/// runs every migration step from the stored storage version up to the current one and
+/// returns the version that was migrated from. Contracts instantiated before versioning was
+/// added have no contract info and are treated as storage version 0
+pub(crate) fn migrate_storage(
+    storage: &mut dyn Storage,
+    env: &Env
+) -> Result<u16, ContractError> {
+    let from_version = match CONTRACT_INFO.may_load(storage)? {
+        Some(info) => {
+            if info.contract != CONTRACT_NAME {
+                return Err(ContractError::MigrationContractMismatch { contract: info.contract });
+            }
+            info.storage_version
+        }
+        None => 0
+    };
+
+    let mut version = from_version;
+    while version < STORAGE_VERSION {
+        match version {
+            0 => v0::migrate_to_v1(storage, env)?,
+            1 => v1::migrate_to_v2(storage)?,
+            2 => v2::migrate_to_v3(storage)?,
+            3 => v3::migrate_to_v4(storage)?,
+            _ => return Err(ContractError::UnsupportedStorageVersion { version })
+        }
+        version += 1;
+    }
+    if version > STORAGE_VERSION {
+        return Err(ContractError::UnsupportedStorageVersion { version });
+    }
+
+    set_contract_info(storage)?;
+    Ok(from_version)
+}
+
+/// storage version 4 moved the quest history of every player out of a single growing vector
+/// into append stores, that also index the history by pet
+pub(crate) mod v3 {
+    use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+    use secret_toolkit::serialization::Json;
+    use secret_toolkit::storage::Keymap;
+    use crate::error::ContractError;
+    use crate::state::{insert_quest_history, QuestHistory, QUEST_HISTORY_KEY};
+
+    pub(crate) static QUEST_HISTORY: Keymap<CanonicalAddr, Vec<QuestHistory>, Json> = Keymap::new(QUEST_HISTORY_KEY);
+
+    /// appends every history oldest first. Quests of different players are not interleaved by
+    /// claim time, the log order only matters within a player's or pet's history
+    pub(super) fn migrate_to_v4(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        let histories = QUEST_HISTORY.iter(storage)?.collect::<StdResult<Vec<_>>>()?;
+        for (addr, history) in histories {
+            for entry in history {
+                insert_quest_history(addr.clone(), entry, storage)?;
+            }
+            QUEST_HISTORY.remove(storage, &addr)?;
+        }
+        Ok(())
+    }
+}
+
+/// storage version 3 replaced the battle outcome, which only said whether the challenger won,
+/// with an outcome that can be a draw
+pub(crate) mod v2 {
+    use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+    use schemars::JsonSchema;
+    use secret_toolkit::serialization::Json;
+    use secret_toolkit::storage::Keymap;
+    use serde::{Deserialize, Serialize};
+    use crate::battle::BattleOutcome;
+    use crate::error::ContractError;
+    use crate::state;
+    use crate::state::{BattleStatus, Expiration, BATTLE_KEY};
+
+    pub(crate) static BATTLES: Keymap<u64, BattleInfo, Json> = Keymap::new(BATTLE_KEY);
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+    pub(crate) struct BattleInfo {
+        pub(crate) id: u64,
+        pub(crate) initiator_address: CanonicalAddr,
+        pub(crate) pet_id: String,
+        pub(crate) other_pet_id: String,
+        pub(crate) wager: u64,
+        pub(crate) status: BattleStatus,
+        // true if pet_id wins
+        pub(crate) outcome: Option<bool>,
+        #[serde(default)]
+        pub(crate) escrowed: bool,
+        #[serde(default)]
+        pub(crate) expiration: Expiration
+    }
+
+    impl BattleInfo {
+        pub(crate) fn migrate(self) -> state::BattleInfo {
+            state::BattleInfo {
+                id: self.id,
+                initiator_address: self.initiator_address,
+                pet_id: self.pet_id,
+                other_pet_id: self.other_pet_id,
+                wager: self.wager,
+                status: self.status,
+                outcome: self.outcome.map(BattleOutcome::from_challenger_wins),
+                escrowed: self.escrowed,
+                expiration: self.expiration,
+            }
+        }
+    }
+
+    /// rewrites every battle in place, the store keeps its namespace
+    pub(super) fn migrate_to_v3(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        let battles = BATTLES.iter(storage)?.collect::<StdResult<Vec<_>>>()?;
+        for (battle_id, battle) in battles {
+            state::BATTLES.insert(storage, &battle_id, &battle.migrate())?;
+        }
+        Ok(())
+    }
+}
+
+/// storage version 2 moved the quests into an admin managed catalogue, which starts with the
+/// four trials that used to be hard-coded. Player quests already use the catalogue ids
+pub(crate) mod v1 {
+    use cosmwasm_std::Storage;
+    use schemars::JsonSchema;
+    use secret_toolkit::serialization::Json;
+    use secret_toolkit::storage::Keymap;
+    use serde::{Deserialize, Serialize};
+    use crate::error::ContractError;
+    use crate::state::{QuestRange, Stat, StatWeight, QUEST_CATALOGUE_KEY};
+
+    pub(crate) static QUEST_CATALOGUE: Keymap<String, QuestDefinition, Json> = Keymap::new(QUEST_CATALOGUE_KEY);
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+    pub(crate) struct QuestDefinition {
+        pub(crate) id: String,
+        pub(crate) name: String,
+        pub(crate) stat_weights: Vec<StatWeight>,
+        pub(crate) explore_seconds: u64,
+        pub(crate) cooldown_seconds: u64,
+        pub(crate) base_loot: QuestRange,
+        pub(crate) difficulty: QuestRange,
+        pub(crate) growth_on_pass: QuestRange,
+        pub(crate) growth_on_exceptional_pass: QuestRange,
+        pub(crate) enabled: bool
+    }
+
+    impl QuestDefinition {
+        /// the trials as they were hard-coded before the catalogue
+        fn trial(id: &str, name: &str, stat: Stat) -> Self {
+            QuestDefinition {
+                id: id.to_string(),
+                name: name.to_string(),
+                stat_weights: vec![StatWeight { stat, weight: 1 }],
+                explore_seconds: 30,
+                cooldown_seconds: 60,
+                base_loot: QuestRange::new(1, 5),
+                difficulty: QuestRange::new(1, 3),
+                growth_on_pass: QuestRange::new(0, 1),
+                growth_on_exceptional_pass: QuestRange::new(1, 2),
+                enabled: true,
+            }
+        }
+    }
+
+    pub(super) fn migrate_to_v2(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        if QUEST_CATALOGUE.get_len(storage)? > 0 {
+            return Ok(());
+        }
+        let trials = [
+            QuestDefinition::trial("trial_of_resilience", "Trial Of Resilience", Stat::Health),
+            QuestDefinition::trial("trial_of_endurance", "Trial Of Endurance", Stat::Stamina),
+            QuestDefinition::trial("trial_of_titans", "Trial Of Titans", Stat::Strength),
+            QuestDefinition::trial("trial_of_wisdom", "Trial Of Wisdom", Stat::Intelligence),
+        ];
+        for definition in trials {
+            QUEST_CATALOGUE.insert(storage, &definition.id, &definition)?;
+        }
+        Ok(())
+    }
+}
+
+/// the layouts written before storage was versioned. These were bincode serialized and kept
+/// the quest types, quest outcomes and battle statuses as their display strings
+pub(crate) mod v0 {
+    use cosmwasm_std::{CanonicalAddr, Env, StdResult, Storage, Timestamp};
+    use schemars::JsonSchema;
+    use secret_toolkit::storage::{Item, Keymap};
+    use serde::{Deserialize, Serialize};
+    use crate::error::ContractError;
+    use crate::randomness::init_prng_seed;
+    use crate::state;
+    use crate::state::{BattleStatus, Config, PetStats, QuestOutcome, PRNG_SEED};
+
+    pub(crate) static CONFIG: Item<Config> = Item::new(b"config");
+    pub(crate) static PETS: Keymap<String, PetState> = Keymap::new(b"pets");
+    pub(crate) static QUESTS: Keymap<CanonicalAddr, Vec<Quest>> = Keymap::new(b"quests");
+    pub(crate) static QUEST_HISTORY: Keymap<CanonicalAddr, Vec<QuestHistory>> = Keymap::new(b"quest_history");
+    pub(crate) static BATTLES: Keymap<u64, BattleInfo> = Keymap::new(b"battles");
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+    pub(crate) struct PetState {
+        pub(crate) pet_id: String,
+        pub(crate) on_quest: Option<Quest>,
+        pub(crate) current: PetStats,
+        pub(crate) max: PetStats,
+        pub(crate) upgrade_costs: PetStats
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+    pub(crate) struct Quest {
+        pub(crate) pet_id: Option<String>,
+        pub(crate) quest_type: String,
+        pub(crate) awaiting_claiming: bool,
+        pub(crate) finished_exploring: Option<Timestamp>,
+        pub(crate) finished_cooldown: Option<Timestamp>,
+        pub(crate) base_loot: u16,
+        pub(crate) difficulty: u16,
+        pub(crate) difficulty_increment: u16
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+    pub(crate) struct QuestHistory {
+        pub(crate) pet_id: String,
+        pub(crate) quest_type: String,
+        pub(crate) time_started: Timestamp,
+        pub(crate) time_ended: Timestamp,
+        pub(crate) loot_collected: u16,
+        pub(crate) outcome: String
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+    pub(crate) struct BattleInfo {
+        pub(crate) id: u64,
+        pub(crate) initiator_address: CanonicalAddr,
+        pub(crate) pet_id: String,
+        pub(crate) other_pet_id: String,
+        pub(crate) wager: u64,
+        pub(crate) status: String,
+        pub(crate) outcome: Option<bool>
+    }
+
+    fn quest_type(value: &str) -> Result<String, ContractError> {
+        let quest_type = match value {
+            "Trial Of Resilience" => "trial_of_resilience",
+            "Trial Of Titans" => "trial_of_titans",
+            "Trial Of Endurance" => "trial_of_endurance",
+            "Trial Of Wisdom" => "trial_of_wisdom",
+            _ => return Err(ContractError::UnknownLegacyValue { value: value.to_string() })
+        };
+        Ok(quest_type.to_string())
+    }
+
+    fn quest_outcome(value: &str) -> Result<QuestOutcome, ContractError> {
+        match value {
+            "Fail" => Ok(QuestOutcome::Fail),
+            "Pass" => Ok(QuestOutcome::Pass),
+            "Exceptional Pass" => Ok(QuestOutcome::ExceptionalPass),
+            _ => Err(ContractError::UnknownLegacyValue { value: value.to_string() })
+        }
+    }
+
+    fn battle_status(value: &str) -> Result<BattleStatus, ContractError> {
+        match value {
+            "pending" => Ok(BattleStatus::Pending),
+            "accepted" => Ok(BattleStatus::Accepted),
+            _ => Err(ContractError::UnknownLegacyValue { value: value.to_string() })
+        }
+    }
+
+    impl Quest {
+        pub(crate) fn migrate(self) -> Result<state::Quest, ContractError> {
+            Ok(state::Quest {
+                pet_id: self.pet_id,
+                quest_type: quest_type(&self.quest_type)?,
+                awaiting_claiming: self.awaiting_claiming,
+                started_exploring: None,
+                finished_exploring: self.finished_exploring,
+                finished_cooldown: self.finished_cooldown,
+                base_loot: self.base_loot,
+                difficulty: self.difficulty,
+                difficulty_increment: self.difficulty_increment,
+                outcome: None,
+            })
+        }
+    }
+
+    impl PetState {
+        pub(crate) fn migrate(self) -> Result<state::PetState, ContractError> {
+            Ok(state::PetState {
+                pet_id: self.pet_id,
+                on_quest: self.on_quest.map(Quest::migrate).transpose()?,
+                current: self.current,
+                max: self.max,
+                upgrade_costs: self.upgrade_costs.into(),
+                power: 0,
+                battle_rating: 0,
+                upgrades: state::PetUpgrades::default(),
+                progression: state::PetProgression::default(),
+                energy: state::PetEnergy::default(),
+                record: state::BattleRecord::default(),
+                lineage: state::PetLineage::default(),
+                traits: None,
+                name: None,
+            })
+        }
+    }
+
+    impl QuestHistory {
+        pub(crate) fn migrate(self) -> Result<state::QuestHistory, ContractError> {
+            Ok(state::QuestHistory {
+                pet_id: self.pet_id,
+                quest_type: quest_type(&self.quest_type)?,
+                time_started: self.time_started,
+                time_ended: self.time_ended,
+                loot_collected: self.loot_collected,
+                outcome: quest_outcome(&self.outcome)?,
+            })
+        }
+    }
+
+    impl BattleInfo {
+        /// battles are moved to the layout of storage version 2, which version 3 migrates
+        pub(crate) fn migrate(self) -> Result<super::v2::BattleInfo, ContractError> {
+            Ok(super::v2::BattleInfo {
+                id: self.id,
+                initiator_address: self.initiator_address,
+                pet_id: self.pet_id,
+                other_pet_id: self.other_pet_id,
+                wager: self.wager,
+                status: battle_status(&self.status)?,
+                outcome: self.outcome,
+                escrowed: false,
+                expiration: state::Expiration::Never,
+            })
+        }
+    }
+
+    /// moves every entry of the bincode stores into the json stores, converting the legacy
+    /// strings into enums, and seeds the prng state which did not exist yet
+    pub(super) fn migrate_to_v1(storage: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+        let config = CONFIG.load(storage)?;
+        state::CONFIG.save(storage, &config)?;
+        CONFIG.remove(storage);
+        if PRNG_SEED.may_load(storage)?.is_none() {
+            init_prng_seed(storage, env, config.entropy.as_bytes())?;
+        }
+
+        let pets = PETS.iter(storage)?.collect::<StdResult<Vec<_>>>()?;
+        for (pet_id, pet) in pets {
+            state::save_pet(&mut pet.migrate()?, storage)?;
+            PETS.remove(storage, &pet_id)?;
+        }
+
+        let quests = QUESTS.iter(storage)?.collect::<StdResult<Vec<_>>>()?;
+        for (addr, addr_quests) in quests {
+            let addr_quests = addr_quests
+                .into_iter()
+                .map(Quest::migrate)
+                .collect::<Result<Vec<_>, _>>()?;
+            state::QUESTS.insert(storage, &addr, &addr_quests)?;
+            QUESTS.remove(storage, &addr)?;
+        }
+
+        let histories = QUEST_HISTORY.iter(storage)?.collect::<StdResult<Vec<_>>>()?;
+        for (addr, history) in histories {
+            let history = history
+                .into_iter()
+                .map(QuestHistory::migrate)
+                .collect::<Result<Vec<_>, _>>()?;
+            super::v3::QUEST_HISTORY.insert(storage, &addr, &history)?;
+            QUEST_HISTORY.remove(storage, &addr)?;
+        }
+
+        let battles = BATTLES.iter(storage)?.collect::<StdResult<Vec<_>>>()?;
+        for (battle_id, battle) in battles {
+            super::v2::BATTLES.insert(storage, &battle_id, &battle.migrate()?)?;
+            BATTLES.remove(storage, &battle_id)?;
+        }
+
+        Ok(())
+    }
+}
@@ -3,20 +3,25 @@ use schemars::JsonSchema;
 use secret_toolkit::permit::Permit;
 use secret_toolkit::snip721::ViewerInfo;
 use serde::{Deserialize, Serialize};
-use crate::state::{BattleInfoWithoutInitiator, ContractData, PetState, QuestHistory, QuestSummary};
+use crate::battle::{BattleLog, TieBreaker};
+use crate::state::{BattleInfoWithoutInitiator, ContractData, LeaderboardEntry, ContractStatusLevel, Expiration, PetProgression, PetState, ProgressionConfig, QuestCheck, QuestDefinition, QuestHistory, QuestOdds, QuestOutcome, QuestSummary, Stat, TournamentInfo, EntrantProgress, PetPrivacyPolicy, PublicPetInfo, UpgradeConfig, BreedingConfig, PetLineage, RaritySupply};
+use crate::species::SpeciesConfig;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
-pub(crate) struct InstantiateMsg {
-    pub(crate) admin: Addr,
-    pub(crate) max_stats: u16,
-    pub(crate) entropy: String,
-    pub(crate) loot_contract: ContractData,
-    pub(crate) pet_contract: ContractData
+pub struct InstantiateMsg {
+    pub admin: Addr,
+    pub max_stats: u16,
+    pub entropy: String,
+    pub loot_contract: ContractData,
+    pub pet_contract: ContractData
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MigrateMsg { }
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub(crate) enum ExecuteMsg {
+pub enum ExecuteMsg {
     MintPet {
         recipient: String,
         amount: Uint128,
@@ -25,39 +30,91 @@ pub(crate) enum ExecuteMsg {
         entropy: Option<Binary>,
         padding: Option<String>,
     },
+    /// commits to entropy that is revealed by a later action, only required on chains
+    /// without `env.block.random`
+    CommitEntropy {
+        commitment: Binary
+    },
     ReleasePet {
         pet_id: String,
         permit: Permit,
         loot_permit: Permit,
         pet_permit: Permit
     },
+    /// raises several stats by the given points, paid with a single burn
     UpgradePetStats {
         pet_id: String,
-        stat: String,
+        upgrades: Vec<(Stat, u16)>,
+        permit: Permit,
+        loot_permit: Permit,
+        pet_permit: Permit
+    },
+    /// resets the pet to its base stats for a fee, refunding part of the loot spent on
+    /// upgrades and giving back the spent stat points
+    RespecPet {
+        pet_id: String,
+        permit: Permit,
+        loot_permit: Permit,
+        pet_permit: Permit
+    },
+    /// spends stat points from level ups, without any loot cost
+    SpendStatPoints {
+        pet_id: String,
+        stat: Stat,
+        points: u16,
+        pet_permit: Permit
+    },
+    /// mints a child of two of the sender's pets for the breeding fee, both parents have to
+    /// wait out the breeding cooldown before they breed again
+    BreedPets {
+        parent_a: String,
+        parent_b: String,
+        // reveals a committed secret on chains without `env.block.random`
+        entropy: Option<Binary>,
         permit: Permit,
         loot_permit: Permit,
         pet_permit: Permit
     },
+    /// names the pet on its token, none clears the name
+    RenamePet {
+        pet_id: String,
+        name: Option<String>,
+        pet_permit: Permit
+    },
+    /// rewrites the token metadata of the pets whose tokens no longer match them, e.g. pets
+    /// minted before the metadata was kept in sync or after the privacy policy changed
+    ResyncPets {
+        pet_ids: Vec<String>
+    },
     SendPetOnQuest {
         pet_id: String,
         quest_type: String,
+        // reveals a committed secret on chains without `env.block.random`
+        entropy: Option<Binary>,
         permit: Permit,
         loot_permit: Permit,
         pet_permit: Permit
     },
     ClaimQuestRewards {
-        quest_type: String
+        quest_type: String,
+        entropy: Option<Binary>
     },
+    /// challenges another pet, the challenge expires after a day unless an expiration is given,
+    /// which must come within three days
     BattlePet {
         pet_id: String,
         other_pet_id: String,
         wager: u64,
+        expiration: Option<Expiration>,
         permit: Permit,
         loot_permit: Permit,
         pet_permit: Permit
     },
+    /// accepting fights the battle, the rounds are rolled with the contract rng
     AcceptBattle {
         battle_id: u64,
+        // reveals a committed secret on chains without `env.block.random`
+        entropy: Option<Binary>,
         permit: Permit,
         loot_permit: Permit,
         pet_permit: Permit
@@ -76,12 +133,160 @@ pub(crate) enum ExecuteMsg {
         battle_id: u64,
         pet_id: String,
         pet_permit: Permit
+    },
+    /// refunds the initiator of an expired challenge, anyone can send it
+    ReclaimExpiredBattle {
+        battle_id: u64
+    },
+    /// fights the longest waiting pet with the same wager and a close rating right away,
+    /// or queues the pet until a compatible pet enters
+    EnterMatchmaking {
+        pet_id: String,
+        wager: u64,
+        // reveals a committed secret on chains without `env.block.random`
+        entropy: Option<Binary>,
+        permit: Permit,
+        loot_permit: Permit,
+        pet_permit: Permit
+    },
+    /// takes a queued pet out of matchmaking and refunds its wager
+    LeaveMatchmaking {
+        pet_id: String,
+        pet_permit: Permit
+    },
+    /// opens a single elimination tournament any player can register a pet for by paying the
+    /// entry fee, the fees make up the prize pool
+    CreateTournament {
+        name: String,
+        entry_fee: u64,
+        max_entrants: u32,
+        // defaults to an hour from now, must end within a week
+        registration: Option<Expiration>,
+        // percent of the prize pool per place starting with the champion, defaults to 60/30/10
+        prize_split: Option<Vec<u16>>
+    },
+    /// registers the pet, the bracket is played as soon as the tournament is full
+    JoinTournament {
+        tournament_id: u64,
+        pet_id: String,
+        // reveals a committed secret on chains without `env.block.random`
+        entropy: Option<Binary>,
+        permit: Permit,
+        loot_permit: Permit,
+        pet_permit: Permit
+    },
+    /// takes a pet out of a tournament that has not been played and refunds its entry fee
+    LeaveTournament {
+        tournament_id: u64,
+        pet_id: String,
+        pet_permit: Permit
+    },
+    /// plays the bracket of a tournament whose registration ended, anyone can send it.
+    /// Tournaments with fewer than two pets are cancelled and refunded instead
+    PlayTournament {
+        tournament_id: u64,
+        entropy: Option<Binary>
+    },
+
+    // admin messages
+    /// new entropy is mixed into the prng state rather than replacing it
+    UpdateConfig {
+        max_stats: Option<u16>,
+        entropy: Option<String>
+    },
+    SetLootContract {
+        contract: ContractData
+    },
+    SetPetContract {
+        contract: ContractData
+    },
+    ChangeAdmin {
+        address: String
+    },
+    /// adds a quest to the catalogue, players receive it the next time their quests are used
+    AddQuest {
+        quest: QuestDefinition
+    },
+    /// replaces the definition of an existing quest, quests already in progress keep the
+    /// loot and difficulty they rolled
+    UpdateQuest {
+        quest: QuestDefinition
+    },
+    SetContractStatus {
+        level: ContractStatusLevel
+    },
+    /// applies to quests claimed from now on, pets keep the levels they already reached
+    SetProgressionConfig {
+        config: ProgressionConfig
+    },
+    /// decides battles fought from now on that end with both pets on the same share of hp
+    SetTieBreaker {
+        tie_breaker: TieBreaker
+    },
+    /// tokens follow the new policy once they are next synced, see `ResyncPets`
+    SetPrivacyPolicy {
+        policy: PetPrivacyPolicy
+    },
+    /// the new curves apply to the next upgrades, loot already spent is refunded by the new
+    /// refund share
+    SetUpgradeConfig {
+        config: UpgradeConfig
+    },
+    /// cooldowns already running are measured against the new cooldown
+    SetBreedingConfig {
+        config: BreedingConfig
+    },
+    /// applies to pets minted or bred from now on, pets already counted against a supply cap
+    /// keep counting when the caps change
+    SetSpeciesConfig {
+        config: SpeciesConfig
     }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub(crate) enum QueryMsg {
+pub enum QueryMsg {
+    ContractInfo { },
+    Config { },
+    QuestCatalogue { },
+    PetProgression {
+        pet_id: String
+    },
+    PetEnergy {
+        pet_id: String
+    },
+    /// the parts of the pet the privacy policy makes public
+    PetInfo {
+        pet_id: String
+    },
+    /// the public parts of several pets, pets that do not exist are left out
+    PetsInfo {
+        pet_ids: Vec<String>
+    },
+    /// the pets minted with each rarity and the caps on them
+    RaritySupply { },
+    /// the parents and generation of the pet and how long until it can breed again
+    PetLineage {
+        pet_id: String
+    },
+    /// the pending challenges against the pet, so that its owner can find them
+    PetBattles {
+        pet_id: String
+    },
+    /// pets that have battled ordered by elo rating, paged by the last pet of the previous page
+    Leaderboard {
+        limit: Option<u32>,
+        start_after: Option<String>
+    },
+    /// the bracket, placements and prizes of the tournament
+    Tournament {
+        tournament_id: u64
+    },
+    /// the matches a pet fought in the tournament and where it placed
+    TournamentEntrant {
+        tournament_id: u64,
+        pet_id: String
+    },
     AllPets {
         start_after: Option<String>,
         limit: Option<u32>
@@ -101,7 +306,7 @@ pub(crate) enum QueryMsg {
 /// queries using permits
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub(crate) enum QueryWithPermits {
+pub enum QueryWithPermits {
     MyPets {
         owner: String,
         viewer: Option<ViewerInfo>,
@@ -115,18 +320,92 @@ pub(crate) enum QueryWithPermits {
         start_after: Option<String>
     },
     MyQuests { },
-    MyQuestHistory { },
+    /// the player's claimed quests latest first, optionally of one quest type or outcome
+    MyQuestHistory {
+        page: Option<u32>,
+        page_size: Option<u32>,
+        quest_type: Option<String>,
+        outcome: Option<QuestOutcome>
+    },
+    /// the claimed quests of the pet latest first, including those of its previous owners
+    PetQuestHistory {
+        pet_id: String,
+        page: Option<u32>,
+        page_size: Option<u32>,
+        quest_type: Option<String>,
+        outcome: Option<QuestOutcome>
+    },
+    /// the chance of every outcome if the pet was sent on one of the player's quests now
+    QuestOdds {
+        pet_id: String,
+        quest_type: String
+    },
     MyBattles {
         pet_permit: Permit
+    },
+    /// a battle with its round by round log, only for the owners of the two pets
+    BattleDetails {
+        battle_id: u64
     }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub(crate) enum QueryAnswer {
+pub enum QueryAnswer {
+    QuestCatalogue { quests: Vec<QuestDefinition> },
+    ContractInfo {
+        contract: String,
+        version: String,
+        storage_version: u16
+    },
+    Config {
+        admin: Addr,
+        max_stats: u16,
+        status: ContractStatusLevel,
+        loot_contract: ContractData,
+        pet_contract: ContractData,
+        progression: ProgressionConfig,
+        tie_breaker: TieBreaker,
+        privacy: PetPrivacyPolicy,
+        upgrades: UpgradeConfig,
+        breeding: BreedingConfig,
+        species: SpeciesConfig
+    },
+    PetProgression {
+        pet_id: String,
+        progression: PetProgression,
+        // none once the pet reached the max level
+        next_level_xp: Option<u64>
+    },
+    PetEnergy {
+        pet_id: String,
+        energy: u16,
+        max_energy: u16,
+        seconds_until_full: u64
+    },
+    RaritySupply { supply: Vec<RaritySupply> },
+    PetLineage {
+        pet_id: String,
+        lineage: PetLineage,
+        breeding_cooldown: u64
+    },
+    Leaderboard { entries: Vec<LeaderboardEntry> },
+    PetInfo { pet: PublicPetInfo },
+    PetsInfo { pets: Vec<PublicPetInfo> },
+    Tournament { tournament: TournamentInfo },
+    TournamentEntrant { progress: EntrantProgress },
     Pets { pets: Vec<PetState> },
     Balance { amount: Uint128 },
     Quests { quests: Vec<QuestSummary> },
     History { quest_history: Vec<QuestHistory> },
-    Battles { battles: Vec<BattleInfoWithoutInitiator> }
+    QuestOdds {
+        check: QuestCheck,
+        odds: QuestOdds
+    },
+    Battles { battles: Vec<BattleInfoWithoutInitiator> },
+    BattleDetails {
+        battle: BattleInfoWithoutInitiator,
+        // none until the battle is accepted
+        log: Option<BattleLog>
+    }
 }
\ No newline at end of file
@@ -0,0 +1,826 @@
+//! In-process harness that wires the game, loot20 and pet721 contracts together so full
+//! flows can be tested without a chain. Every execute runs the contract handler directly,
+//! then dispatches the `WasmMsg::Execute` messages it returns in order, and every
+//! `query_wasm_smart` made by the game is routed to the matching contract. A failed message
+//! rolls back the storage of all three contracts, like a failed transaction would.
+//!
+//! pet721 is built against `secret-cosmwasm-std` while the game and loot20 use the scrtlabs
+//! fork, so everything crossing the pet721 boundary is converted through its json form.
+//!
+//! Messages are taken as anything `Serialize`, so downstream crates can drive the contracts
+//! with the game's `msg` types or their own. Enable the `multitest` feature to use it outside
+//! this crate.
+
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    from_slice, to_vec, Addr, Api, Attribute, Binary, BlockInfo, ContractResult, CosmosMsg, Deps,
+    DepsMut, Empty, Env, Order, Querier, QuerierResult, QuerierWrapper, QueryRequest, Response,
+    Storage, SystemError, SystemResult, Uint128, WasmMsg, WasmQuery,
+};
+use pet721_cosmwasm_std as pet_std;
+use secret_toolkit::permit::{
+    validate, Permit, PermitParams, PermitSignature, PubKey, SignedPermit, TokenPermissions,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryAnswer, QueryMsg, QueryWithPermits};
+use crate::state::{ContractData, PetState};
+
+pub const ADMIN: &str = "admin";
+pub const GAME_ADDR: &str = "game_contract";
+pub const LOOT_ADDR: &str = "loot_contract";
+pub const PET_ADDR: &str = "pet_contract";
+pub const MAX_STATS: u16 = 20;
+
+/// seconds that pass per block when advancing time
+const BLOCK_TIME_SECONDS: u64 = 5;
+
+/// a player with a secp256k1 key, able to sign query permits for any of the contracts
+#[derive(Clone, Debug)]
+pub struct TestUser {
+    pub name: String,
+    /// the address derived from the public key, which is what permits resolve to
+    pub address: String,
+    private_key: Vec<u8>,
+    public_key: Binary,
+}
+
+/// the permits a player hands to the game for itself, loot20 and pet721
+#[derive(Clone, Debug)]
+pub struct Permits {
+    pub permit: Permit,
+    pub loot_permit: Permit,
+    pub pet_permit: Permit,
+}
+
+/// the attributes of every message executed in a transaction, and the data of the first
+#[derive(Clone, Debug, Default)]
+pub struct AppResponse {
+    pub attributes: Vec<Attribute>,
+    pub data: Option<Binary>,
+}
+
+impl AppResponse {
+    /// the value of the first attribute with the given key
+    pub fn attribute(&self, key: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.clone())
+    }
+}
+
+struct Snapshot {
+    game: MockStorage,
+    loot: MockStorage,
+    pet: pet_std::testing::MockStorage,
+}
+
+pub struct App {
+    block: BlockInfo,
+    api: MockApi,
+    pet_api: pet_std::testing::MockApi,
+    game: MockStorage,
+    loot: MockStorage,
+    pet: pet_std::testing::MockStorage,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl App {
+    /// instantiates all three contracts the same way `uploader/src/full_setup.ts` does:
+    /// the game is added as a minter of both token contracts and then made their admin
+    pub fn new() -> Self {
+        let mut block = mock_env().block;
+        block.random = Some(block_random(block.height));
+
+        let mut app = App {
+            block,
+            api: MockApi::default(),
+            pet_api: pet_std::testing::MockApi::default(),
+            game: MockStorage::new(),
+            loot: MockStorage::new(),
+            pet: pet_std::testing::MockStorage::new(),
+        };
+
+        let loot_init = format!(
+            "{{\"name\":\"LootToken\",\"symbol\":\"LTK\",\"decimals\":6,\"prng_seed\":\"bG9vdA==\",\
+            \"admin\":\"{ADMIN}\",\"config\":{{\"public_total_supply\":true,\"enable_deposit\":false,\
+            \"enable_redeem\":false,\"enable_mint\":true,\"enable_burn\":true}}}}"
+        );
+        app.instantiate_loot(loot_init.as_bytes())
+            .expect("loot20 instantiates");
+
+        let pet_init = format!(
+            "{{\"name\":\"PetToken\",\"symbol\":\"PET\",\"entropy\":\"pets\",\"admin\":\"{ADMIN}\",\
+            \"config\":{{\"public_token_supply\":true,\"public_owner\":true,\"enable_sealed_metadata\":false,\
+            \"unwrapped_metadata_is_private\":false,\"minter_may_update_metadata\":true,\
+            \"owner_may_update_metadata\":false,\"enable_burn\":true}}}}"
+        );
+        app.instantiate_pet(pet_init.as_bytes())
+            .expect("pet721 instantiates");
+
+        let game_init = InstantiateMsg {
+            admin: Addr::unchecked(ADMIN),
+            max_stats: MAX_STATS,
+            entropy: "multitest".to_string(),
+            loot_contract: ContractData {
+                hash: code_hash(LOOT_ADDR),
+                addr: LOOT_ADDR.to_string(),
+            },
+            pet_contract: ContractData {
+                hash: code_hash(PET_ADDR),
+                addr: PET_ADDR.to_string(),
+            },
+        };
+        app.instantiate_game(game_init).expect("game instantiates");
+
+        for contract in [LOOT_ADDR, PET_ADDR] {
+            let add_minters = format!("{{\"add_minters\":{{\"minters\":[\"{GAME_ADDR}\"]}}}}");
+            app.execute_raw(ADMIN, contract, add_minters.as_bytes())
+                .expect("game becomes a minter");
+            let change_admin = format!("{{\"change_admin\":{{\"address\":\"{GAME_ADDR}\"}}}}");
+            app.execute_raw(ADMIN, contract, change_admin.as_bytes())
+                .expect("game becomes the admin");
+        }
+
+        app
+    }
+
+    pub fn block(&self) -> &BlockInfo {
+        &self.block
+    }
+
+    /// moves the chain forward by the given number of seconds, producing at least one block
+    pub fn advance_time(&mut self, seconds: u64) {
+        self.block.time = self.block.time.plus_seconds(seconds);
+        self.block.height += (seconds / BLOCK_TIME_SECONDS).max(1);
+        if self.block.random.is_some() {
+            self.block.random = Some(block_random(self.block.height));
+        }
+    }
+
+    /// moves the chain forward by the given number of blocks
+    pub fn advance_blocks(&mut self, blocks: u64) {
+        self.advance_time(blocks * BLOCK_TIME_SECONDS);
+    }
+
+    /// toggles `env.block.random`, to exercise the commit-reveal fallback
+    pub fn set_block_random(&mut self, enabled: bool) {
+        self.block.random = if enabled {
+            Some(block_random(self.block.height))
+        } else {
+            None
+        };
+    }
+
+    /// a deterministic player whose key is derived from its name
+    pub fn user(&self, name: &str) -> TestUser {
+        let private_key = Sha256::digest(name.as_bytes()).to_vec();
+        let public_key = recover_public_key(&self.api, &private_key);
+        let mut user = TestUser {
+            name: name.to_string(),
+            address: String::new(),
+            private_key,
+            public_key,
+        };
+
+        // permits resolve to the address derived from the public key, so let the toolkit
+        // derive it rather than duplicating the bech32 encoding here
+        let permit = self.permit(&user, GAME_ADDR);
+        let storage = MockStorage::new();
+        let querier: MockQuerier = MockQuerier::new(&[]);
+        let deps = Deps {
+            storage: &storage,
+            api: &self.api,
+            querier: QuerierWrapper::new(&querier),
+        };
+        user.address = validate(
+            deps,
+            "revoked_permits",
+            &permit,
+            GAME_ADDR.to_string(),
+            None,
+        )
+        .expect("permit signed by the user is valid");
+        user
+    }
+
+    /// a signed permit for the given contract carrying every token permission
+    pub fn permit(&self, user: &TestUser, contract: &str) -> Permit {
+        let params = PermitParams {
+            allowed_tokens: vec![contract.to_string()],
+            permit_name: format!("{}_permit", contract),
+            chain_id: self.block.chain_id.clone(),
+            permissions: vec![
+                TokenPermissions::Allowance,
+                TokenPermissions::Balance,
+                TokenPermissions::History,
+                TokenPermissions::Owner,
+            ],
+        };
+        let signed_bytes = to_vec(&SignedPermit::from_params(&params)).expect("permit serializes");
+        let signature = self
+            .api
+            .secp256k1_sign(&signed_bytes, &user.private_key)
+            .expect("permit is signed");
+
+        Permit {
+            params,
+            signature: PermitSignature {
+                pub_key: PubKey {
+                    r#type: "tendermint/PubKeySecp256k1".to_string(),
+                    value: user.public_key.clone(),
+                },
+                signature: Binary::from(signature),
+            },
+        }
+    }
+
+    pub fn permits(&self, user: &TestUser) -> Permits {
+        Permits {
+            permit: self.permit(user, GAME_ADDR),
+            loot_permit: self.permit(user, LOOT_ADDR),
+            pet_permit: self.permit(user, PET_ADDR),
+        }
+    }
+
+    /// mints loot to a player, the harness admin stays a loot20 minter after setup
+    pub fn give_loot(&mut self, user: &TestUser, amount: u128) {
+        let mint = format!(
+            "{{\"mint\":{{\"recipient\":\"{}\",\"amount\":\"{}\"}}}}",
+            user.address, amount
+        );
+        self.execute_raw(ADMIN, LOOT_ADDR, mint.as_bytes())
+            .expect("admin mints loot");
+    }
+
+    /// lets the game spend a player's loot, which burns and escrows rely on
+    pub fn approve_game(&mut self, user: &TestUser, amount: u128) {
+        let allowance = format!(
+            "{{\"increase_allowance\":{{\"spender\":\"{GAME_ADDR}\",\"amount\":\"{amount}\"}}}}"
+        );
+        self.execute_raw(&user.address, LOOT_ADDR, allowance.as_bytes())
+            .expect("allowance is increased");
+    }
+
+    /// a player holding the given loot, all of which the game may spend
+    pub fn funded_user(&mut self, name: &str, loot: u128) -> TestUser {
+        let user = self.user(name);
+        self.give_loot(&user, loot);
+        self.approve_game(&user, loot);
+        user
+    }
+
+    /// a fresh app with alice and bob, each holding the given loot
+    pub fn with_players(loot: u128) -> (App, TestUser, TestUser) {
+        let mut app = App::new();
+        let alice = app.funded_user("alice", loot);
+        let bob = app.funded_user("bob", loot);
+        (app, alice, bob)
+    }
+
+    /// mints a pet to the player, returning its id
+    pub fn try_mint_pet(&mut self, user: &TestUser) -> Result<String, String> {
+        let msg = ExecuteMsg::MintPet {
+            recipient: user.address.clone(),
+            amount: Uint128::new(1),
+            memo: None,
+            decoys: None,
+            entropy: None,
+            padding: None,
+        };
+        let response = self.execute_game(&user.address, &msg)?;
+        Ok(response
+            .attribute("pet_id")
+            .expect("mints report the pet id"))
+    }
+
+    pub fn mint_pet(&mut self, user: &TestUser) -> String {
+        self.try_mint_pet(user).expect("pet is minted")
+    }
+
+    /// sends the pet on a quest, signed with the player's permits
+    pub fn send_on_quest(
+        &mut self,
+        user: &TestUser,
+        pet_id: &str,
+        quest_type: &str,
+    ) -> Result<(), String> {
+        let permits = self.permits(user);
+        let msg = ExecuteMsg::SendPetOnQuest {
+            pet_id: pet_id.to_string(),
+            quest_type: quest_type.to_string(),
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        self.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    pub fn claim_quest(&mut self, user: &TestUser, quest_type: &str) -> Result<(), String> {
+        let msg = ExecuteMsg::ClaimQuestRewards {
+            quest_type: quest_type.to_string(),
+            entropy: None,
+        };
+        self.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    /// challenges another pet, the challenge expires after the default period
+    pub fn battle(
+        &mut self,
+        user: &TestUser,
+        pet_id: &str,
+        other_pet_id: &str,
+        wager: u64,
+    ) -> Result<(), String> {
+        let permits = self.permits(user);
+        let msg = ExecuteMsg::BattlePet {
+            pet_id: pet_id.to_string(),
+            other_pet_id: other_pet_id.to_string(),
+            wager,
+            expiration: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        self.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    /// the loot20 balance of a player, read through its permit
+    pub fn loot_balance(&self, user: &TestUser) -> u128 {
+        let permit = to_vec(&self.permit(user, LOOT_ADDR)).expect("permit serializes");
+        let query = format!(
+            "{{\"with_permit\":{{\"query\":{{\"balance\":{{}}}},\"permit\":{}}}}}",
+            String::from_utf8_lossy(&permit)
+        );
+        let answer = self
+            .router()
+            .query(LOOT_ADDR, &Binary::from(query.as_bytes()))
+            .expect("balance query succeeds");
+        let answer: LootBalanceAnswer =
+            from_slice(answer.as_slice()).expect("balance answer parses");
+        answer.balance.amount.u128()
+    }
+
+    /// the total loot20 supply, battles escrow wagers so they must leave it unchanged
+    pub fn loot_supply(&self) -> u128 {
+        let answer = self
+            .router()
+            .query(LOOT_ADDR, &Binary::from(b"{\"token_info\":{}}".as_slice()))
+            .expect("token info query succeeds");
+        let answer: LootTokenInfoAnswer =
+            from_slice(answer.as_slice()).expect("token info answer parses");
+        answer
+            .token_info
+            .total_supply
+            .expect("the harness loot20 has a public supply")
+            .u128()
+    }
+
+    /// executes a message as `sender`, rolling every contract back if any message fails
+    pub fn execute<T: Serialize>(
+        &mut self,
+        sender: &str,
+        contract: &str,
+        msg: &T,
+    ) -> Result<AppResponse, String> {
+        let msg = to_vec(msg).map_err(|err| err.to_string())?;
+        self.execute_raw(sender, contract, &msg)
+    }
+
+    /// executes a game message as `sender`
+    pub fn execute_game(&mut self, sender: &str, msg: &ExecuteMsg) -> Result<AppResponse, String> {
+        self.execute(sender, GAME_ADDR, msg)
+    }
+
+    pub fn execute_raw(
+        &mut self,
+        sender: &str,
+        contract: &str,
+        msg: &[u8],
+    ) -> Result<AppResponse, String> {
+        let snapshot = self.snapshot();
+        let mut response = AppResponse::default();
+        match self.dispatch(sender, contract, msg, &mut response) {
+            Ok(()) => Ok(response),
+            Err(err) => {
+                self.restore(snapshot);
+                Err(err)
+            }
+        }
+    }
+
+    /// queries any of the three contracts
+    pub fn query<T: Serialize, R: DeserializeOwned>(
+        &self,
+        contract: &str,
+        msg: &T,
+    ) -> Result<R, String> {
+        let msg = Binary::from(to_vec(msg).map_err(|err| err.to_string())?);
+        let router = self.router();
+        let answer = match contract {
+            GAME_ADDR => {
+                let msg: QueryMsg = from_slice(msg.as_slice()).map_err(|err| err.to_string())?;
+                let deps = Deps {
+                    storage: &self.game,
+                    api: &self.api,
+                    querier: QuerierWrapper::new(&router),
+                };
+                crate::contract::query(deps, contract_env(&self.block, GAME_ADDR), msg)
+                    .map_err(|err| err.to_string())?
+            }
+            _ => router.query(contract, &msg)?,
+        };
+        from_slice(answer.as_slice()).map_err(|err| err.to_string())
+    }
+
+    /// queries the game contract
+    pub fn query_game<R: DeserializeOwned>(&self, msg: &QueryMsg) -> Result<R, String> {
+        self.query(GAME_ADDR, msg)
+    }
+
+    /// queries the game with the player's game, loot and pet permits
+    pub fn query_with_permits(
+        &self,
+        user: &TestUser,
+        query: QueryWithPermits,
+    ) -> Result<QueryAnswer, String> {
+        let permits = self.permits(user);
+        let msg = QueryMsg::WithPermits {
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+            query,
+        };
+        self.query_game(&msg)
+    }
+
+    /// every pet the player owns
+    pub fn my_pets(&self, user: &TestUser) -> Vec<PetState> {
+        let query = QueryWithPermits::MyPets {
+            owner: user.address.clone(),
+            viewer: None,
+            limit: None,
+            start_after: None,
+        };
+        match self.query_with_permits(user, query) {
+            Ok(QueryAnswer::Pets { pets }) => pets,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    pub fn my_pet(&self, user: &TestUser, pet_id: &str) -> PetState {
+        self.my_pets(user)
+            .into_iter()
+            .find(|pet| pet.pet_id == pet_id)
+            .expect("the player owns the pet")
+    }
+
+    fn router(&self) -> Router {
+        Router {
+            block: &self.block,
+            api: &self.api,
+            pet_api: &self.pet_api,
+            loot: &self.loot,
+            pet: &self.pet,
+        }
+    }
+
+    fn dispatch(
+        &mut self,
+        sender: &str,
+        contract: &str,
+        msg: &[u8],
+        app_response: &mut AppResponse,
+    ) -> Result<(), String> {
+        let response = self.execute_contract(sender, contract, msg)?;
+        app_response.attributes.extend(response.attributes.clone());
+        if app_response.data.is_none() {
+            app_response.data = response.data.clone();
+        }
+
+        for sub_msg in response.messages {
+            match sub_msg.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }) => {
+                    self.dispatch(contract, &contract_addr, msg.as_slice(), app_response)?;
+                }
+                other => return Err(format!("unsupported message in multitest: {:?}", other)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute_contract(
+        &mut self,
+        sender: &str,
+        contract: &str,
+        msg: &[u8],
+    ) -> Result<Response, String> {
+        match contract {
+            GAME_ADDR => {
+                let msg: ExecuteMsg = from_slice(msg).map_err(|err| err.to_string())?;
+                let router = Router {
+                    block: &self.block,
+                    api: &self.api,
+                    pet_api: &self.pet_api,
+                    loot: &self.loot,
+                    pet: &self.pet,
+                };
+                let deps = DepsMut {
+                    storage: &mut self.game,
+                    api: &self.api,
+                    querier: QuerierWrapper::new(&router),
+                };
+                crate::contract::execute(
+                    deps,
+                    contract_env(&self.block, GAME_ADDR),
+                    mock_info(sender, &[]),
+                    msg,
+                )
+                .map_err(|err| err.to_string())
+            }
+            LOOT_ADDR => {
+                let msg: snip20_reference_impl::msg::ExecuteMsg =
+                    from_slice(msg).map_err(|err| err.to_string())?;
+                let querier: MockQuerier = MockQuerier::new(&[]);
+                let deps = DepsMut {
+                    storage: &mut self.loot,
+                    api: &self.api,
+                    querier: QuerierWrapper::new(&querier),
+                };
+                snip20_reference_impl::contract::execute(
+                    deps,
+                    contract_env(&self.block, LOOT_ADDR),
+                    mock_info(sender, &[]),
+                    msg,
+                )
+                .map_err(|err| err.to_string())
+            }
+            PET_ADDR => {
+                let msg: snip721_reference_impl::msg::ExecuteMsg =
+                    from_slice(msg).map_err(|err| err.to_string())?;
+                let env: pet_std::Env = convert(&contract_env(&self.block, PET_ADDR))?;
+                let querier: pet_std::testing::MockQuerier =
+                    pet_std::testing::MockQuerier::new(&[]);
+                let deps = pet_std::DepsMut {
+                    storage: &mut self.pet,
+                    api: &self.pet_api,
+                    querier: pet_std::QuerierWrapper::new(&querier),
+                };
+                let response = snip721_reference_impl::contract::execute(
+                    deps,
+                    env,
+                    pet_std::testing::mock_info(sender, &[]),
+                    msg,
+                )
+                .map_err(|err| err.to_string())?;
+                convert(&response)
+            }
+            _ => Err(format!("no contract at {}", contract)),
+        }
+    }
+
+    fn instantiate_game(&mut self, msg: InstantiateMsg) -> Result<Response, String> {
+        let router = Router {
+            block: &self.block,
+            api: &self.api,
+            pet_api: &self.pet_api,
+            loot: &self.loot,
+            pet: &self.pet,
+        };
+        let deps = DepsMut {
+            storage: &mut self.game,
+            api: &self.api,
+            querier: QuerierWrapper::new(&router),
+        };
+        crate::contract::instantiate(
+            deps,
+            contract_env(&self.block, GAME_ADDR),
+            mock_info(ADMIN, &[]),
+            msg,
+        )
+        .map_err(|err| err.to_string())
+    }
+
+    fn instantiate_loot(&mut self, msg: &[u8]) -> Result<Response, String> {
+        let msg: snip20_reference_impl::msg::InstantiateMsg =
+            from_slice(msg).map_err(|err| err.to_string())?;
+        let querier: MockQuerier = MockQuerier::new(&[]);
+        let deps = DepsMut {
+            storage: &mut self.loot,
+            api: &self.api,
+            querier: QuerierWrapper::new(&querier),
+        };
+        snip20_reference_impl::contract::instantiate(
+            deps,
+            contract_env(&self.block, LOOT_ADDR),
+            mock_info(ADMIN, &[]),
+            msg,
+        )
+        .map_err(|err| err.to_string())
+    }
+
+    fn instantiate_pet(&mut self, msg: &[u8]) -> Result<Response, String> {
+        let msg: snip721_reference_impl::msg::InstantiateMsg =
+            from_slice(msg).map_err(|err| err.to_string())?;
+        let env: pet_std::Env = convert(&contract_env(&self.block, PET_ADDR))?;
+        let querier: pet_std::testing::MockQuerier = pet_std::testing::MockQuerier::new(&[]);
+        let deps = pet_std::DepsMut {
+            storage: &mut self.pet,
+            api: &self.pet_api,
+            querier: pet_std::QuerierWrapper::new(&querier),
+        };
+        let response = snip721_reference_impl::contract::instantiate(
+            deps,
+            env,
+            pet_std::testing::mock_info(ADMIN, &[]),
+            msg,
+        )
+        .map_err(|err| err.to_string())?;
+        convert(&response)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            game: copy_storage(&self.game),
+            loot: copy_storage(&self.loot),
+            pet: copy_pet_storage(&self.pet),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.game = snapshot.game;
+        self.loot = snapshot.loot;
+        self.pet = snapshot.pet;
+    }
+}
+
+/// routes the smart queries the game makes to the token contracts of the [`App`]
+struct Router<'a> {
+    block: &'a BlockInfo,
+    api: &'a MockApi,
+    pet_api: &'a pet_std::testing::MockApi,
+    loot: &'a MockStorage,
+    pet: &'a pet_std::testing::MockStorage,
+}
+
+impl Router<'_> {
+    fn query(&self, contract: &str, msg: &Binary) -> Result<Binary, String> {
+        match contract {
+            LOOT_ADDR => {
+                let msg: snip20_reference_impl::msg::QueryMsg =
+                    from_slice(msg.as_slice()).map_err(|err| err.to_string())?;
+                let querier: MockQuerier = MockQuerier::new(&[]);
+                let deps = Deps {
+                    storage: self.loot,
+                    api: self.api,
+                    querier: QuerierWrapper::new(&querier),
+                };
+                snip20_reference_impl::contract::query(
+                    deps,
+                    contract_env(self.block, LOOT_ADDR),
+                    msg,
+                )
+                .map_err(|err| err.to_string())
+            }
+            PET_ADDR => {
+                let msg: snip721_reference_impl::msg::QueryMsg =
+                    from_slice(msg.as_slice()).map_err(|err| err.to_string())?;
+                let env: pet_std::Env = convert(&contract_env(self.block, PET_ADDR))?;
+                let querier: pet_std::testing::MockQuerier =
+                    pet_std::testing::MockQuerier::new(&[]);
+                let deps = pet_std::Deps {
+                    storage: self.pet,
+                    api: self.pet_api,
+                    querier: pet_std::QuerierWrapper::new(&querier),
+                };
+                let answer = snip721_reference_impl::contract::query(deps, env, msg)
+                    .map_err(|err| err.to_string())?;
+                Ok(Binary::from(answer.as_slice()))
+            }
+            _ => Err(format!("no contract at {}", contract)),
+        }
+    }
+}
+
+impl Querier for Router<'_> {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(request) => request,
+            Err(err) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: err.to_string(),
+                    request: Binary::from(bin_request),
+                })
+            }
+        };
+
+        match request {
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr, msg, ..
+            }) => match self.query(&contract_addr, &msg) {
+                Ok(answer) => SystemResult::Ok(ContractResult::Ok(answer)),
+                Err(err) => SystemResult::Ok(ContractResult::Err(err)),
+            },
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "only smart wasm queries are routed in multitest".to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct LootBalance {
+    amount: cosmwasm_std::Uint128,
+}
+
+#[derive(serde::Deserialize)]
+struct LootBalanceAnswer {
+    balance: LootBalance,
+}
+
+#[derive(serde::Deserialize)]
+struct LootTokenInfo {
+    total_supply: Option<cosmwasm_std::Uint128>,
+}
+
+#[derive(serde::Deserialize)]
+struct LootTokenInfoAnswer {
+    token_info: LootTokenInfo,
+}
+
+fn code_hash(contract: &str) -> String {
+    format!("{}_hash", contract)
+}
+
+fn contract_env(block: &BlockInfo, contract: &str) -> Env {
+    let mut env = mock_env();
+    env.block = block.clone();
+    env.contract.address = Addr::unchecked(contract);
+    env.contract.code_hash = code_hash(contract);
+    env
+}
+
+fn block_random(height: u64) -> Binary {
+    Binary::from(Sha256::digest(height.to_be_bytes()).to_vec())
+}
+
+/// moves a value between the two cosmwasm-std versions through its json form
+fn convert<A: Serialize, B: DeserializeOwned>(value: &A) -> Result<B, String> {
+    let json = to_vec(value).map_err(|err| err.to_string())?;
+    from_slice(&json).map_err(|err| err.to_string())
+}
+
+/// secp256k1 signatures recover to two candidate keys, the signer's key is the one both
+/// signatures agree on
+fn recover_public_key(api: &MockApi, private_key: &[u8]) -> Binary {
+    let candidates = |message: &[u8]| -> Vec<Vec<u8>> {
+        let signature = api
+            .secp256k1_sign(message, private_key)
+            .expect("message is signed");
+        let message_hash = Sha256::digest(message);
+        (0..2u8)
+            .filter_map(|param| {
+                api.secp256k1_recover_pubkey(&message_hash, &signature, param)
+                    .ok()
+            })
+            .collect()
+    };
+
+    let first = candidates(b"first message");
+    let second = candidates(b"second message");
+    let public_key = first
+        .into_iter()
+        .find(|key| second.contains(key))
+        .expect("both signatures recover the signer's key");
+    Binary::from(public_key)
+}
+
+fn copy_storage(storage: &MockStorage) -> MockStorage {
+    let mut copy = MockStorage::new();
+    for (key, value) in storage.range(None, None, Order::Ascending) {
+        copy.set(&key, &value);
+    }
+    copy
+}
+
+fn copy_pet_storage(storage: &pet_std::testing::MockStorage) -> pet_std::testing::MockStorage {
+    use pet_std::Storage as _;
+
+    let mut copy = pet_std::testing::MockStorage::new();
+    for (key, value) in storage.range(None, None, pet_std::Order::Ascending) {
+        copy.set(&key, &value);
+    }
+    copy
+}
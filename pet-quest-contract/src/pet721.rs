@@ -3,6 +3,7 @@ use secret_toolkit::permit::Permit;
 use secret_toolkit::snip721::{Cw721Approval, ViewerInfo};
 use serde::{Deserialize, Serialize};
 use crate::msg::{ExecuteMsg, QueryMsg, QueryWithPermits};
+use crate::state::{PetPrivacyPolicy, PetState, Stat};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -67,11 +68,99 @@ pub(crate) enum Pet721ExecuteMsg {
         decoys: Option<Vec<String>>,
         entropy: Option<String>,
         padding: Option<String>,
+        public_metadata: Option<Pet721Metadata>,
+        private_metadata: Option<Pet721Metadata>,
     },
+    SetMetadata {
+        token_id: String,
+        public_metadata: Option<Pet721Metadata>,
+        private_metadata: Option<Pet721Metadata>,
+        padding: Option<String>,
+    },
+}
+
+/// the parts of the pet721 token metadata the game writes, the other fields are left empty
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Pet721Metadata {
+    pub(crate) token_uri: Option<String>,
+    pub(crate) extension: Option<Pet721Extension>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Pet721Extension {
+    pub(crate) name: Option<String>,
+    pub(crate) attributes: Option<Vec<Pet721Trait>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Pet721Trait {
+    pub(crate) display_type: Option<String>,
+    pub(crate) trait_type: Option<String>,
+    pub(crate) value: String,
+    pub(crate) max_value: Option<String>,
+}
+
+impl Pet721Trait {
+    fn text(trait_type: String, value: String) -> Self {
+        Pet721Trait { display_type: None, trait_type: Some(trait_type), value, max_value: None }
+    }
+
+    fn number(trait_type: String, value: u16) -> Self {
+        Pet721Trait {
+            display_type: Some("number".to_string()),
+            trait_type: Some(trait_type),
+            value: value.to_string(),
+            max_value: None,
+        }
+    }
+}
+
+/// the public and private metadata of a pet's token. The name, species, rarity and cosmetics
+/// are always public, the level, stats and max stats follow the privacy policy so the token
+/// shows no more than the public pet queries do
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PetTokenMetadata {
+    pub(crate) public_metadata: Pet721Metadata,
+    pub(crate) private_metadata: Pet721Metadata,
+}
+
+impl PetTokenMetadata {
+    pub(crate) fn from_pet(pet: &PetState, policy: &PetPrivacyPolicy) -> Self {
+        let mut public = vec![];
+        let mut private = vec![];
+        if let Some(traits) = &pet.traits {
+            public.extend(traits.attributes().into_iter()
+                .map(|attribute| Pet721Trait::text(attribute.trait_type, attribute.value)));
+        }
+
+        let level = Pet721Trait::number("level".to_string(), pet.progression.level);
+        if policy.progression { public.push(level) } else { private.push(level) }
+        for stat in Stat::all() {
+            let current = Pet721Trait::number(stat.name().to_string(), pet.current.get_stat(stat.clone()));
+            if policy.current_stats { public.push(current) } else { private.push(current) }
+        }
+        for stat in Stat::all() {
+            let max = Pet721Trait::number(format!("max_{}", stat.name()), pet.max.get_stat(stat));
+            if policy.max_stats { public.push(max) } else { private.push(max) }
+        }
+
+        let metadata = |name: Option<String>, attributes: Vec<Pet721Trait>| Pet721Metadata {
+            token_uri: None,
+            extension: Some(Pet721Extension { name, attributes: Some(attributes) }),
+        };
+        PetTokenMetadata {
+            public_metadata: metadata(pet.name.clone(), public),
+            private_metadata: metadata(None, private),
+        }
+    }
 }
 
 impl Pet721ExecuteMsg {
-    pub(crate) fn from_execute_msg(msg: ExecuteMsg, token_id: Option<String>) -> Self {
+    pub(crate) fn from_execute_msg(
+        msg: ExecuteMsg,
+        token_id: Option<String>,
+        metadata: PetTokenMetadata
+    ) -> Self {
         match msg {
             ExecuteMsg::MintPet {
                 recipient,
@@ -90,6 +179,8 @@ impl Pet721ExecuteMsg {
                     decoys,
                     entropy: entropy_str,
                     padding,
+                    public_metadata: Some(metadata.public_metadata),
+                    private_metadata: Some(metadata.private_metadata),
                 }
             },
             _ => panic!("Expected MintPet variant")
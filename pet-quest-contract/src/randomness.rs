@@ -1,11 +1,132 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Binary, CanonicalAddr, Env, StdResult, Storage};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
 use sha2::{Digest, Sha256};
+use crate::error::ContractError;
+use crate::state::{EntropyCommitment, ENTROPY_COMMITMENTS, PRNG_SEED};
 
-pub fn generate_seed(sender: &Addr, block_time: u64, entropy: &[u8]) -> u64 {
+fn hash_parts(parts: &[&[u8]]) -> [u8; 32] {
     let mut hasher = Sha256::new();
-    hasher.update(sender.as_bytes());
-    hasher.update(&block_time.to_be_bytes());
-    hasher.update(entropy);
-    let hash = hasher.finalize();
-    u64::from_be_bytes(hash[0..8].try_into().unwrap())
-}
\ No newline at end of file
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+fn load_prng_seed(storage: &dyn Storage) -> StdResult<[u8; 32]> {
+    Ok(PRNG_SEED.may_load(storage)?.unwrap_or_default())
+}
+
+/// seeds the contract wide prng state, called once on instantiation
+pub(crate) fn init_prng_seed(
+    storage: &mut dyn Storage,
+    env: &Env,
+    entropy: &[u8]
+) -> StdResult<()> {
+    let random = env.block.random.clone().unwrap_or_default();
+    let seed = hash_parts(&[
+        entropy,
+        &env.block.height.to_be_bytes(),
+        &env.block.time.nanos().to_be_bytes(),
+        random.as_slice(),
+    ]);
+    PRNG_SEED.save(storage, &seed)
+}
+
+/// folds the block and sender of every execute into the prng state, so that the state
+/// keeps evolving even between executes that do not draw any randomness
+pub(crate) fn fold_execute_entropy(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr
+) -> StdResult<()> {
+    let seed = load_prng_seed(storage)?;
+    let random = env.block.random.clone().unwrap_or_default();
+    let new_seed = hash_parts(&[
+        &seed,
+        sender.as_bytes(),
+        &env.block.height.to_be_bytes(),
+        &env.block.time.nanos().to_be_bytes(),
+        random.as_slice(),
+    ]);
+    PRNG_SEED.save(storage, &new_seed)
+}
+
+/// mixes admin supplied entropy into the prng state, used when the config entropy is rotated
+pub(crate) fn mix_prng_entropy(
+    storage: &mut dyn Storage,
+    entropy: &[u8]
+) -> StdResult<()> {
+    let seed = load_prng_seed(storage)?;
+    let new_seed = hash_parts(&[&seed, entropy]);
+    PRNG_SEED.save(storage, &new_seed)
+}
+
+/// stores the hash of a secret that the sender will reveal in a later block, only
+/// needed when the chain does not provide `env.block.random`
+pub(crate) fn commit_entropy(
+    storage: &mut dyn Storage,
+    env: &Env,
+    addr: &CanonicalAddr,
+    commitment: Binary
+) -> Result<(), ContractError> {
+    if commitment.len() != 32 {
+        return Err(ContractError::InvalidEntropyCommitment);
+    }
+
+    let entry = EntropyCommitment {
+        commitment,
+        height: env.block.height
+    };
+    ENTROPY_COMMITMENTS.insert(storage, addr, &entry)?;
+    Ok(())
+}
+
+/// resolves the unpredictable input for an execute. The chain provided block random is
+/// used when it is available, otherwise the sender must reveal the secret behind a
+/// commitment made in an earlier block. The commitment is consumed by the reveal.
+pub(crate) fn block_entropy(
+    storage: &mut dyn Storage,
+    env: &Env,
+    addr: &CanonicalAddr,
+    reveal: Option<Binary>
+) -> Result<Vec<u8>, ContractError> {
+    if let Some(random) = &env.block.random {
+        return Ok(random.to_vec());
+    }
+
+    let commitment = match ENTROPY_COMMITMENTS.get(storage, addr) {
+        Some(commitment) => commitment,
+        None => return Err(ContractError::EntropyNotCommitted)
+    };
+    if commitment.height >= env.block.height {
+        return Err(ContractError::EntropyRevealedTooEarly);
+    }
+
+    let reveal = match reveal {
+        Some(reveal) => reveal,
+        None => return Err(ContractError::EntropyNotRevealed)
+    };
+    if hash_parts(&[reveal.as_slice()]).as_slice() != commitment.commitment.as_slice() {
+        return Err(ContractError::EntropyMismatch);
+    }
+
+    ENTROPY_COMMITMENTS.remove(storage, addr)?;
+    Ok(reveal.to_vec())
+}
+
+/// draws a new rng from the prng state, advancing the state so that every draw in the
+/// same execute produces an independent stream
+pub(crate) fn draw_rng(
+    storage: &mut dyn Storage,
+    entropy: &[u8]
+) -> StdResult<SmallRng> {
+    let seed = load_prng_seed(storage)?;
+    let new_seed = hash_parts(&[&seed, entropy]);
+    PRNG_SEED.save(storage, &new_seed)?;
+
+    let rng_seed = hash_parts(&[&new_seed, b"rng"]);
+    Ok(SmallRng::seed_from_u64(u64::from_be_bytes(rng_seed[0..8].try_into().unwrap())))
+}
@@ -0,0 +1,250 @@
+use rand::rngs::SmallRng;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::error::ContractError;
+use crate::state::{PetStats, Stat, MIN_MAX_STATS};
+
+// the base stats of a minted pet are rolled in this range before the species bias is added
+pub(crate) static MIN_ROLLED_STAT: u16 = 5;
+pub(crate) static MAX_ROLLED_STAT: u16 = 8;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary
+}
+
+impl Rarity {
+    pub(crate) fn all() -> [Rarity; 5] {
+        [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Epic, Rarity::Legendary]
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Rarity::Common => "common",
+            Rarity::Uncommon => "uncommon",
+            Rarity::Rare => "rare",
+            Rarity::Epic => "epic",
+            Rarity::Legendary => "legendary",
+        }
+    }
+}
+
+/// how often a rarity is rolled, what it adds to the stats and how many pets of it can exist
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RarityTier {
+    pub rarity: Rarity,
+    pub weight: u16,
+    // added to every max stat, up to the species and config maxes
+    pub stat_bonus: u16,
+    // the most pets ever minted or bred with this rarity, none for no cap
+    pub supply_cap: Option<u64>
+}
+
+/// the values one cosmetic trait of a species is rolled from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TraitPool {
+    pub trait_type: String,
+    pub values: Vec<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Species {
+    pub id: String,
+    pub name: String,
+    pub weight: u16,
+    // added to the rolled base stats, small enough that every minted pet can still be upgraded
+    pub stat_bias: PetStats,
+    // the highest each max stat of the species can roll, the config max stats still apply
+    pub max: PetStats,
+    pub traits: Vec<TraitPool>
+}
+
+/// the species and rarities pets are rolled with when they are minted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SpeciesConfig {
+    pub species: Vec<Species>,
+    pub rarities: Vec<RarityTier>
+}
+
+impl Default for SpeciesConfig {
+    fn default() -> Self {
+        let colors = |values: &[&str]| TraitPool {
+            trait_type: "color".to_string(),
+            values: values.iter().map(|value| value.to_string()).collect(),
+        };
+        let patterns = TraitPool {
+            trait_type: "pattern".to_string(),
+            values: vec!["plain".to_string(), "striped".to_string(), "spotted".to_string()],
+        };
+        let species = |id: &str, name: &str, stat_bias: PetStats, max: PetStats, color: TraitPool| Species {
+            id: id.to_string(),
+            name: name.to_string(),
+            weight: 1,
+            stat_bias,
+            max,
+            traits: vec![color, patterns.clone()],
+        };
+        let tier = |rarity: Rarity, weight: u16, stat_bonus: u16, supply_cap: Option<u64>| RarityTier {
+            rarity,
+            weight,
+            stat_bonus,
+            supply_cap,
+        };
+
+        SpeciesConfig {
+            species: vec![
+                species("drake", "Drake", PetStats::new(1, 2, 0, 0, 0),
+                    PetStats::new(40, 40, 30, 30, 30), colors(&["crimson", "emerald", "obsidian"])),
+                species("tortoise", "Tortoise", PetStats::new(2, 0, 1, 0, 0),
+                    PetStats::new(40, 30, 40, 30, 30), colors(&["moss", "sand", "slate"])),
+                species("owl", "Owl", PetStats::new(0, 0, 0, 2, 1),
+                    PetStats::new(30, 30, 30, 40, 40), colors(&["snow", "tawny", "midnight"])),
+            ],
+            rarities: vec![
+                tier(Rarity::Common, 600, 0, None),
+                tier(Rarity::Uncommon, 250, 1, None),
+                tier(Rarity::Rare, 100, 2, None),
+                tier(Rarity::Epic, 40, 3, Some(1_000)),
+                tier(Rarity::Legendary, 10, 4, Some(100)),
+            ],
+        }
+    }
+}
+
+impl SpeciesConfig {
+    pub(crate) fn validate(&self) -> Result<(), ContractError> {
+        let invalid = |reason: &str| Err(ContractError::InvalidSpeciesConfig { reason: reason.to_string() });
+
+        if !self.species.iter().any(|species| species.weight > 0) {
+            return invalid("at least one species must have a weight");
+        }
+        for (index, species) in self.species.iter().enumerate() {
+            if self.species[..index].iter().any(|other| other.id == species.id) {
+                return invalid("species ids must be unique");
+            }
+            if Stat::all().into_iter().any(|stat| species.max.get_stat(stat) < MIN_MAX_STATS) {
+                return invalid("species max stats must be at least the minimum max stat");
+            }
+            let biased = Stat::all().into_iter()
+                .any(|stat| MAX_ROLLED_STAT.saturating_add(species.stat_bias.get_stat(stat)) >= MIN_MAX_STATS);
+            if biased {
+                return invalid("stat biases must keep base stats below the minimum max stat");
+            }
+            if species.traits.iter().any(|pool| pool.values.is_empty()) {
+                return invalid("trait pools must not be empty");
+            }
+        }
+        if !self.rarities.iter().any(|tier| tier.weight > 0) {
+            return invalid("at least one rarity must have a weight");
+        }
+        for (index, tier) in self.rarities.iter().enumerate() {
+            if self.rarities[..index].iter().any(|other| other.rarity == tier.rarity) {
+                return invalid("every rarity can only have one tier");
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn species(&self, id: &str) -> Option<&Species> {
+        self.species.iter().find(|species| species.id == id)
+    }
+
+    pub(crate) fn roll_species(&self, rng: &mut SmallRng) -> &Species {
+        pick_weighted(&self.species, |species| species.weight, rng)
+            .expect("validated configs have a weighted species")
+    }
+
+    /// rolls among the tiers that still have supply left, `minted` gives the pets minted
+    /// with each rarity so far
+    pub(crate) fn roll_rarity(
+        &self,
+        minted: impl Fn(&Rarity) -> u64,
+        rng: &mut SmallRng
+    ) -> Result<&RarityTier, ContractError> {
+        let available: Vec<&RarityTier> = self.rarities.iter()
+            .filter(|tier| tier.supply_cap.map_or(true, |cap| minted(&tier.rarity) < cap))
+            .collect();
+        match pick_weighted(&available, |tier| tier.weight, rng) {
+            Some(tier) => Ok(*tier),
+            None => Err(ContractError::SupplyExhausted)
+        }
+    }
+}
+
+impl Species {
+    /// rolls the base and max stats of a minted pet of this species
+    pub(crate) fn roll_stats(&self, tier: &RarityTier, max_stat_value: u16, rng: &mut SmallRng) -> (PetStats, PetStats) {
+        let mut base = PetStats::new(0, 0, 0, 0, 0);
+        let mut max = PetStats::new(0, 0, 0, 0, 0);
+        for stat in Stat::all() {
+            let highest = self.max.get_stat(stat.clone()).min(max_stat_value);
+            let max_value = rng.gen_range(MIN_MAX_STATS..=highest)
+                .saturating_add(tier.stat_bonus)
+                .min(highest);
+            let base_value = rng.gen_range(MIN_ROLLED_STAT..=MAX_ROLLED_STAT)
+                + self.stat_bias.get_stat(stat.clone());
+            max.set_stat(stat.clone(), max_value);
+            base.set_stat(stat, base_value);
+        }
+        (base, max)
+    }
+
+    pub(crate) fn roll_cosmetics(&self, rng: &mut SmallRng) -> Vec<PetTrait> {
+        self.traits.iter()
+            .map(|pool| PetTrait {
+                trait_type: pool.trait_type.clone(),
+                value: pool.values[rng.gen_range(0..pool.values.len())].clone(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PetTrait {
+    pub trait_type: String,
+    pub value: String
+}
+
+/// what a pet is, mirrored into the attributes of its pet721 token. Pets minted before species
+/// existed have none
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PetTraits {
+    pub species: String,
+    pub rarity: Rarity,
+    pub cosmetics: Vec<PetTrait>
+}
+
+impl PetTraits {
+    /// the species and rarity followed by the cosmetics, as the token attributes list them
+    pub(crate) fn attributes(&self) -> Vec<PetTrait> {
+        let mut attributes = vec![
+            PetTrait { trait_type: "species".to_string(), value: self.species.clone() },
+            PetTrait { trait_type: "rarity".to_string(), value: self.rarity.name().to_string() },
+        ];
+        attributes.extend(self.cosmetics.iter().cloned());
+        attributes
+    }
+}
+
+/// picks an item with a chance proportional to its weight, none if every weight is zero
+fn pick_weighted<'a, T>(items: &'a [T], weight: impl Fn(&T) -> u16, rng: &mut SmallRng) -> Option<&'a T> {
+    let total: u32 = items.iter().map(|item| u32::from(weight(item))).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut roll = rng.gen_range(0..total);
+    for item in items {
+        let item_weight = u32::from(weight(item));
+        if roll < item_weight {
+            return Some(item);
+        }
+        roll -= item_weight;
+    }
+    None
+}
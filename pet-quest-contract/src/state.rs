@@ -1,28 +1,133 @@
 use std::cmp::PartialEq;
-use std::ops::Mul;
 use schemars::JsonSchema;
-use secret_toolkit::storage::{Item, Keymap};
+use secret_toolkit::serialization::Json;
+use secret_toolkit::storage::{AppendStore, Item, Keymap};
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{Addr, StdResult, Storage, Timestamp, StdError, CanonicalAddr};
+use cosmwasm_std::{to_vec, Addr, StdResult, Storage, Timestamp, StdError, CanonicalAddr, Binary, BlockInfo, Uint128};
 use rand::{Rng};
 use rand::rngs::SmallRng;
-
-pub(crate) static QUEST_COOLDOWN_SECONDS: u64 = 60;
-pub(crate) static QUEST_EXPLORE_TIME_SECONDS: u64 = 30;
-
-
-pub(crate) static CONFIG_KEY: &[u8] = b"config";
-pub(crate) static PETS_KEY: &[u8] = b"pets";
-pub(crate) static QUESTS_KEY: &[u8] = b"quests";
-pub(crate) static QUEST_HISTORY_KEY: &[u8] = b"quest_history";
+use sha2::{Digest, Sha256};
+use crate::battle::{BattleLog, BattleOutcome, TieBreaker};
+use crate::tournament::{Placement, TournamentMatch};
+use crate::species::{PetTraits, Rarity, RarityTier, Species, SpeciesConfig};
+use crate::error::ContractError;
+use crate::pet721::PetTokenMetadata;
+
+// timings of the quests the catalogue starts with
+pub(crate) static DEFAULT_QUEST_COOLDOWN_SECONDS: u64 = 60;
+pub(crate) static DEFAULT_QUEST_EXPLORE_SECONDS: u64 = 30;
+// new pets roll their max stats from this value up to the configured max_stats
+pub(crate) static MIN_MAX_STATS: u16 = 12;
+// level ups raise the max stats up to this value
+pub(crate) static MAX_STAT_VALUE: u16 = 1_000;
+// quests are resolved with a d20 roll plus the pet's modifier, against the quest difficulty
+// plus the base target. Beating the target by the margin is an exceptional pass
+pub(crate) static QUEST_DIE_SIDES: u16 = 20;
+pub(crate) static QUEST_BASE_TARGET: u16 = 10;
+pub(crate) static QUEST_EXCEPTIONAL_MARGIN: u16 = 10;
+// bounds of the quest catalogue, a quest's difficulty increment never grows past the max difficulty
+pub(crate) static MAX_QUEST_DIFFICULTY: u16 = 1_000;
+pub(crate) static MAX_QUEST_BASE_LOOT: u16 = 10_000;
+pub(crate) static MAX_QUEST_GROWTH: u16 = 100;
+// pets hold ENERGY_BASE plus ENERGY_PER_STAMINA for every stamina point, quests and battles
+// spend it and one point comes back every ENERGY_REGEN_SECONDS
+pub(crate) static ENERGY_BASE: u16 = 20;
+pub(crate) static ENERGY_PER_STAMINA: u16 = 2;
+pub(crate) static ENERGY_REGEN_SECONDS: u64 = 60;
+pub(crate) static QUEST_ENERGY_COST: u16 = 10;
+pub(crate) static BATTLE_ENERGY_COST: u16 = 15;
+// challenges without an expiration can be accepted for a day
+pub(crate) static DEFAULT_BATTLE_EXPIRY_SECONDS: u64 = 86_400;
+// challenges must expire within three days, heights are converted assuming the fastest blocks
+pub(crate) static MAX_BATTLE_EXPIRY_SECONDS: u64 = 259_200;
+pub(crate) static MIN_SECONDS_PER_BLOCK: u64 = 5;
+// pending challenges a pet can be part of at once, as challenger or challenged
+pub(crate) static MAX_PENDING_BATTLES_PER_PET: u32 = 3;
+// challenges must risk at least this much loot
+pub(crate) static MIN_BATTLE_WAGER: u64 = 5;
+// pets start at the initial elo rating and move by at most the k factor per battle
+pub(crate) static INITIAL_RATING: u32 = 1200;
+pub(crate) static RATING_K_FACTOR: i64 = 32;
+pub(crate) static DEFAULT_LEADERBOARD_LIMIT: u32 = 10;
+pub(crate) static MAX_LEADERBOARD_LIMIT: u32 = 50;
+// the leaderboard groups pets by rating so that a rating change only rewrites its buckets
+pub(crate) static LEADERBOARD_BUCKET_SIZE: u32 = 10;
+// queued pets are paired with pets of the same wager whose rating is at most one bucket away
+pub(crate) static RATING_BUCKET_SIZE: u32 = 200;
+// tournaments without a registration deadline take entrants for an hour
+pub(crate) static DEFAULT_TOURNAMENT_REGISTRATION_SECONDS: u64 = 3_600;
+// registration must close within a week, so a tournament that never fills is still played
+pub(crate) static MAX_TOURNAMENT_REGISTRATION_SECONDS: u64 = 604_800;
+// pets a single public batch query can look up
+pub(crate) static MAX_PETS_INFO: u32 = 30;
+pub(crate) static DEFAULT_QUEST_HISTORY_PAGE_SIZE: u32 = 20;
+pub(crate) static MAX_QUEST_HISTORY_PAGE_SIZE: u32 = 50;
+// the lowest value a bred pet starts a stat at
+pub(crate) static MIN_BRED_STAT: u16 = 1;
+// the most a bred stat may stray from what it inherited, so children stay like their parents
+pub(crate) static MAX_BREEDING_MUTATION: u16 = 5;
+// characters in a pet's name
+pub(crate) static MAX_PET_NAME_LENGTH: usize = 32;
+
+
+// stores that hold structs are json serialized so fields can be added with a serde default,
+// the `_v1` namespaces replaced the bincode stores of storage version 0 (see migrate.rs)
+pub(crate) static CONTRACT_INFO_KEY: &[u8] = b"contract_info";
+pub(crate) static CONFIG_KEY: &[u8] = b"config_v1";
+pub(crate) static PETS_KEY: &[u8] = b"pets_v1";
+pub(crate) static QUESTS_KEY: &[u8] = b"quests_v1";
+pub(crate) static QUEST_HISTORY_KEY: &[u8] = b"quest_history_v1";
+pub(crate) static QUEST_LOG_KEY: &[u8] = b"quest_log";
+pub(crate) static PLAYER_QUEST_HISTORY_KEY: &[u8] = b"player_quest_history";
+pub(crate) static PET_QUEST_HISTORY_KEY: &[u8] = b"pet_quest_history";
 pub(crate) static PET_COUNTER_KEY: &[u8] = b"pet_counter";
 pub(crate) static BATTLE_COUNTER_KEY: &[u8] = b"battle_counter";
 pub(crate) static PET_BATTLES_KEY: &[u8] = b"pet_battles";
-pub(crate) static BATTLE_KEY: &[u8] = b"battles";
+pub(crate) static BATTLE_KEY: &[u8] = b"battles_v1";
+pub(crate) static BATTLE_LOGS_KEY: &[u8] = b"battle_logs";
 pub(crate) static LOOT20_KEY: &[u8] = b"loot_20";
 pub(crate) static PET721_KEY: &[u8] = b"pet_721";
-
-pub(crate) static CONFIG: Item<Config> = Item::new(CONFIG_KEY);
+pub(crate) static PRNG_SEED_KEY: &[u8] = b"prng_seed";
+pub(crate) static ENTROPY_COMMITMENTS_KEY: &[u8] = b"entropy_commitments";
+pub(crate) static CONTRACT_STATUS_KEY: &[u8] = b"contract_status";
+pub(crate) static QUEST_CATALOGUE_KEY: &[u8] = b"quest_catalogue";
+pub(crate) static PROGRESSION_CONFIG_KEY: &[u8] = b"progression_config";
+pub(crate) static TIE_BREAKER_KEY: &[u8] = b"tie_breaker";
+pub(crate) static PRIVACY_POLICY_KEY: &[u8] = b"privacy_policy";
+pub(crate) static UPGRADE_CONFIG_KEY: &[u8] = b"upgrade_config";
+pub(crate) static BREEDING_CONFIG_KEY: &[u8] = b"breeding_config";
+pub(crate) static SPECIES_CONFIG_KEY: &[u8] = b"species_config";
+pub(crate) static RARITY_SUPPLY_KEY: &[u8] = b"rarity_supply";
+pub(crate) static TOKEN_METADATA_KEY: &[u8] = b"token_metadata";
+pub(crate) static LEADERBOARD_KEY: &[u8] = b"leaderboard";
+pub(crate) static LEADERBOARD_BUCKETS_KEY: &[u8] = b"leaderboard_buckets";
+pub(crate) static MATCHMAKING_QUEUE_KEY: &[u8] = b"matchmaking_queue";
+pub(crate) static MATCHMAKING_PETS_KEY: &[u8] = b"matchmaking_pets";
+pub(crate) static TOURNAMENT_COUNTER_KEY: &[u8] = b"tournament_counter";
+pub(crate) static TOURNAMENTS_KEY: &[u8] = b"tournaments";
+pub(crate) static TOURNAMENT_PETS_KEY: &[u8] = b"tournament_pets";
+
+pub(crate) static CONTRACT_INFO: Item<ContractInfo, Json> = Item::new(CONTRACT_INFO_KEY);
+pub(crate) static CONFIG: Item<Config, Json> = Item::new(CONFIG_KEY);
+pub(crate) static CONTRACT_STATUS: Item<ContractStatusLevel> = Item::new(CONTRACT_STATUS_KEY);
+pub(crate) static PROGRESSION_CONFIG: Item<ProgressionConfig, Json> = Item::new(PROGRESSION_CONFIG_KEY);
+pub(crate) static TIE_BREAKER: Item<TieBreaker, Json> = Item::new(TIE_BREAKER_KEY);
+pub(crate) static PRIVACY_POLICY: Item<PetPrivacyPolicy, Json> = Item::new(PRIVACY_POLICY_KEY);
+pub(crate) static UPGRADE_CONFIG: Item<UpgradeConfig, Json> = Item::new(UPGRADE_CONFIG_KEY);
+pub(crate) static BREEDING_CONFIG: Item<BreedingConfig, Json> = Item::new(BREEDING_CONFIG_KEY);
+pub(crate) static SPECIES_CONFIG: Item<SpeciesConfig, Json> = Item::new(SPECIES_CONFIG_KEY);
+// the pets minted or bred with each rarity, released pets keep counting against the caps
+pub(crate) static RARITY_SUPPLY: Keymap<Rarity, u64> = Keymap::new(RARITY_SUPPLY_KEY);
+// sha256 of the metadata last written to each pet's token, to tell which tokens have drifted
+pub(crate) static TOKEN_METADATA: Keymap<String, Binary> = Keymap::new(TOKEN_METADATA_KEY);
+
+/// cw2 style contract version, with the version of the storage layouts that `migrate` checks
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
+pub(crate) struct ContractInfo {
+    pub(crate) contract: String,
+    pub(crate) version: String,
+    pub(crate) storage_version: u16
+}
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
 pub(crate) struct Config {
@@ -31,10 +136,351 @@ pub(crate) struct Config {
     pub(crate) entropy: String
 }
 
+/// which game actions are currently allowed, admin messages are allowed at every level
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractStatusLevel {
+    NormalRun,
+    // no quests can be started or claimed
+    StopQuests,
+    // no battles can be started, accepted or claimed, pending battles can still be refunded
+    StopBattles,
+    StopAll
+}
+
+/// how much experience quests award and what a pet gains when it levels up
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct ProgressionConfig {
+    pub xp_on_fail: u64,
+    pub xp_on_pass: u64,
+    pub xp_on_exceptional_pass: u64,
+    // total experience needed to reach level 2, 3, ... the last entry is the max level
+    pub level_thresholds: Vec<u64>,
+    // free points that can be spent on any stat below its max
+    pub stat_points_per_level: u16,
+    // added to every max stat
+    pub max_stats_per_level: u16
+}
+
+impl Default for ProgressionConfig {
+    fn default() -> Self {
+        ProgressionConfig {
+            xp_on_fail: 2,
+            xp_on_pass: 5,
+            xp_on_exceptional_pass: 10,
+            level_thresholds: vec![10, 25, 45, 70, 100, 140, 190, 250, 320],
+            stat_points_per_level: 1,
+            max_stats_per_level: 1,
+        }
+    }
+}
+
+impl ProgressionConfig {
+    pub(crate) fn validate(&self) -> Result<(), ContractError> {
+        let invalid = |reason: &str| Err(ContractError::InvalidProgressionConfig { reason: reason.to_string() });
+
+        if self.level_thresholds.first() == Some(&0) {
+            return invalid("level thresholds must not be zero");
+        }
+        if self.level_thresholds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return invalid("level thresholds must be increasing");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn xp_for(&self, outcome: &QuestOutcome) -> u64 {
+        match outcome {
+            QuestOutcome::Fail => self.xp_on_fail,
+            QuestOutcome::Pass => self.xp_on_pass,
+            QuestOutcome::ExceptionalPass => self.xp_on_exceptional_pass,
+        }
+    }
+
+    /// the total experience needed for the level after `level`, none at the max level
+    pub(crate) fn next_level_xp(&self, level: u16) -> Option<u64> {
+        self.level_thresholds.get(level.saturating_sub(1) as usize).copied()
+    }
+}
+
+/// contracts instantiated before progression was added use the default config
+pub(crate) fn load_progression_config(storage: &dyn Storage) -> StdResult<ProgressionConfig> {
+    Ok(PROGRESSION_CONFIG.may_load(storage)?.unwrap_or_default())
+}
+
+pub(crate) fn load_tie_breaker(storage: &dyn Storage) -> StdResult<TieBreaker> {
+    Ok(TIE_BREAKER.may_load(storage)?.unwrap_or_default())
+}
+
+/// contracts instantiated before upgrade curves were added keep the flat 5 loot per point
+pub(crate) fn load_upgrade_config(storage: &dyn Storage) -> StdResult<UpgradeConfig> {
+    Ok(UPGRADE_CONFIG.may_load(storage)?.unwrap_or_default())
+}
+
+/// the loot cost of raising a stat by one point, by the value the stat is raised from
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CostCurve {
+    // base + per_point * value
+    Linear { base: u64, per_point: u64 },
+    // base + per_point * value^2
+    Quadratic { base: u64, per_point: u64 },
+    // the cost from each value starting at 0, values past the end cost the last entry
+    Table { costs: Vec<u64> }
+}
+
+impl CostCurve {
+    pub(crate) fn cost(&self, value: u16) -> u64 {
+        let value = u64::from(value);
+        match self {
+            CostCurve::Linear { base, per_point } => base.saturating_add(per_point.saturating_mul(value)),
+            CostCurve::Quadratic { base, per_point } => base.saturating_add(per_point.saturating_mul(value * value)),
+            CostCurve::Table { costs } => costs.get(value as usize).or(costs.last()).copied().unwrap_or(0)
+        }
+    }
+}
+
+/// what raising each stat with loot costs, and what a respec costs and pays back
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct UpgradeConfig {
+    pub health: CostCurve,
+    pub strength: CostCurve,
+    pub stamina: CostCurve,
+    pub intelligence: CostCurve,
+    pub luck: CostCurve,
+    pub respec_fee: u64,
+    // the share of the loot spent on upgrades that a respec pays back
+    pub respec_refund_percent: u16
+}
+
+impl Default for UpgradeConfig {
+    fn default() -> Self {
+        let linear = CostCurve::Linear { base: 0, per_point: 5 };
+        UpgradeConfig {
+            health: linear.clone(),
+            strength: linear.clone(),
+            stamina: linear.clone(),
+            intelligence: linear.clone(),
+            luck: linear,
+            respec_fee: 50,
+            respec_refund_percent: 50,
+        }
+    }
+}
+
+impl UpgradeConfig {
+    pub(crate) fn validate(&self) -> Result<(), ContractError> {
+        let invalid = |reason: &str| Err(ContractError::InvalidUpgradeConfig { reason: reason.to_string() });
+
+        if self.respec_refund_percent > 100 {
+            return invalid("respec refund must be at most 100 percent");
+        }
+        let empty_table = Stat::all().iter()
+            .any(|stat| matches!(self.curve(stat), CostCurve::Table { costs } if costs.is_empty()));
+        if empty_table {
+            return invalid("cost tables must not be empty");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn curve(&self, stat: &Stat) -> &CostCurve {
+        match stat {
+            Stat::Health => &self.health,
+            Stat::Strength => &self.strength,
+            Stat::Stamina => &self.stamina,
+            Stat::Intelligence => &self.intelligence,
+            Stat::Luck => &self.luck,
+        }
+    }
+
+    pub(crate) fn upgrade_costs(&self, stats: &PetStats) -> UpgradeCosts {
+        UpgradeCosts {
+            health: self.health.cost(stats.health),
+            strength: self.strength.cost(stats.strength),
+            stamina: self.stamina.cost(stats.stamina),
+            intelligence: self.intelligence.cost(stats.intelligence),
+            luck: self.luck.cost(stats.luck),
+        }
+    }
+
+    /// the cost of raising the stats point by point, upgrades of the same stat add up
+    pub(crate) fn cost_of_upgrades(&self, current: &PetStats, upgrades: &[(Stat, u16)]) -> u64 {
+        let mut stats = current.clone();
+        let mut total: u64 = 0;
+        for (stat, points) in upgrades {
+            for _ in 0..*points {
+                let value = stats.get_stat(stat.clone());
+                total = total.saturating_add(self.curve(stat).cost(value));
+                stats.set_stat(stat.clone(), value.saturating_add(1));
+            }
+        }
+        total
+    }
+
+    pub(crate) fn respec_refund(&self, loot_spent: u64) -> u64 {
+        (u128::from(loot_spent) * u128::from(self.respec_refund_percent) / 100) as u64
+    }
+}
+
+/// the loot cost of raising each stat of a pet by its next point
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, Eq, PartialEq)]
+pub struct UpgradeCosts {
+    pub health: u64,
+    pub strength: u64,
+    pub stamina: u64,
+    pub intelligence: u64,
+    pub luck: u64
+}
+
+impl From<PetStats> for UpgradeCosts {
+    fn from(costs: PetStats) -> Self {
+        UpgradeCosts {
+            health: u64::from(costs.health),
+            strength: u64::from(costs.strength),
+            stamina: u64::from(costs.stamina),
+            intelligence: u64::from(costs.intelligence),
+            luck: u64::from(costs.luck),
+        }
+    }
+}
+
+/// what went into the pet's stats since it was minted or last respecced. Pets minted before
+/// this was recorded take their stats before their first upgrade as base
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PetUpgrades {
+    pub base: Option<PetStats>,
+    pub loot_spent: u64,
+    pub stat_points_spent: u16
+}
+
+pub(crate) fn load_species_config(storage: &dyn Storage) -> StdResult<SpeciesConfig> {
+    Ok(SPECIES_CONFIG.may_load(storage)?.unwrap_or_default())
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
-pub(crate) struct ContractData {
-    pub(crate) hash: String,
-    pub(crate) addr: String
+pub struct RaritySupply {
+    pub rarity: Rarity,
+    pub minted: u64,
+    pub supply_cap: Option<u64>
+}
+
+pub(crate) fn get_rarity_supply(storage: &dyn Storage) -> StdResult<Vec<RaritySupply>> {
+    let config = load_species_config(storage)?;
+    Ok(Rarity::all().into_iter()
+        .map(|rarity| RaritySupply {
+            minted: RARITY_SUPPLY.get(storage, &rarity).unwrap_or(0),
+            supply_cap: config.rarities.iter()
+                .find(|tier| tier.rarity == rarity)
+                .and_then(|tier| tier.supply_cap),
+            rarity,
+        })
+        .collect())
+}
+
+pub(crate) fn load_breeding_config(storage: &dyn Storage) -> StdResult<BreedingConfig> {
+    Ok(BREEDING_CONFIG.may_load(storage)?.unwrap_or_default())
+}
+
+/// what breeding costs and how far a child's stats may stray from its parents
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct BreedingConfig {
+    // burned from the breeder
+    pub fee: u64,
+    // how long both parents wait after breeding before they can breed again
+    pub cooldown_seconds: u64,
+    // the most a stat of the child is raised or lowered from what it inherited
+    pub mutation: u16
+}
+
+impl Default for BreedingConfig {
+    fn default() -> Self {
+        BreedingConfig {
+            fee: 100,
+            cooldown_seconds: 86_400,
+            mutation: 1,
+        }
+    }
+}
+
+impl BreedingConfig {
+    pub(crate) fn validate(&self) -> Result<(), ContractError> {
+        let invalid = |reason: &str| Err(ContractError::InvalidBreedingConfig { reason: reason.to_string() });
+
+        if self.cooldown_seconds == 0 {
+            return invalid("cooldown must not be zero");
+        }
+        if self.mutation > MAX_BREEDING_MUTATION {
+            return invalid(&format!("mutation must not be above {}", MAX_BREEDING_MUTATION));
+        }
+        Ok(())
+    }
+}
+
+/// where a pet comes from, minted pets have no parents and are generation 0
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PetLineage {
+    pub parents: Option<(String, String)>,
+    pub generation: u32,
+    // the last time the pet bred, its breeding cooldown runs from here
+    pub last_bred: Option<Timestamp>
+}
+
+pub(crate) fn load_privacy_policy(storage: &dyn Storage) -> StdResult<PetPrivacyPolicy> {
+    Ok(PRIVACY_POLICY.may_load(storage)?.unwrap_or_default())
+}
+
+/// which parts of a pet anyone can query without a permit, owners see their whole pets through
+/// `MyPets`. By default the rolled max stats and what the pet is doing stay with the owner
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct PetPrivacyPolicy {
+    pub current_stats: bool,
+    pub max_stats: bool,
+    pub progression: bool,
+    pub energy: bool,
+    pub record: bool,
+    // whether the pet is on a quest
+    pub quest_status: bool,
+    // the pending challenges against the pet
+    pub open_challenges: bool,
+    // the parents, generation and breeding cooldown of the pet, policies set before lineage
+    // could be hidden keep it private
+    #[serde(default)]
+    pub lineage: bool
+}
+
+impl Default for PetPrivacyPolicy {
+    fn default() -> Self {
+        PetPrivacyPolicy {
+            current_stats: true,
+            max_stats: false,
+            progression: true,
+            energy: true,
+            record: true,
+            quest_status: false,
+            open_challenges: true,
+            lineage: true,
+        }
+    }
+}
+
+/// the parts of a pet the privacy policy makes public, private parts are none
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct PublicPetInfo {
+    pub pet_id: String,
+    pub current: Option<PetStats>,
+    pub max: Option<PetStats>,
+    pub progression: Option<PetProgression>,
+    pub energy: Option<u16>,
+    pub record: Option<BattleRecord>,
+    pub on_quest: Option<bool>,
+    // always public, the pet721 token shows the same attributes
+    pub traits: Option<PetTraits>,
+    pub name: Option<String>
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct ContractData {
+    pub hash: String,
+    pub addr: String
 }
 
 pub(crate) static PET_COUNTER: Item<u64> = Item::new(PET_COUNTER_KEY);
@@ -43,13 +489,40 @@ pub(crate) static BATTLE_COUNTER: Item<u64> = Item::new(BATTLE_COUNTER_KEY);
 pub(crate) static LOOT20_DATA: Item<ContractData> = Item::new(LOOT20_KEY);
 pub(crate) static PET721_DATA: Item<ContractData> = Item::new(PET721_KEY);
 
-pub(crate) static PETS: Keymap<String, PetState> = Keymap::new(PETS_KEY);
-pub(crate) static BATTLES: Keymap<u64, BattleInfo> = Keymap::new(BATTLE_KEY);
-pub(crate) static PET_BATTLES: Keymap<String, Vec<u64>> = Keymap::new(PET_BATTLES_KEY);
+pub(crate) static PRNG_SEED: Item<[u8; 32]> = Item::new(PRNG_SEED_KEY);
+pub(crate) static ENTROPY_COMMITMENTS: Keymap<CanonicalAddr, EntropyCommitment> = Keymap::new(ENTROPY_COMMITMENTS_KEY);
 
-pub(crate) static QUESTS: Keymap<CanonicalAddr, Vec<Quest>> = Keymap::new(QUESTS_KEY);
-pub(crate) static QUEST_HISTORY: Keymap<CanonicalAddr, Vec<QuestHistory>> = Keymap::new(QUEST_HISTORY_KEY);
+pub(crate) static PETS: Keymap<String, PetState, Json> = Keymap::new(PETS_KEY);
+pub(crate) static BATTLES: Keymap<u64, BattleInfo, Json> = Keymap::new(BATTLE_KEY);
+// kept apart from the battles so that listing battles does not load every log
+pub(crate) static BATTLE_LOGS: Keymap<u64, BattleLog, Json> = Keymap::new(BATTLE_LOGS_KEY);
+pub(crate) static PET_BATTLES: Keymap<String, Vec<u64>> = Keymap::new(PET_BATTLES_KEY);
+// the rating of every pet that has fought a battle by pet id, suffixed with the bucket of the
+// rating, and the buckets that hold pets from the highest down
+pub(crate) static LEADERBOARD: Keymap<String, u32> = Keymap::new(LEADERBOARD_KEY);
+pub(crate) static LEADERBOARD_BUCKETS: Item<Vec<u32>> = Item::new(LEADERBOARD_BUCKETS_KEY);
+// queued pets by wager in the order they entered, and the wager every queued pet is under
+pub(crate) static MATCHMAKING_QUEUE: Keymap<u64, Vec<MatchmakingEntry>, Json> = Keymap::new(MATCHMAKING_QUEUE_KEY);
+pub(crate) static MATCHMAKING_PETS: Keymap<String, u64> = Keymap::new(MATCHMAKING_PETS_KEY);
+pub(crate) static TOURNAMENT_COUNTER: Item<u64> = Item::new(TOURNAMENT_COUNTER_KEY);
+pub(crate) static TOURNAMENTS: Keymap<u64, Tournament, Json> = Keymap::new(TOURNAMENTS_KEY);
+// the tournament every pet registered for a tournament that has not been played yet is in
+pub(crate) static TOURNAMENT_PETS: Keymap<String, u64> = Keymap::new(TOURNAMENT_PETS_KEY);
+
+pub(crate) static QUEST_CATALOGUE: Keymap<String, QuestDefinition, Json> = Keymap::new(QUEST_CATALOGUE_KEY);
+pub(crate) static QUESTS: Keymap<CanonicalAddr, Vec<Quest>, Json> = Keymap::new(QUESTS_KEY);
+// every claimed quest in claim order, the player and pet histories hold positions in it and
+// are suffixed with the player's canonical address and the pet id
+pub(crate) static QUEST_LOG: AppendStore<QuestHistory, Json> = AppendStore::new(QUEST_LOG_KEY);
+pub(crate) static PLAYER_QUEST_HISTORY: AppendStore<u32> = AppendStore::new(PLAYER_QUEST_HISTORY_KEY);
+pub(crate) static PET_QUEST_HISTORY: AppendStore<u32> = AppendStore::new(PET_QUEST_HISTORY_KEY);
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub(crate) struct EntropyCommitment {
+    // sha256 of the secret that will be revealed
+    pub(crate) commitment: Binary,
+    pub(crate) height: u64
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub(crate) struct BattleInfo {
@@ -58,40 +531,594 @@ pub(crate) struct BattleInfo {
     pub(crate) pet_id: String,
     pub(crate) other_pet_id: String,
     pub(crate) wager: u64,
-    pub(crate) status: String,
+    pub(crate) status: BattleStatus,
+
+    // none until the battle is fought, pet_id is the challenger
+    pub(crate) outcome: Option<BattleOutcome>,
+
+    // wagers are held by the game, battles from before escrow burned them instead
+    #[serde(default)]
+    pub(crate) escrowed: bool,
+
+    // a pending challenge can no longer be accepted once expired, challenges from before
+    // expirations were added never expire
+    #[serde(default)]
+    pub(crate) expiration: Expiration
+}
+
+impl BattleInfo {
+    pub(crate) fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.status == BattleStatus::Pending && self.expiration.is_expired(block)
+    }
+
+    /// the pet that won the battle, none until it is accepted or if it was a draw
+    pub(crate) fn winner(&self) -> Option<&String> {
+        match self.outcome {
+            Some(BattleOutcome::ChallengerWin) => Some(&self.pet_id),
+            Some(BattleOutcome::DefenderWin) => Some(&self.other_pet_id),
+            _ => None,
+        }
+    }
+
+    /// what the pet can claim once the battle is fought: the winner takes both wagers and a
+    /// draw returns each pet its own wager
+    pub(crate) fn payout(&self, pet_id: &str) -> StdResult<Uint128> {
+        let wager = Uint128::from(self.wager);
+        if self.outcome == Some(BattleOutcome::Draw) {
+            return Ok(wager);
+        }
+        match self.winner() {
+            Some(winner) if winner == pet_id => Ok(wager.checked_mul(Uint128::new(2))?),
+            _ => Ok(Uint128::zero())
+        }
+    }
+
+    pub(crate) fn without_initiator(self, block: &BlockInfo) -> BattleInfoWithoutInitiator {
+        let status = if self.is_expired(block) {
+            BattleStatus::Expired
+        } else {
+            self.status
+        };
+        BattleInfoWithoutInitiator {
+            id: self.id,
+            pet_id: self.pet_id,
+            other_pet_id: self.other_pet_id,
+            wager: self.wager,
+            status,
+            outcome: self.outcome,
+            expiration: self.expiration,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BattleStatus {
+    Pending,
+    Accepted,
+    // only reported by queries, the stored status stays pending until the wager is reclaimed
+    Expired
+}
+
+/// the same json and semantics as the pet721 `Expiration`, expired at the given point and after
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    // block height
+    AtHeight(u64),
+    // seconds since 01/01/1970
+    AtTime(u64),
+    #[default]
+    Never
+}
 
-    // true if pet_id wins
-    pub(crate) outcome: Option<bool>
+impl Expiration {
+    pub(crate) fn is_expired(&self, block: &BlockInfo) -> bool {
+        match self {
+            Expiration::AtHeight(height) => block.height >= *height,
+            Expiration::AtTime(time) => block.time.seconds() >= *time,
+            Expiration::Never => false,
+        }
+    }
+
+    /// whether the expiration comes at most max_seconds after the block, never is not
+    pub(crate) fn is_within(&self, block: &BlockInfo, max_seconds: u64) -> bool {
+        match self {
+            Expiration::AtHeight(height) => *height <= block.height.saturating_add(max_seconds / MIN_SECONDS_PER_BLOCK),
+            Expiration::AtTime(time) => *time <= block.time.seconds().saturating_add(max_seconds),
+            Expiration::Never => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BattleInfoWithoutInitiator {
+    pub id: u64,
+    pub pet_id: String,
+    pub other_pet_id: String,
+    pub wager: u64,
+    pub status: BattleStatus,
+
+    // none until the battle is fought, pet_id is the challenger
+    pub outcome: Option<BattleOutcome>,
+    pub expiration: Expiration
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentStatus {
+    Registration,
+    Finished,
+    // fewer than two pets registered before the deadline, the entry fees were refunded
+    Cancelled
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub(crate) struct BattleInfoWithoutInitiator {
+pub(crate) struct TournamentEntrant {
+    pub(crate) pet_id: String,
+    // the player that paid the entry fee and is paid the prize
+    pub(crate) owner: CanonicalAddr
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub(crate) struct Tournament {
     pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) creator: CanonicalAddr,
+    pub(crate) entry_fee: u64,
+    pub(crate) max_entrants: u32,
+    // pets can register until the deadline, the bracket is played once it passes or the
+    // tournament is full
+    pub(crate) registration: Expiration,
+    // percent of the prize pool per place, starting with the champion
+    pub(crate) prize_split: Vec<u16>,
+    pub(crate) status: TournamentStatus,
+    pub(crate) entrants: Vec<TournamentEntrant>,
+    pub(crate) matches: Vec<TournamentMatch>,
+    pub(crate) placements: Vec<Placement>
+}
+
+impl Tournament {
+    pub(crate) fn is_full(&self) -> bool {
+        self.entrants.len() >= self.max_entrants as usize
+    }
+
+    pub(crate) fn prize_pool(&self) -> StdResult<u64> {
+        self.entry_fee.checked_mul(self.entrants.len() as u64)
+            .ok_or_else(|| StdError::generic_err("prize pool overflow"))
+    }
+
+    pub(crate) fn info(&self) -> StdResult<TournamentInfo> {
+        Ok(TournamentInfo {
+            id: self.id,
+            name: self.name.clone(),
+            entry_fee: self.entry_fee,
+            max_entrants: self.max_entrants,
+            registration: self.registration,
+            prize_split: self.prize_split.clone(),
+            prize_pool: self.prize_pool()?,
+            status: self.status.clone(),
+            entrants: self.entrants.iter().map(|entrant| entrant.pet_id.clone()).collect(),
+            matches: self.matches.clone(),
+            placements: self.placements.clone(),
+        })
+    }
+
+    /// the matches the pet fought and where it placed, none if the pet did not register
+    pub(crate) fn progress(&self, pet_id: &str) -> Option<EntrantProgress> {
+        if !self.entrants.iter().any(|entrant| entrant.pet_id == pet_id) {
+            return None;
+        }
+        let matches: Vec<TournamentMatch> = self.matches.iter()
+            .filter(|m| m.pet_id == pet_id || m.other_pet_id.as_deref() == Some(pet_id))
+            .cloned()
+            .collect();
+        Some(EntrantProgress {
+            tournament_id: self.id,
+            pet_id: pet_id.to_string(),
+            status: self.status.clone(),
+            wins: matches.iter()
+                .filter(|m| m.winner == pet_id && m.other_pet_id.is_some())
+                .count() as u16,
+            matches,
+            placement: self.placements.iter().find(|placement| placement.pet_id == pet_id).cloned(),
+        })
+    }
+}
+
+/// the tournament without the addresses of the players
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TournamentInfo {
+    pub id: u64,
+    pub name: String,
+    pub entry_fee: u64,
+    pub max_entrants: u32,
+    pub registration: Expiration,
+    pub prize_split: Vec<u16>,
+    pub prize_pool: u64,
+    pub status: TournamentStatus,
+    pub entrants: Vec<String>,
+    pub matches: Vec<TournamentMatch>,
+    pub placements: Vec<Placement>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EntrantProgress {
+    pub tournament_id: u64,
+    pub pet_id: String,
+    pub status: TournamentStatus,
+    // byes are not counted
+    pub wins: u16,
+    pub matches: Vec<TournamentMatch>,
+    pub placement: Option<Placement>
+}
+
+/// a pet waiting for an opponent, its wager is held in escrow while it waits
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub(crate) struct MatchmakingEntry {
     pub(crate) pet_id: String,
-    pub(crate) other_pet_id: String,
+    // the player that paid the wager
+    pub(crate) owner: CanonicalAddr,
     pub(crate) wager: u64,
-    pub(crate) status: String,
+    pub(crate) rating: u32
+}
+
+impl MatchmakingEntry {
+    pub(crate) fn matches(&self, other: &MatchmakingEntry) -> bool {
+        let bucket = self.rating / RATING_BUCKET_SIZE;
+        let other_bucket = other.rating / RATING_BUCKET_SIZE;
+        self.wager == other.wager && self.owner != other.owner && bucket.abs_diff(other_bucket) <= 1
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PetState {
+    pub pet_id: String,
+    pub on_quest: Option<Quest>,
+    pub current: PetStats,
+    pub max: PetStats,
+    // derived fields, see `derive`
+    pub upgrade_costs: UpgradeCosts,
+    #[serde(default)]
+    pub power: u32,
+    #[serde(default)]
+    pub battle_rating: u32,
+    #[serde(default)]
+    pub upgrades: PetUpgrades,
+    #[serde(default)]
+    pub progression: PetProgression,
+    #[serde(default)]
+    pub energy: PetEnergy,
+    #[serde(default)]
+    pub record: BattleRecord,
+    #[serde(default)]
+    pub lineage: PetLineage,
+    #[serde(default)]
+    pub traits: Option<PetTraits>,
+    #[serde(default)]
+    pub name: Option<String>
+}
+
+impl PetState {
+    /// a new pet with the given stats as its base, derived fields are filled when it is saved
+    pub(crate) fn new(pet_id: String, current: PetStats, max: PetStats, lineage: PetLineage) -> Self {
+        PetState {
+            pet_id,
+            on_quest: None,
+            current: current.clone(),
+            max,
+            upgrade_costs: UpgradeCosts::default(),
+            power: 0,
+            battle_rating: 0,
+            upgrades: PetUpgrades {
+                base: Some(current),
+                ..PetUpgrades::default()
+            },
+            progression: PetProgression::default(),
+            energy: PetEnergy::default(),
+            record: BattleRecord::default(),
+            lineage,
+            traits: None,
+            name: None
+        }
+    }
+
+    /// recomputes the fields derived from the stats and record: the cost of the next point of
+    /// each stat, the power score summing the stats and the battle rating. Runs on every save
+    /// and when pets are queried, so that they never drift from what upgrades charge
+    pub(crate) fn derive(&mut self, config: &UpgradeConfig) {
+        self.upgrade_costs = config.upgrade_costs(&self.current);
+        self.power = self.current.total();
+        self.battle_rating = self.record.rating;
+    }
+
+    fn record_base(&mut self) {
+        if self.upgrades.base.is_none() {
+            self.upgrades.base = Some(self.current.clone());
+        }
+    }
+
+    /// raises the stats and returns the loot the upgrades cost
+    pub(crate) fn apply_upgrades(
+        &mut self,
+        upgrades: &[(Stat, u16)],
+        config: &UpgradeConfig
+    ) -> Result<u64, ContractError> {
+        if upgrades.is_empty() || upgrades.iter().any(|(_, points)| *points == 0) {
+            return Err(ContractError::InvalidUpgrade { reason: "every upgrade must raise a stat".to_string() });
+        }
+        let mut upgraded = self.current.clone();
+        for (stat, points) in upgrades {
+            let value = upgraded.get_stat(stat.clone()).saturating_add(*points);
+            if value > self.max.get_stat(stat.clone()) {
+                return Err(ContractError::StatMaxed);
+            }
+            upgraded.set_stat(stat.clone(), value);
+        }
+
+        let cost = config.cost_of_upgrades(&self.current, upgrades);
+        self.record_base();
+        self.current = upgraded;
+        self.upgrades.loot_spent = self.upgrades.loot_spent.saturating_add(cost);
+        self.derive(config);
+        Ok(cost)
+    }
+
+    pub(crate) fn spend_stat_points(
+        &mut self,
+        stat: Stat,
+        points: u16,
+        config: &UpgradeConfig
+    ) -> Result<(), ContractError> {
+        let have = self.progression.stat_points;
+        if points == 0 || points > have {
+            return Err(ContractError::InsufficientStatPoints { needed: points.max(1), have });
+        }
+        let new_value = self.current.get_stat(stat.clone()).saturating_add(points);
+        if new_value > self.max.get_stat(stat.clone()) {
+            return Err(ContractError::StatMaxed);
+        }
+
+        self.record_base();
+        self.current.set_stat(stat, new_value);
+        self.progression.stat_points -= points;
+        self.upgrades.stat_points_spent += points;
+        self.derive(config);
+        Ok(())
+    }
+
+    /// resets the stats to the base and gives back the spent stat points, returns the loot
+    /// to refund
+    pub(crate) fn respec(&mut self, config: &UpgradeConfig) -> u64 {
+        let refund = config.respec_refund(self.upgrades.loot_spent);
+        if let Some(base) = &self.upgrades.base {
+            self.current = base.clone();
+        }
+        self.progression.stat_points += self.upgrades.stat_points_spent;
+        self.upgrades.loot_spent = 0;
+        self.upgrades.stat_points_spent = 0;
+        self.derive(config);
+        refund
+    }
+
+    pub(crate) fn public_info(&self, policy: &PetPrivacyPolicy, now: Timestamp) -> PublicPetInfo {
+        PublicPetInfo {
+            pet_id: self.pet_id.clone(),
+            current: policy.current_stats.then(|| self.current.clone()),
+            max: policy.max_stats.then(|| self.max.clone()),
+            progression: policy.progression.then(|| self.progression.clone()),
+            energy: policy.energy.then(|| self.energy_at(now)),
+            record: policy.record.then(|| self.record.clone()),
+            on_quest: policy.quest_status.then(|| self.on_quest.is_some()),
+            traits: self.traits.clone(),
+            name: self.name.clone(),
+        }
+    }
+
+    pub(crate) fn max_energy(&self) -> u16 {
+        ENERGY_BASE.saturating_add(self.current.stamina.saturating_mul(ENERGY_PER_STAMINA))
+    }
+
+    pub(crate) fn energy_at(&self, now: Timestamp) -> u16 {
+        let mut energy = self.energy.clone();
+        energy.regenerate(now);
+        self.max_energy().saturating_sub(energy.used)
+    }
+
+    /// the seconds until the pet can breed again
+    pub(crate) fn breeding_cooldown(&self, config: &BreedingConfig, now: Timestamp) -> u64 {
+        match self.lineage.last_bred {
+            Some(last_bred) => last_bred.seconds()
+                .saturating_add(config.cooldown_seconds)
+                .saturating_sub(now.seconds()),
+            None => 0
+        }
+    }
+
+    pub(crate) fn seconds_until_full(&self, now: Timestamp) -> u64 {
+        let mut energy = self.energy.clone();
+        energy.regenerate(now);
+        if energy.used == 0 {
+            return 0;
+        }
+        energy.used as u64 * ENERGY_REGEN_SECONDS - (now.seconds() - energy.updated.seconds())
+    }
+
+    pub(crate) fn spend_energy(&mut self, cost: u16, now: Timestamp) -> Result<(), ContractError> {
+        let energy = self.energy_at(now);
+        if energy < cost {
+            return Err(ContractError::PetExhausted { energy, needed: cost });
+        }
+        self.energy.regenerate(now);
+        self.energy.used += cost;
+        Ok(())
+    }
+
+    /// adds the experience for a claimed quest and applies every level gained,
+    /// returns the number of levels gained
+    pub(crate) fn award_quest(&mut self, outcome: &QuestOutcome, config: &ProgressionConfig) -> u16 {
+        let progression = &mut self.progression;
+        progression.xp = progression.xp.saturating_add(config.xp_for(outcome));
+        match outcome {
+            QuestOutcome::Fail => progression.quests_failed += 1,
+            QuestOutcome::Pass => progression.quests_passed += 1,
+            QuestOutcome::ExceptionalPass => progression.quests_exceptional += 1,
+        }
+
+        let mut levels_gained = 0;
+        while let Some(next_level_xp) = config.next_level_xp(self.progression.level) {
+            if self.progression.xp < next_level_xp {
+                break;
+            }
+            self.progression.level = self.progression.level.saturating_add(1);
+            self.progression.stat_points = self.progression.stat_points.saturating_add(config.stat_points_per_level);
+            for stat in Stat::all() {
+                let max = self.max.get_stat(stat.clone());
+                let raised = max.saturating_add(config.max_stats_per_level).min(MAX_STAT_VALUE);
+                self.max.set_stat(stat, raised.max(max));
+            }
+            levels_gained = levels_gained.saturating_add(1);
+        }
+        levels_gained
+    }
+}
+
+/// the energy a pet spent and has not regenerated yet, stored as the amount used so that a
+/// stamina upgrade immediately raises the current energy
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct PetEnergy {
+    pub used: u16,
+    // regeneration is counted from here
+    pub updated: Timestamp
+}
+
+impl PetEnergy {
+    /// applies the whole points regenerated since the last update, the time towards the
+    /// next point is kept
+    fn regenerate(&mut self, now: Timestamp) {
+        let elapsed = now.seconds().saturating_sub(self.updated.seconds());
+        let points = (elapsed / ENERGY_REGEN_SECONDS).min(self.used as u64);
+        self.used -= points as u16;
+        self.updated = if self.used == 0 {
+            now
+        } else {
+            self.updated.plus_seconds(points * ENERGY_REGEN_SECONDS)
+        };
+    }
+}
+
+/// experience and lifetime quest counts of a pet, pets stored before progression was added
+/// start at level 1 without experience
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PetProgression {
+    pub level: u16,
+    pub xp: u64,
+    pub quests_failed: u32,
+    pub quests_passed: u32,
+    pub quests_exceptional: u32,
+    // unspent points from level ups
+    pub stat_points: u16
+}
+
+impl Default for PetProgression {
+    fn default() -> Self {
+        PetProgression {
+            level: 1,
+            xp: 0,
+            quests_failed: 0,
+            quests_passed: 0,
+            quests_exceptional: 0,
+            stat_points: 0,
+        }
+    }
+}
+
+/// elo rating and lifetime battle counts of a pet, pets stored before ratings were added
+/// start at the initial rating
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BattleRecord {
+    pub rating: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32
+}
+
+impl Default for BattleRecord {
+    fn default() -> Self {
+        BattleRecord {
+            rating: INITIAL_RATING,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+}
+
+impl BattleRecord {
+    pub(crate) fn record(&mut self, score: &BattleScore, opponent_rating: u32) {
+        let change = rating_change(self.rating, opponent_rating, score);
+        self.rating = (i64::from(self.rating) + change).max(0) as u32;
+        match score {
+            BattleScore::Win => self.wins += 1,
+            BattleScore::Draw => self.draws += 1,
+            BattleScore::Loss => self.losses += 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BattleScore {
+    Win,
+    Draw,
+    Loss
+}
 
-    // true if pet_id wins
-    pub(crate) outcome: Option<bool>
+// expected score in per mille of the higher rated pet, for rating differences of 0, 25, 50 ...
+// up to 400. Larger differences count as 400
+static EXPECTED_SCORES: [i64; 17] = [
+    500, 536, 571, 606, 640, 673, 703, 733, 760, 785, 808, 830, 849, 867, 882, 896, 909
+];
+
+/// the elo change of a pet, with the expected score 1 / (1 + 10^(-difference / 400)) looked
+/// up from a table to stay in integers
+pub(crate) fn rating_change(rating: u32, opponent_rating: u32, score: &BattleScore) -> i64 {
+    let difference = i64::from(rating) - i64::from(opponent_rating);
+    let step = ((difference.abs().min(400) + 12) / 25) as usize;
+    let expected = if difference >= 0 {
+        EXPECTED_SCORES[step]
+    } else {
+        1000 - EXPECTED_SCORES[step]
+    };
+    let actual = match score {
+        BattleScore::Win => 1000,
+        BattleScore::Draw => 500,
+        BattleScore::Loss => 0,
+    };
+    RATING_K_FACTOR * (actual - expected) / 1000
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub(crate) struct PetState {
+pub(crate) struct RatedPet {
     pub(crate) pet_id: String,
-    pub(crate) on_quest: Option<Quest>,
-    pub(crate) current: PetStats,
-    pub(crate) max: PetStats,
-    pub(crate) upgrade_costs: PetStats
+    pub(crate) rating: u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub pet_id: String,
+    // none when the privacy policy hides records
+    pub record: Option<BattleRecord>
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub(crate) struct PetStats {
-    pub(crate) health: u16,
-    pub(crate) strength: u16,
-    pub(crate) stamina: u16,
-    pub(crate) intelligence: u16,
-    pub(crate) luck: u16,
+pub struct PetStats {
+    pub health: u16,
+    pub strength: u16,
+    pub stamina: u16,
+    pub intelligence: u16,
+    pub luck: u16,
 }
 
 impl PetStats {
@@ -111,6 +1138,10 @@ impl PetStats {
         }
     }
 
+    pub(crate) fn total(&self) -> u32 {
+        Stat::all().into_iter().map(|stat| u32::from(self.get_stat(stat))).sum()
+    }
+
     pub fn get_stat(&self, stat: Stat) -> u16 {
         match stat {
             Stat::Health => self.health,
@@ -132,8 +1163,9 @@ impl PetStats {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
-pub(crate) enum Stat {
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stat {
     Health,
     Strength,
     Stamina,
@@ -142,179 +1174,410 @@ pub(crate) enum Stat {
 }
 
 impl Stat {
-    pub(crate) fn from_string(str: String) -> Self {
-        match str.as_str() {
-            "Health" => Stat::Health,
-            "Strength" => Stat::Strength,
-            "Stamina" => Stat::Stamina,
-            "Intelligence" => Stat::Intelligence,
-            "Luck" => Stat::Luck,
-            _ => Stat::Health
+    pub(crate) fn all() -> [Stat; 5] {
+        [Stat::Health, Stat::Strength, Stat::Stamina, Stat::Intelligence, Stat::Luck]
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Stat::Health => "health",
+            Stat::Strength => "strength",
+            Stat::Stamina => "stamina",
+            Stat::Intelligence => "intelligence",
+            Stat::Luck => "luck",
         }
     }
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
-pub(crate) struct QuestSummary {
-    pub(crate) status: String,
-    pub(crate) quest_type: String,
-    pub(crate) finished_exploring: Option<Timestamp>,
-    pub(crate) finished_cooldown: Option<Timestamp>,
-    pub(crate) outcome: Option<String>,
-    pub(crate) loot: Loot,
+pub struct QuestSummary {
+    pub status: String,
+    pub quest_type: String,
+    pub finished_exploring: Option<Timestamp>,
+    pub finished_cooldown: Option<Timestamp>,
+    pub outcome: Option<QuestOutcome>,
+    pub loot: Loot,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
-pub(crate) struct Loot {
-    pub(crate) fail: u16,
-    pub(crate) pass: u16,
-    pub(crate) exceptional_pass: u16,
+pub struct Loot {
+    pub fail: u16,
+    pub pass: u16,
+    pub exceptional_pass: u16,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
-pub(crate) struct Quest {
-    pub(crate) pet_id: Option<String>,
-    pub(crate) quest_type: String,
-    pub(crate) awaiting_claiming: bool,
-    pub(crate) finished_exploring: Option<Timestamp>,
-    pub(crate) finished_cooldown: Option<Timestamp>,
-    pub(crate) base_loot: u16,
-    pub(crate) difficulty: u16,
-    pub(crate) difficulty_increment: u16
+pub struct Quest {
+    pub pet_id: Option<String>,
+    // id of the quest definition in the catalogue
+    pub quest_type: String,
+    pub awaiting_claiming: bool,
+    #[serde(default)]
+    pub started_exploring: Option<Timestamp>,
+    pub finished_exploring: Option<Timestamp>,
+    pub finished_cooldown: Option<Timestamp>,
+    pub base_loot: u16,
+    pub difficulty: u16,
+    pub difficulty_increment: u16,
+    // rolled when the quest is started, quests started before dice rolls were added are
+    // rolled when they are claimed
+    #[serde(default)]
+    pub outcome: Option<QuestOutcome>
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
-pub(crate) struct QuestHistory {
-    pub(crate) pet_id: String,
-    pub(crate) quest_type: String,
-    pub(crate) time_started: Timestamp,
-    pub(crate) time_ended: Timestamp,
-    pub(crate) loot_collected: u16,
-    pub(crate) outcome: String
+pub struct QuestHistory {
+    pub pet_id: String,
+    pub quest_type: String,
+    pub time_started: Timestamp,
+    pub time_ended: Timestamp,
+    pub loot_collected: u16,
+    pub outcome: QuestOutcome
+}
+
+/// narrows a quest history down to one quest type and/or outcome
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, Eq, PartialEq)]
+pub(crate) struct QuestHistoryFilter {
+    pub(crate) quest_type: Option<String>,
+    pub(crate) outcome: Option<QuestOutcome>
+}
+
+impl QuestHistoryFilter {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.quest_type.is_none() && self.outcome.is_none()
+    }
+
+    pub(crate) fn matches(&self, entry: &QuestHistory) -> bool {
+        self.quest_type.as_ref().map_or(true, |quest_type| *quest_type == entry.quest_type) &&
+            self.outcome.as_ref().map_or(true, |outcome| *outcome == entry.outcome)
+    }
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
-pub(crate) enum QuestOutcome {
+#[serde(rename_all = "snake_case")]
+pub enum QuestOutcome {
     Fail,
     Pass,
     ExceptionalPass
 }
 
-impl QuestOutcome {
-    pub(crate) fn to_string(self) -> String {
-        match self {
-            QuestOutcome::Fail => "Fail".to_string(),
-            QuestOutcome::Pass => "Pass".to_string(),
-            QuestOutcome::ExceptionalPass => "Exceptional Pass".to_string(),
-        }
+/// an inclusive range that quest values are rolled from
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
+pub struct QuestRange {
+    pub min: u16,
+    pub max: u16
+}
+
+impl QuestRange {
+    pub(crate) fn new(min: u16, max: u16) -> Self {
+        QuestRange { min, max }
+    }
+
+    fn roll(&self, rng: &mut SmallRng) -> u16 {
+        rng.gen_range(self.min..=self.max)
     }
 }
 
-pub(crate) enum QuestType {
-    TrialOfResilience,
-    TrialOfTitans,
-    TrialOfEndurance,
-    TrialOfWisdom
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
+pub struct StatWeight {
+    pub stat: Stat,
+    pub weight: u16
 }
 
-impl QuestType {
-    fn to_string(self) -> String {
-        match self {
-            QuestType::TrialOfResilience => {
-                "Trial Of Resilience".to_string()
-            }
-            QuestType::TrialOfTitans => {
-                "Trial Of Titans".to_string()
-            }
-            QuestType::TrialOfEndurance => {
-                "Trial Of Endurance".to_string()
-            }
-            QuestType::TrialOfWisdom => {
-                "Trial Of Wisdom".to_string()
-            }
+/// an entry of the admin managed quest catalogue
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
+pub struct QuestDefinition {
+    // stable id, used as the quest_type of player quests
+    pub id: String,
+    pub name: String,
+    // the pet's stats are averaged with these weights before luck is added
+    pub stat_weights: Vec<StatWeight>,
+    pub explore_seconds: u64,
+    // counted from the start of the quest
+    pub cooldown_seconds: u64,
+    pub base_loot: QuestRange,
+    pub difficulty: QuestRange,
+    pub growth_on_pass: QuestRange,
+    pub growth_on_exceptional_pass: QuestRange,
+    // disabled quests stay visible but cannot be started
+    pub enabled: bool
+}
+
+impl QuestDefinition {
+    fn trial(id: &str, name: &str, stat: Stat) -> Self {
+        QuestDefinition {
+            id: id.to_string(),
+            name: name.to_string(),
+            stat_weights: vec![StatWeight { stat, weight: 1 }],
+            explore_seconds: DEFAULT_QUEST_EXPLORE_SECONDS,
+            cooldown_seconds: DEFAULT_QUEST_COOLDOWN_SECONDS,
+            base_loot: QuestRange::new(1, 5),
+            difficulty: QuestRange::new(1, 3),
+            growth_on_pass: QuestRange::new(0, 1),
+            growth_on_exceptional_pass: QuestRange::new(1, 2),
+            enabled: true,
         }
     }
 
-    fn from_string(string: String) -> Self {
-        match string.as_str() {
-            "Trial Of Resilience" => QuestType::TrialOfResilience,
-            "Trial Of Titans" => QuestType::TrialOfTitans,
-            "Trial Of Endurance" => QuestType::TrialOfEndurance,
-            "Trial Of Wisdom" => QuestType::TrialOfWisdom,
-            _ => QuestType::TrialOfTitans
+    pub(crate) fn validate(&self) -> Result<(), ContractError> {
+        let invalid = |reason: &str| Err(ContractError::InvalidQuestDefinition { reason: reason.to_string() });
+
+        if self.id.is_empty() || !self.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+            return invalid("id must be snake_case");
+        }
+        if self.stat_weights.iter().map(|weight| weight.weight as u32).sum::<u32>() == 0 {
+            return invalid("stat weights must not all be zero");
+        }
+        if self.explore_seconds == 0 || self.cooldown_seconds < self.explore_seconds {
+            return invalid("cooldown must be at least the explore time, which must not be zero");
+        }
+        let ranges = [&self.base_loot, &self.difficulty, &self.growth_on_pass, &self.growth_on_exceptional_pass];
+        if ranges.iter().any(|range| range.min > range.max) {
+            return invalid("range min must not be above max");
+        }
+        if self.base_loot.max > MAX_QUEST_BASE_LOOT {
+            return invalid(&format!("base loot must not be above {}", MAX_QUEST_BASE_LOOT));
+        }
+        if self.difficulty.max > MAX_QUEST_DIFFICULTY {
+            return invalid(&format!("difficulty must not be above {}", MAX_QUEST_DIFFICULTY));
+        }
+        if self.growth_on_pass.max > MAX_QUEST_GROWTH || self.growth_on_exceptional_pass.max > MAX_QUEST_GROWTH {
+            return invalid(&format!("growth must not be above {}", MAX_QUEST_GROWTH));
+        }
+        Ok(())
+    }
+
+    /// the weighted average of the pet's stats for this quest
+    pub(crate) fn stat_value(&self, stats: &PetStats) -> u16 {
+        let total_weight: u32 = self.stat_weights.iter().map(|weight| weight.weight as u32).sum();
+        let weighted: u32 = self.stat_weights.iter()
+            .map(|weight| stats.get_stat(weight.stat.clone()) as u32 * weight.weight as u32)
+            .sum();
+        (weighted / total_weight.max(1)) as u16
+    }
+
+    /// a fresh quest with its loot and difficulty rolled from the definition
+    fn roll_quest(&self, rng: &mut SmallRng) -> Quest {
+        Quest {
+            quest_type: self.id.clone(),
+            awaiting_claiming: false,
+            pet_id: None,
+            started_exploring: None,
+            finished_exploring: None,
+            finished_cooldown: None,
+            base_loot: self.base_loot.roll(rng),
+            difficulty: self.difficulty.roll(rng),
+            difficulty_increment: 0,
+            outcome: None,
+        }
+    }
+
+    /// a fresh quest at the bottom of every range, used when a quest added to the catalogue
+    /// is handed to an existing player without any randomness available
+    fn minimum_quest(&self) -> Quest {
+        Quest {
+            quest_type: self.id.clone(),
+            awaiting_claiming: false,
+            pet_id: None,
+            started_exploring: None,
+            finished_exploring: None,
+            finished_cooldown: None,
+            base_loot: self.base_loot.min,
+            difficulty: self.difficulty.min,
+            difficulty_increment: 0,
+            outcome: None,
         }
     }
 }
 
-pub(crate) struct QuestData {
-    stat: Stat
+/// the quests every catalogue starts with
+pub(crate) fn default_quest_catalogue() -> Vec<QuestDefinition> {
+    vec![
+        QuestDefinition::trial("trial_of_resilience", "Trial Of Resilience", Stat::Health),
+        QuestDefinition::trial("trial_of_endurance", "Trial Of Endurance", Stat::Stamina),
+        QuestDefinition::trial("trial_of_titans", "Trial Of Titans", Stat::Strength),
+        QuestDefinition::trial("trial_of_wisdom", "Trial Of Wisdom", Stat::Intelligence),
+    ]
 }
 
-impl QuestType {
-    fn get_quest_data(self) -> QuestData {
-        match self {
-            QuestType::TrialOfResilience => {
-                QuestData { stat: Stat::Health}
-            }
-            QuestType::TrialOfTitans => {
-                QuestData { stat: Stat::Strength}
-            }
-            QuestType::TrialOfEndurance => {
-                QuestData { stat: Stat::Stamina}
-            }
-            QuestType::TrialOfWisdom => {
-                QuestData { stat: Stat::Intelligence}
-            }
+pub(crate) fn save_default_quest_catalogue(storage: &mut dyn Storage) -> StdResult<()> {
+    for definition in default_quest_catalogue() {
+        QUEST_CATALOGUE.insert(storage, &definition.id, &definition)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn get_quest_definition(
+    quest_type: &str,
+    storage: &dyn Storage
+) -> Result<QuestDefinition, ContractError> {
+    match QUEST_CATALOGUE.get(storage, &quest_type.to_string()) {
+        Some(definition) => Ok(definition),
+        None => Err(ContractError::QuestNotFound)
+    }
+}
+
+pub(crate) fn get_quest_catalogue(storage: &dyn Storage) -> StdResult<Vec<QuestDefinition>> {
+    QUEST_CATALOGUE.iter(storage)?
+        .map(|entry| entry.map(|(_, definition)| definition))
+        .collect()
+}
+
+/// the quests of a player, with every catalogue quest they do not have yet appended. The
+/// appended quests are only stored once an execute saves the player's quests again
+pub(crate) fn load_player_quests(
+    addr: &CanonicalAddr,
+    storage: &dyn Storage
+) -> StdResult<Option<Vec<Quest>>> {
+    let mut quests = match QUESTS.get(storage, addr) {
+        Some(quests) => quests,
+        None => return Ok(None)
+    };
+
+    for definition in get_quest_catalogue(storage)? {
+        if !quests.iter().any(|quest| quest.quest_type == definition.id) {
+            quests.push(definition.minimum_quest());
         }
     }
+    Ok(Some(quests))
 }
 
+/// rolls the species, rarity and stats of a minted pet and stores it
 pub(crate) fn generate_new_pet(
     pet_id: String,
     storage: &mut dyn Storage,
     mut rng: SmallRng
-) -> StdResult<()> {
-    let max_stat_value = CONFIG.load(storage)?.max_stats;
-    let pet_stats = PetStats::new(
-        rng.gen_range(5..=8),
-        rng.gen_range(5..=8),
-        rng.gen_range(5..=8),
-        rng.gen_range(5..=8),
-        rng.gen_range(5..=8)
-    );
-
-    let pet_maxes = PetStats::new(
-        rng.gen_range(12..=max_stat_value),
-        rng.gen_range(12..=max_stat_value),
-        rng.gen_range(12..=max_stat_value),
-        rng.gen_range(12..=max_stat_value),
-        rng.gen_range(12..=max_stat_value),
-    );
-
-    let pet_state = PetState {
-        pet_id: pet_id.clone(),
-        on_quest: None,
-        current: pet_stats.clone(),
-        max: pet_maxes,
-        upgrade_costs: PetStats {
-            health: get_cost_of_stat_upgrade(&pet_stats, Stat::Health)?,
-            strength: get_cost_of_stat_upgrade(&pet_stats, Stat::Strength)?,
-            stamina: get_cost_of_stat_upgrade(&pet_stats, Stat::Stamina)?,
-            intelligence: get_cost_of_stat_upgrade(&pet_stats, Stat::Intelligence)?,
-            luck: get_cost_of_stat_upgrade(&pet_stats, Stat::Luck)?,
-        }
+) -> Result<PetState, ContractError> {
+    let max_stat_value = CONFIG.load(storage)?.max_stats.min(MAX_STAT_VALUE);
+    let config = load_species_config(storage)?;
+    let species = config.roll_species(&mut rng);
+    let tier = roll_rarity(&config, storage, &mut rng)?;
+    let (pet_stats, pet_maxes) = species.roll_stats(&tier, max_stat_value, &mut rng);
+
+    let mut pet_state = PetState::new(pet_id, pet_stats, pet_maxes, PetLineage::default());
+    pet_state.traits = Some(roll_traits(species, &tier, &mut rng));
+    save_pet(&mut pet_state, storage)?;
+    Ok(pet_state)
+}
+
+/// rolls a rarity with supply left and counts the new pet against its cap
+fn roll_rarity(
+    config: &SpeciesConfig,
+    storage: &mut dyn Storage,
+    rng: &mut SmallRng
+) -> Result<RarityTier, ContractError> {
+    let tier = config.roll_rarity(|rarity| RARITY_SUPPLY.get(storage, rarity).unwrap_or(0), rng)?.clone();
+    let minted = RARITY_SUPPLY.get(storage, &tier.rarity).unwrap_or(0);
+    RARITY_SUPPLY.insert(storage, &tier.rarity, &(minted + 1))?;
+    Ok(tier)
+}
+
+fn roll_traits(species: &Species, tier: &RarityTier, rng: &mut SmallRng) -> PetTraits {
+    PetTraits {
+        species: species.id.clone(),
+        rarity: tier.rarity,
+        cosmetics: species.roll_cosmetics(rng),
+    }
+}
+
+/// rolls the base and max stats of a child. Each stat is inherited as a value between the
+/// two parents, upgrades and spent stat points are not passed on, and then mutated by up to
+/// the configured amount either way
+pub(crate) fn breed_stats(
+    parent_a: &PetState,
+    parent_b: &PetState,
+    mutation: u16,
+    max_stat_value: u16,
+    rng: &mut SmallRng
+) -> (PetStats, PetStats) {
+    let base_a = parent_a.upgrades.base.as_ref().unwrap_or(&parent_a.current);
+    let base_b = parent_b.upgrades.base.as_ref().unwrap_or(&parent_b.current);
+    let mutation = i32::from(mutation);
+    let mut inherit = |a: u16, b: u16, lowest: u16, highest: u16| -> u16 {
+        let inherited = i32::from(rng.gen_range(a.min(b)..=a.max(b)));
+        let mutated = inherited + rng.gen_range(-mutation..=mutation);
+        mutated.clamp(i32::from(lowest), i32::from(highest)) as u16
     };
 
-    PETS.insert(storage, &pet_id, &pet_state)
+    let mut base = PetStats::new(0, 0, 0, 0, 0);
+    let mut max = PetStats::new(0, 0, 0, 0, 0);
+    for stat in Stat::all() {
+        let max_value = inherit(
+            parent_a.max.get_stat(stat.clone()),
+            parent_b.max.get_stat(stat.clone()),
+            MIN_MAX_STATS,
+            max_stat_value
+        );
+        let base_value = inherit(
+            base_a.get_stat(stat.clone()),
+            base_b.get_stat(stat.clone()),
+            MIN_BRED_STAT,
+            max_value
+        );
+        max.set_stat(stat.clone(), max_value);
+        base.set_stat(stat, base_value);
+    }
+    (base, max)
 }
 
-pub(crate) fn get_cost_of_stat_upgrade(
-    pet_stats: &PetStats,
-    stat: Stat
-) -> StdResult<u16> {
-    let pet_stat = pet_stats.get_stat(stat);
-    let cost_of_upgrade = pet_stat * 5;
-    Ok(cost_of_upgrade)
+/// stores the child of the two pets and starts the breeding cooldown of both parents, pets
+/// out on a quest cannot breed
+pub(crate) fn breed_pets(
+    child_id: String,
+    parent_a: &str,
+    parent_b: &str,
+    block: &BlockInfo,
+    storage: &mut dyn Storage,
+    mut rng: SmallRng
+) -> Result<PetState, ContractError> {
+    let now = block.time;
+    let config = load_breeding_config(storage)?;
+    let mut parents = vec![];
+    for pet_id in [parent_a, parent_b] {
+        let pet = match PETS.get(storage, &pet_id.to_string()) {
+            Some(pet) => pet,
+            None => return Err(ContractError::PetNotFound { pet_id: pet_id.to_string() })
+        };
+        if pet.on_quest.is_some() {
+            return Err(ContractError::PetOnQuest);
+        }
+        check_pet_free(pet_id, block, storage)?;
+        let seconds_left = pet.breeding_cooldown(&config, now);
+        if seconds_left > 0 {
+            return Err(ContractError::BreedingCooldown { pet_id: pet_id.to_string(), seconds_left });
+        }
+        parents.push(pet);
+    }
+
+    let max_stat_value = CONFIG.load(storage)?.max_stats.min(MAX_STAT_VALUE);
+    let (base, max) = breed_stats(&parents[0], &parents[1], config.mutation, max_stat_value, &mut rng);
+    let lineage = PetLineage {
+        parents: Some((parent_a.to_string(), parent_b.to_string())),
+        generation: parents[0].lineage.generation.max(parents[1].lineage.generation) + 1,
+        last_bred: None,
+    };
+    let mut child = PetState::new(child_id, base, max, lineage);
+
+    // the child is of the species of either parent and rolls its own rarity and cosmetics,
+    // children of pets without a species that is still configured get a new species
+    let species_config = load_species_config(storage)?;
+    let inherited: Vec<&Species> = parents.iter()
+        .filter_map(|parent| parent.traits.as_ref())
+        .filter_map(|traits| species_config.species(&traits.species))
+        .collect();
+    let species = match inherited.len() {
+        0 => species_config.roll_species(&mut rng),
+        len => inherited[rng.gen_range(0..len)]
+    };
+    let tier = roll_rarity(&species_config, storage, &mut rng)?;
+    child.traits = Some(roll_traits(species, &tier, &mut rng));
+    save_pet(&mut child, storage)?;
+
+    for parent in parents.iter_mut() {
+        parent.lineage.last_bred = Some(now);
+        save_pet(parent, storage)?;
+    }
+    Ok(child)
 }
 
 pub(crate) fn generate_new_quests_for_addr(
@@ -322,47 +1585,10 @@ pub(crate) fn generate_new_quests_for_addr(
     storage: &mut dyn Storage,
     mut rng: SmallRng
 ) -> StdResult<()> {
-    let mut quests = Vec::with_capacity(4);
-    quests.push(Quest {
-        quest_type: QuestType::TrialOfResilience.to_string(),
-        awaiting_claiming: false,
-        pet_id: None,
-        finished_exploring: None,
-        finished_cooldown: None,
-        base_loot: rng.gen_range(1..=5),
-        difficulty: rng.gen_range(1..=3),
-        difficulty_increment: 0,
-    });
-    quests.push(Quest {
-        quest_type: QuestType::TrialOfEndurance.to_string(),
-        awaiting_claiming: false,
-        pet_id: None,
-        finished_exploring: None,
-        finished_cooldown: None,
-        base_loot: rng.gen_range(1..=5),
-        difficulty: rng.gen_range(1..=3),
-        difficulty_increment: 0,
-    });
-    quests.push(Quest {
-        quest_type: QuestType::TrialOfTitans.to_string(),
-        awaiting_claiming: false,
-        pet_id: None,
-        finished_exploring: None,
-        finished_cooldown: None,
-        base_loot: rng.gen_range(1..=5),
-        difficulty: rng.gen_range(1..=3),
-        difficulty_increment: 0,
-    });
-    quests.push(Quest {
-        quest_type: QuestType::TrialOfWisdom.to_string(),
-        awaiting_claiming: false,
-        pet_id: None,
-        finished_exploring: None,
-        finished_cooldown: None,
-        base_loot: rng.gen_range(1..=5),
-        difficulty: rng.gen_range(1..=3),
-        difficulty_increment: 0,
-    });
+    let quests: Vec<Quest> = get_quest_catalogue(storage)?
+        .iter()
+        .map(|definition| definition.roll_quest(&mut rng))
+        .collect();
     QUESTS.insert(storage, &addr, &quests)
 }
 
@@ -372,27 +1598,29 @@ pub(crate) fn update_quest_after_claiming(
     storage: &mut dyn Storage,
     mut rng: SmallRng,
     outcome: QuestOutcome
-) -> Result<(), StdError> {
-    if let Some(mut quests) = QUESTS.get(storage, &addr) {
+) -> Result<(), ContractError> {
+    let definition = get_quest_definition(&quest_type, storage)?;
+    if let Some(mut quests) = load_player_quests(&addr, storage)? {
         for quest in quests.iter_mut() {
             if quest.quest_type == quest_type {
-                quest.base_loot = rng.gen_range(1..=5);
-                quest.difficulty = rng.gen_range(1..=3);
+                quest.base_loot = definition.base_loot.roll(&mut rng);
+                quest.difficulty = definition.difficulty.roll(&mut rng);
                 quest.pet_id = None;
+                quest.started_exploring = None;
                 quest.finished_exploring = None;
                 quest.awaiting_claiming = false;
-                match outcome {
-                    QuestOutcome::Pass => quest.difficulty_increment += rng.gen_range(0..=1),
-                    QuestOutcome::ExceptionalPass => quest.difficulty_increment += rng.gen_range(1..=2),
-                    _ => {}
-                }
+                quest.outcome = None;
+                let growth = match outcome {
+                    QuestOutcome::Pass => definition.growth_on_pass.roll(&mut rng),
+                    QuestOutcome::ExceptionalPass => definition.growth_on_exceptional_pass.roll(&mut rng),
+                    _ => 0
+                };
+                quest.difficulty_increment = quest.difficulty_increment.saturating_add(growth).min(MAX_QUEST_DIFFICULTY);
             }
         }
         QUESTS.insert(storage, &addr, &quests)?;
-        Ok(())
-    } else {
-        Ok(())
     }
+    Ok(())
 }
 
 pub(crate) fn check_pet_availability(
@@ -411,20 +1639,29 @@ pub(crate) fn check_quest_availability(
     quest_type: String,
     storage: &dyn Storage,
     timestamp: Timestamp,
-) -> bool {
-    if let Some(quests) = QUESTS.get(storage, &addr) {
+) -> Result<bool, ContractError> {
+    if !get_quest_definition(&quest_type, storage)?.enabled {
+        return Ok(false);
+    }
+
+    if let Some(quests) = load_player_quests(&addr, storage)? {
         for quest in quests.iter() {
             if quest.quest_type == quest_type {
                 if let Some(finished_exploring) = quest.finished_exploring {
                     if finished_exploring.seconds() > timestamp.seconds() {
-                        return false;
+                        return Ok(false);
+                    }
+                }
+                if let Some(finished_cooldown) = quest.finished_cooldown {
+                    if finished_cooldown.seconds() > timestamp.seconds() {
+                        return Ok(false);
                     }
                 }
-                return true;
+                return Ok(true);
             }
         }
     }
-    false
+    Ok(false)
 }
 
 pub(crate) fn check_quest_awaiting_claim(
@@ -453,17 +1690,21 @@ pub(crate) fn update_quest_after_starting_explore(
     addr: CanonicalAddr,
     quest_type: String,
     storage: &mut dyn Storage,
-    now: Timestamp
-) -> Result<(), StdError> {
-    if let Some(mut quests) = QUESTS.get(storage, &addr) {
+    now: Timestamp,
+    outcome: QuestOutcome
+) -> Result<(), ContractError> {
+    let definition = get_quest_definition(&quest_type, storage)?;
+    if let Some(mut quests) = load_player_quests(&addr, storage)? {
         for quest in quests.iter_mut() {
             if quest.quest_type == quest_type {
                 quest.pet_id = Some(pet_id.clone());
+                quest.started_exploring = Some(Timestamp::from_seconds(now.seconds()));
                 quest.finished_exploring = Option::from(Timestamp::from_seconds(
-                    now.seconds() + QUEST_EXPLORE_TIME_SECONDS));
+                    now.seconds() + definition.explore_seconds));
                 quest.finished_cooldown = Option::from(Timestamp::from_seconds(
-                    now.seconds() + QUEST_COOLDOWN_SECONDS));
+                    now.seconds() + definition.cooldown_seconds));
                 quest.awaiting_claiming = true;
+                quest.outcome = Some(outcome.clone());
             }
         }
         QUESTS.insert(storage, &addr, &quests)?;
@@ -472,6 +1713,46 @@ pub(crate) fn update_quest_after_starting_explore(
     Ok(())
 }
 
+/// remembers the metadata written to the pet's token, false if the token already had it
+pub(crate) fn record_token_metadata(
+    pet_id: &str,
+    metadata: &PetTokenMetadata,
+    storage: &mut dyn Storage
+) -> StdResult<bool> {
+    let pet_id = pet_id.to_string();
+    let digest = Binary::from(Sha256::digest(to_vec(metadata)?).to_vec());
+    if TOKEN_METADATA.get(storage, &pet_id).as_ref() == Some(&digest) {
+        return Ok(false);
+    }
+    TOKEN_METADATA.insert(storage, &pet_id, &digest)?;
+    Ok(true)
+}
+
+/// names are trimmed, printable and at most `MAX_PET_NAME_LENGTH` characters
+pub(crate) fn validate_pet_name(name: &str) -> Result<(), ContractError> {
+    let invalid = |reason: &str| Err(ContractError::InvalidPetName { reason: reason.to_string() });
+
+    if name.trim().is_empty() {
+        return invalid("names must not be empty");
+    }
+    if name.trim() != name {
+        return invalid("names must not start or end with whitespace");
+    }
+    if name.chars().count() > MAX_PET_NAME_LENGTH {
+        return invalid(&format!("names must be at most {} characters", MAX_PET_NAME_LENGTH));
+    }
+    if name.chars().any(char::is_control) {
+        return invalid("names must not contain control characters");
+    }
+    Ok(())
+}
+
+/// every write of a pet goes through here, so that its derived fields are always current
+pub(crate) fn save_pet(pet: &mut PetState, storage: &mut dyn Storage) -> StdResult<()> {
+    pet.derive(&load_upgrade_config(storage)?);
+    PETS.insert(storage, &pet.pet_id, pet)
+}
+
 pub(crate) fn update_pet_on_quest(
     addr: CanonicalAddr,
     pet_id: String,
@@ -490,7 +1771,7 @@ pub(crate) fn update_pet_on_quest(
         };
 
         pet.on_quest = quest;
-        PETS.insert(storage, &pet_id, &pet)?;
+        save_pet(&mut pet, storage)?;
     }
 
     Ok(())
@@ -499,27 +1780,59 @@ pub(crate) fn update_pet_on_quest(
 pub(crate) fn remove_pet_on_quest(
     pet_id: String,
     storage: &mut dyn Storage,
-) -> Result<(), StdError> {
+) -> Result<(), ContractError> {
     match PETS.get(storage, &pet_id) {
         Some(mut pet) => {
             pet.on_quest = None;
-            PETS.insert(storage, &pet_id, &pet)?;
+            save_pet(&mut pet, storage)?;
         }
-        None => {return Err(StdError::generic_err("Could not find pet"))}
+        None => {return Err(ContractError::PetNotFound { pet_id })}
     };
 
     Ok(())
 }
 
+pub(crate) fn spend_pet_energy(
+    pet_id: String,
+    cost: u16,
+    now: Timestamp,
+    storage: &mut dyn Storage,
+) -> Result<(), ContractError> {
+    let mut pet = match PETS.get(storage, &pet_id) {
+        Some(pet) => pet,
+        None => return Err(ContractError::PetNotFound { pet_id })
+    };
+    pet.spend_energy(cost, now)?;
+    save_pet(&mut pet, storage)?;
+    Ok(())
+}
+
+/// awards a claimed quest to the pet that went on it and returns the updated pet
+pub(crate) fn update_pet_progression(
+    pet_id: String,
+    outcome: &QuestOutcome,
+    storage: &mut dyn Storage,
+) -> Result<PetState, ContractError> {
+    let mut pet = match PETS.get(storage, &pet_id) {
+        Some(pet) => pet,
+        None => return Err(ContractError::PetNotFound { pet_id })
+    };
+    let config = load_progression_config(storage)?;
+    pet.award_quest(outcome, &config);
+    save_pet(&mut pet, storage)?;
+    Ok(pet)
+}
+
+/// appends the claimed quest to the log and to the histories of the player and the pet
 pub(crate) fn insert_quest_history(
     addr: CanonicalAddr,
     new_entry: QuestHistory,
     storage: &mut dyn Storage,
 ) -> Result<(), StdError> {
-    let mut history = QUEST_HISTORY.get(storage, &addr)
-        .unwrap_or_else(|| Vec::with_capacity(10));
-    history.push(new_entry);
-    QUEST_HISTORY.insert(storage, &addr, &history)?;
+    let position = QUEST_LOG.get_len(storage)?;
+    QUEST_LOG.push(storage, &new_entry)?;
+    PLAYER_QUEST_HISTORY.add_suffix(addr.as_slice()).push(storage, &position)?;
+    PET_QUEST_HISTORY.add_suffix(new_entry.pet_id.as_bytes()).push(storage, &position)?;
     Ok(())
 }
 
@@ -547,87 +1860,370 @@ pub(crate) fn remove_battle_for_pet(
     Ok(())
 }
 
-pub(crate) fn get_quest_history(
-    addr: CanonicalAddr,
+fn leaderboard_bucket(bucket: u32) -> Keymap<'static, String, u32> {
+    LEADERBOARD.add_suffix(&bucket.to_be_bytes())
+}
+
+/// moves the pet from the bucket of its previous rating to the bucket of its rating, or
+/// removes it without a rating. Only the pet's entries are written, the list of buckets only
+/// when a bucket is emptied or filled
+pub(crate) fn update_leaderboard(
+    pet_id: &str,
+    previous_rating: Option<u32>,
+    rating: Option<u32>,
+    storage: &mut dyn Storage,
+) -> StdResult<()> {
+    let pet_id = pet_id.to_string();
+    let bucket = rating.map(|rating| rating / LEADERBOARD_BUCKET_SIZE);
+    let mut buckets = LEADERBOARD_BUCKETS.may_load(storage)?.unwrap_or_default();
+    let mut buckets_changed = false;
+
+    if let Some(previous) = previous_rating.map(|rating| rating / LEADERBOARD_BUCKET_SIZE) {
+        let previous_bucket = leaderboard_bucket(previous);
+        if Some(previous) != bucket && previous_bucket.contains(storage, &pet_id) {
+            previous_bucket.remove(storage, &pet_id)?;
+            if previous_bucket.get_len(storage)? == 0 {
+                buckets.retain(|kept| *kept != previous);
+                buckets_changed = true;
+            }
+        }
+    }
+    if let (Some(bucket), Some(rating)) = (bucket, rating) {
+        leaderboard_bucket(bucket).insert(storage, &pet_id, &rating)?;
+        if let Err(index) = buckets.binary_search_by(|kept| bucket.cmp(kept)) {
+            buckets.insert(index, bucket);
+            buckets_changed = true;
+        }
+    }
+
+    if buckets_changed {
+        LEADERBOARD_BUCKETS.save(storage, &buckets)?;
+    }
+    Ok(())
+}
+
+/// the rating the pet is ranked with, none for pets that are not on the leaderboard
+pub(crate) fn get_leaderboard_rating(pet_id: &str, storage: &dyn Storage) -> Option<u32> {
+    let rating = PETS.get(storage, &pet_id.to_string())?.record.rating;
+    leaderboard_bucket(rating / LEADERBOARD_BUCKET_SIZE).get(storage, &pet_id.to_string())
+}
+
+/// the pets ranked after the given one with their ranks, from the highest rating down. Ties
+/// are ordered by pet id so that pages are stable, only the buckets of the page are loaded
+pub(crate) fn get_leaderboard_page(
+    start_after: Option<RatedPet>,
+    limit: usize,
     storage: &dyn Storage,
-) -> Result<Vec<QuestHistory>, StdError> {
-    let history = QUEST_HISTORY.get(storage, &addr);
-    let history = match history {
-        Some(history) => history,
-        None => return Err(StdError::generic_err("History not found"))
+) -> StdResult<Vec<(u32, RatedPet)>> {
+    let is_after_start = |rated: &RatedPet| match &start_after {
+        Some(start) => rated.rating < start.rating ||
+            (rated.rating == start.rating && rated.pet_id > start.pet_id),
+        None => true
     };
-    Ok(history)
+
+    let mut page = vec![];
+    let mut rank = 0;
+    for bucket in LEADERBOARD_BUCKETS.may_load(storage)?.unwrap_or_default() {
+        let store = leaderboard_bucket(bucket);
+        // the buckets above the start only count towards the ranks
+        if let Some(start) = &start_after {
+            if bucket > start.rating / LEADERBOARD_BUCKET_SIZE {
+                rank += store.get_len(storage)?;
+                continue;
+            }
+        }
+
+        let mut rated_pets = store.iter(storage)?
+            .map(|item| item.map(|(pet_id, rating)| RatedPet { pet_id, rating }))
+            .collect::<StdResult<Vec<RatedPet>>>()?;
+        rated_pets.sort_by(|a, b| b.rating.cmp(&a.rating).then_with(|| a.pet_id.cmp(&b.pet_id)));
+        for rated in rated_pets {
+            rank += 1;
+            if is_after_start(&rated) {
+                if page.len() >= limit {
+                    return Ok(page);
+                }
+                page.push((rank, rated));
+            }
+        }
+    }
+    Ok(page)
 }
 
-pub(crate) fn calculate_outcome(
-    addr: CanonicalAddr,
+/// rates both pets of a fought battle against their ratings from before the battle
+pub(crate) fn update_battle_records(
     pet_id: String,
-    quest_type: String,
-    storage: &dyn Storage,
-) -> Result<QuestOutcome, StdError> {
-    let pet = match PETS.get(storage, &pet_id) {
+    other_pet_id: String,
+    outcome: &BattleOutcome,
+    storage: &mut dyn Storage,
+) -> Result<(), ContractError> {
+    let mut pet = match PETS.get(storage, &pet_id) {
+        Some(pet) => pet,
+        None => return Err(ContractError::PetNotFound { pet_id })
+    };
+    let mut other_pet = match PETS.get(storage, &other_pet_id) {
         Some(pet) => pet,
-        None => return Err(StdError::generic_err("Pet not found"))
+        None => return Err(ContractError::PetNotFound { pet_id: other_pet_id })
     };
 
-    let quests = match QUESTS.get(storage, &addr) {
-        Some(quests) => quests,
-        None => return Err(StdError::generic_err("Quest not found"))
+    let (score, other_score) = match outcome {
+        BattleOutcome::ChallengerWin => (BattleScore::Win, BattleScore::Loss),
+        BattleOutcome::DefenderWin => (BattleScore::Loss, BattleScore::Win),
+        BattleOutcome::Draw => (BattleScore::Draw, BattleScore::Draw)
     };
+    let previous_ratings = [pet.record.rating, other_pet.record.rating];
+    pet.record.record(&score, previous_ratings[1]);
+    other_pet.record.record(&other_score, previous_ratings[0]);
 
-    let mut outcome: QuestOutcome =  QuestOutcome::Fail;
-    for quest in quests.iter() {
-        if quest.quest_type == quest_type.clone() {
-            let stat = QuestType::from_string(quest_type.clone()).get_quest_data().stat;
-            let current_stat_value = pet.current.get_stat(stat);
-            let current_luck = pet.current.get_stat(Stat::Luck);
-            let difficulty = quest.difficulty;
-            let times_won = quest.difficulty_increment;
-
-            let total_to_beat = difficulty + (2 * times_won);
-            let half_total_to_beat = ((total_to_beat + 1) / 2) as i16;
-
-            let total_stats = current_stat_value + ((current_luck + 1) / 2);
-
-            let difference = total_stats as i16 - total_to_beat as i16;
-            match difference {
-                _fail if difference < 0 => {
-                    outcome = QuestOutcome::Fail
-                }
-                _pass if difference >= 0 && difference < half_total_to_beat => {
-                    outcome = QuestOutcome::Pass
-                }
-                _exceptional_pass if difference >= half_total_to_beat => {
-                    outcome = QuestOutcome::ExceptionalPass
-                }
-                _ => {
-                    outcome = QuestOutcome::Fail
-                }
+    for (mut pet, previous_rating) in [pet, other_pet].into_iter().zip(previous_ratings) {
+        save_pet(&mut pet, storage)?;
+        update_leaderboard(&pet.pet_id, Some(previous_rating), Some(pet.record.rating), storage)?;
+    }
+    Ok(())
+}
+
+/// queues the pet behind the pets already waiting with the same wager
+pub(crate) fn enter_matchmaking(
+    entry: MatchmakingEntry,
+    storage: &mut dyn Storage,
+) -> Result<(), ContractError> {
+    if MATCHMAKING_PETS.contains(storage, &entry.pet_id) {
+        return Err(ContractError::AlreadyInMatchmaking { pet_id: entry.pet_id });
+    }
+    let wager = entry.wager;
+    let mut queue = MATCHMAKING_QUEUE.get(storage, &wager).unwrap_or_default();
+    MATCHMAKING_PETS.insert(storage, &entry.pet_id, &wager)?;
+    queue.push(entry);
+    MATCHMAKING_QUEUE.insert(storage, &wager, &queue)?;
+    Ok(())
+}
+
+/// removes the pet from the queue, returning its entry if it was queued
+pub(crate) fn leave_matchmaking(
+    pet_id: &str,
+    storage: &mut dyn Storage,
+) -> StdResult<Option<MatchmakingEntry>> {
+    let pet_id = pet_id.to_string();
+    let wager = match MATCHMAKING_PETS.get(storage, &pet_id) {
+        Some(wager) => wager,
+        None => return Ok(None)
+    };
+    MATCHMAKING_PETS.remove(storage, &pet_id)?;
+
+    let mut queue = MATCHMAKING_QUEUE.get(storage, &wager).unwrap_or_default();
+    let entry = queue.iter().position(|entry| entry.pet_id == pet_id)
+        .map(|index| queue.remove(index));
+    if queue.is_empty() {
+        MATCHMAKING_QUEUE.remove(storage, &wager)?;
+    } else {
+        MATCHMAKING_QUEUE.insert(storage, &wager, &queue)?;
+    }
+    Ok(entry)
+}
+
+/// the queued pets the entry can be paired with, longest waiting first
+pub(crate) fn get_matchmaking_candidates(
+    entry: &MatchmakingEntry,
+    storage: &dyn Storage,
+) -> Vec<MatchmakingEntry> {
+    MATCHMAKING_QUEUE.get(storage, &entry.wager)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|queued| entry.matches(queued))
+        .collect()
+}
+
+/// the challenges of the pet that can still be accepted
+pub(crate) fn get_pending_battles(
+    pet_id: &str,
+    block: &BlockInfo,
+    storage: &dyn Storage,
+) -> Vec<BattleInfo> {
+    PET_BATTLES.get(storage, &pet_id.to_string())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|battle_id| BATTLES.get(storage, battle_id))
+        .filter(|battle| battle.status == BattleStatus::Pending && !battle.is_expired(block))
+        .collect()
+}
+
+/// pets are locked while they have challenged another pet, wait in matchmaking or are
+/// registered for a tournament, so that their stats cannot change before they fight. Challenges
+/// a pet received do not lock it, as its owner never agreed to them. Cancelling or settling
+/// the challenge, leaving matchmaking or playing the tournament unlocks them
+pub(crate) fn check_pet_free(
+    pet_id: &str,
+    block: &BlockInfo,
+    storage: &dyn Storage,
+) -> Result<(), ContractError> {
+    if get_pending_battles(pet_id, block, storage).iter().any(|battle| battle.pet_id == pet_id) ||
+        MATCHMAKING_PETS.contains(storage, &pet_id.to_string()) ||
+        TOURNAMENT_PETS.contains(storage, &pet_id.to_string()) {
+        return Err(ContractError::PetInBattle { pet_id: pet_id.to_string() });
+    }
+    Ok(())
+}
+
+pub(crate) fn get_player_quest_history(
+    addr: &CanonicalAddr,
+    filter: &QuestHistoryFilter,
+    page: u32,
+    page_size: u32,
+    storage: &dyn Storage,
+) -> StdResult<Vec<QuestHistory>> {
+    get_quest_history_page(&PLAYER_QUEST_HISTORY.add_suffix(addr.as_slice()), filter, page, page_size, storage)
+}
+
+pub(crate) fn get_pet_quest_history(
+    pet_id: &str,
+    filter: &QuestHistoryFilter,
+    page: u32,
+    page_size: u32,
+    storage: &dyn Storage,
+) -> StdResult<Vec<QuestHistory>> {
+    get_quest_history_page(&PET_QUEST_HISTORY.add_suffix(pet_id.as_bytes()), filter, page, page_size, storage)
+}
+
+/// a page of the history, latest quests first. Filters apply before paging, so that pages of
+/// a filtered history are full, but then every skipped entry has to be loaded
+fn get_quest_history_page(
+    history: &AppendStore<u32>,
+    filter: &QuestHistoryFilter,
+    page: u32,
+    page_size: u32,
+    storage: &dyn Storage,
+) -> StdResult<Vec<QuestHistory>> {
+    let mut to_skip = page.saturating_mul(page_size) as usize;
+    // without a filter whole pages are skipped without loading their entries
+    let skipped_positions = if filter.is_empty() { std::mem::take(&mut to_skip) } else { 0 };
+
+    let mut entries: Vec<QuestHistory> = vec![];
+    for position in history.iter(storage)?.rev().skip(skipped_positions) {
+        if entries.len() >= page_size as usize {
+            break;
+        }
+        let entry = QUEST_LOG.get_at(storage, position?)?;
+        if !filter.matches(&entry) {
+            continue;
+        }
+        if to_skip > 0 {
+            to_skip -= 1;
+            continue;
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// the values a quest is resolved with, a d20 roll is added to the modifier and compared
+/// against the target. A natural 1 always fails and a natural 20 is always an exceptional pass
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
+pub struct QuestCheck {
+    // weighted quest stats plus half the pet's luck
+    pub modifier: u16,
+    // base difficulty plus two for every time the quest was passed
+    pub target: u16
+}
+
+/// chance of every quest outcome in basis points, they add up to 10000
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Eq, PartialEq)]
+pub struct QuestOdds {
+    pub fail: u16,
+    pub pass: u16,
+    pub exceptional_pass: u16
+}
+
+impl QuestCheck {
+    pub(crate) fn resolve(&self, roll: u16) -> QuestOutcome {
+        if roll <= 1 {
+            return QuestOutcome::Fail;
+        }
+        if roll >= QUEST_DIE_SIDES {
+            return QuestOutcome::ExceptionalPass;
+        }
+
+        let total = roll.saturating_add(self.modifier);
+        if total >= self.target.saturating_add(QUEST_EXCEPTIONAL_MARGIN) {
+            QuestOutcome::ExceptionalPass
+        } else if total >= self.target {
+            QuestOutcome::Pass
+        } else {
+            QuestOutcome::Fail
+        }
+    }
+
+    /// every face of the die is equally likely, so the odds are counted face by face
+    pub(crate) fn odds(&self) -> QuestOdds {
+        let face = 10_000 / QUEST_DIE_SIDES;
+        let mut odds = QuestOdds { fail: 0, pass: 0, exceptional_pass: 0 };
+        for roll in 1..=QUEST_DIE_SIDES {
+            match self.resolve(roll) {
+                QuestOutcome::Fail => odds.fail += face,
+                QuestOutcome::Pass => odds.pass += face,
+                QuestOutcome::ExceptionalPass => odds.exceptional_pass += face,
             }
         }
+        odds
     }
+}
+
+pub(crate) fn roll_quest_die(rng: &mut SmallRng) -> u16 {
+    rng.gen_range(1..=QUEST_DIE_SIDES)
+}
 
-    Ok(outcome)
+pub(crate) fn get_quest_check(
+    addr: &CanonicalAddr,
+    pet_id: String,
+    quest_type: &str,
+    storage: &dyn Storage,
+) -> Result<QuestCheck, ContractError> {
+    let pet = match PETS.get(storage, &pet_id) {
+        Some(pet) => pet,
+        None => return Err(ContractError::PetNotFound { pet_id })
+    };
+    let quest = get_quest(addr.clone(), quest_type.to_string(), storage)?;
+    let definition = get_quest_definition(quest_type, storage)?;
+
+    let luck = pet.current.get_stat(Stat::Luck);
+    Ok(QuestCheck {
+        modifier: definition.stat_value(&pet.current).saturating_add(luck / 2 + luck % 2),
+        target: QUEST_BASE_TARGET
+            .saturating_add(quest.difficulty)
+            .saturating_add(quest.difficulty_increment.saturating_mul(2)),
+    })
+}
+
+pub(crate) fn calculate_outcome(
+    addr: CanonicalAddr,
+    pet_id: String,
+    quest_type: String,
+    storage: &dyn Storage,
+    roll: u16,
+) -> Result<QuestOutcome, ContractError> {
+    let check = get_quest_check(&addr, pet_id, &quest_type, storage)?;
+    Ok(check.resolve(roll))
 }
 
 pub(crate) fn calculate_loot(
     addr: CanonicalAddr,
     quest_type: String,
     storage: &dyn Storage,
-) -> Result<Loot, StdError> {
-    let quests = match QUESTS.get(storage, &addr) {
+) -> Result<Loot, ContractError> {
+    let quests = match load_player_quests(&addr, storage)? {
         Some(quests) => quests,
-        None => return Err(StdError::generic_err("Quests not found"))
+        None => return Err(ContractError::QuestsNotFound)
     };
 
     let mut loot = Loot { fail: 0, pass: 0, exceptional_pass: 0 };
     for quest in quests.iter() {
-        if quest.quest_type == quest_type.clone() {
-            let base = quest.base_loot + quest.difficulty + quest.difficulty_increment;
+        if quest.quest_type == quest_type {
+            let base = quest.base_loot
+                .saturating_add(quest.difficulty)
+                .saturating_add(quest.difficulty_increment);
             loot = Loot {
-                fail: (base + 2) / 3,
+                fail: base / 3 + u16::from(base % 3 != 0),
                 pass: base,
-                exceptional_pass: base.mul(2),
+                exceptional_pass: base.saturating_mul(2),
             };
         }
     }
@@ -639,18 +2235,18 @@ pub(crate) fn get_quest(
     addr: CanonicalAddr,
     quest_type: String,
     storage: &dyn Storage
-) -> Result<Quest, StdError> {
-    let quests = match QUESTS.get(storage, &addr) {
+) -> Result<Quest, ContractError> {
+    let quests = match load_player_quests(&addr, storage)? {
         Some(quests) => quests,
-        None => return Err(StdError::generic_err("Quests not found"))
+        None => return Err(ContractError::QuestsNotFound)
     };
 
     for quest in quests.iter() {
-        if quest.quest_type == quest_type.clone() {
+        if quest.quest_type == quest_type {
             return Ok(quest.clone())
         }
     };
 
-    Err(StdError::generic_err("Quest not found"))
+    Err(ContractError::QuestNotFound)
 }
 
@@ -0,0 +1,149 @@
+use rand::rngs::SmallRng;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::battle::{simulate_battle, BattleOutcome, TieBreaker};
+use crate::state::PetStats;
+
+// bounds the number of battles fought in the execute that resolves the bracket
+pub(crate) static MAX_TOURNAMENT_ENTRANTS: u32 = 32;
+pub(crate) static MIN_TOURNAMENT_ENTRANTS: u32 = 2;
+// the prize pool share in percent of the champion, the runner-up and the semi-finalists
+pub(crate) static DEFAULT_PRIZE_SPLIT: [u16; 3] = [60, 30, 10];
+pub(crate) static MAX_PRIZE_PLACES: usize = 4;
+
+/// a match of the bracket, pets without an opponent advance on a bye
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TournamentMatch {
+    pub round: u16,
+    // the challenger, striking first
+    pub pet_id: String,
+    pub other_pet_id: Option<String>,
+    // none for byes, drawn matches are won on a coin flip
+    pub outcome: Option<BattleOutcome>,
+    pub winner: String
+}
+
+/// how far a pet got in the bracket: the champion places 1st, the runner-up 2nd and the
+/// pets knocked out earlier share the place after the pets still in the bracket
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Placement {
+    pub pet_id: String,
+    pub place: u32,
+    // the round the pet lost in, none for the champion
+    pub eliminated_in: Option<u16>,
+    pub prize: u64
+}
+
+/// shuffles the entrants and fights the bracket out round by round. When the entrants are not
+/// a power of two the first round gives byes, so that every later round is full
+pub(crate) fn resolve_bracket(
+    mut entrants: Vec<(String, PetStats)>,
+    tie_breaker: &TieBreaker,
+    rng: &mut SmallRng
+) -> Vec<TournamentMatch> {
+    for i in (1..entrants.len()).rev() {
+        let j = rng.gen_range(0..=i);
+        entrants.swap(i, j);
+    }
+
+    let mut matches: Vec<TournamentMatch> = vec![];
+    let mut round = 0;
+    while entrants.len() > 1 {
+        round += 1;
+        let byes = entrants.len().next_power_of_two() - entrants.len();
+        let mut remaining = entrants.into_iter();
+        let mut next_round: Vec<(String, PetStats)> = vec![];
+
+        for (pet_id, stats) in remaining.by_ref().take(byes) {
+            matches.push(TournamentMatch {
+                round,
+                pet_id: pet_id.clone(),
+                other_pet_id: None,
+                outcome: None,
+                winner: pet_id.clone(),
+            });
+            next_round.push((pet_id, stats));
+        }
+        while let (Some(pet), Some(other_pet)) = (remaining.next(), remaining.next()) {
+            let (outcome, _) = simulate_battle(&pet.0, &pet.1, &other_pet.0, &other_pet.1, tie_breaker, rng);
+            let pet_wins = match outcome {
+                BattleOutcome::ChallengerWin => true,
+                BattleOutcome::DefenderWin => false,
+                BattleOutcome::Draw => rng.gen_range(0..2) == 0
+            };
+            let winner = if pet_wins { pet.0.clone() } else { other_pet.0.clone() };
+            matches.push(TournamentMatch {
+                round,
+                pet_id: pet.0.clone(),
+                other_pet_id: Some(other_pet.0.clone()),
+                outcome: Some(outcome),
+                winner,
+            });
+            next_round.push(if pet_wins { pet } else { other_pet });
+        }
+        entrants = next_round;
+    }
+    matches
+}
+
+/// places every entrant of the resolved bracket and splits the prize pool across the places.
+/// Each share of the split goes to a place, shared by the pets in it, starting with the
+/// champion. Shares of places nobody reached and rounding leftovers go to the champion
+pub(crate) fn place_entrants(
+    matches: &[TournamentMatch],
+    entrant_count: u32,
+    prize_pool: u64,
+    prize_split: &[u16]
+) -> Vec<Placement> {
+    let bracket_size = entrant_count.next_power_of_two();
+    let rounds = matches.iter().map(|m| m.round).max().unwrap_or(0);
+
+    let mut placements: Vec<Placement> = vec![];
+    for m in matches.iter().filter(|m| m.other_pet_id.is_some()) {
+        let loser = match &m.other_pet_id {
+            Some(other_pet_id) if *other_pet_id != m.winner => other_pet_id.clone(),
+            _ => m.pet_id.clone()
+        };
+        placements.push(Placement {
+            pet_id: loser,
+            place: (bracket_size >> m.round) + 1,
+            eliminated_in: Some(m.round),
+            prize: 0,
+        });
+    }
+    if let Some(last) = matches.last() {
+        placements.push(Placement {
+            pet_id: last.winner.clone(),
+            place: 1,
+            eliminated_in: None,
+            prize: 0,
+        });
+    }
+    placements.sort_by_key(|placement| placement.place);
+
+    // the first share is the champion's, the next ones go to the losers of the final,
+    // the semi-finals and so on
+    let mut awarded: u64 = 0;
+    for (tier, share) in prize_split.iter().enumerate().skip(1) {
+        let round = match (rounds + 1).checked_sub(tier as u16) {
+            Some(round) if round > 0 => round,
+            _ => break
+        };
+        let tier_pets = placements.iter()
+            .filter(|placement| placement.eliminated_in == Some(round))
+            .count() as u128;
+        if tier_pets == 0 {
+            continue;
+        }
+        let prize = (u128::from(prize_pool) * u128::from(*share) / 100 / tier_pets) as u64;
+        for placement in placements.iter_mut().filter(|placement| placement.eliminated_in == Some(round)) {
+            placement.prize = prize;
+            awarded += prize;
+        }
+    }
+    if let Some(champion) = placements.first_mut() {
+        champion.prize = prize_pool - awarded;
+    }
+    placements
+}
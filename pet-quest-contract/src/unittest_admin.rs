@@ -0,0 +1,272 @@
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::Addr;
+
+    use crate::battle::TieBreaker;
+    use crate::contract::instantiate;
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryAnswer, QueryMsg};
+    use crate::multitest::{App, ADMIN, LOOT_ADDR, MAX_STATS, PET_ADDR};
+    use crate::species::SpeciesConfig;
+    use crate::state::{
+        BreedingConfig, ContractData, ContractStatusLevel, PetPrivacyPolicy, ProgressionConfig,
+        UpgradeConfig, MAX_STAT_VALUE, MIN_MAX_STATS,
+    };
+
+    // Helper functions
+
+    fn config(app: &App) -> QueryAnswer {
+        app.query_game(&QueryMsg::Config {}).unwrap()
+    }
+
+    fn set_status(app: &mut App, level: ContractStatusLevel) {
+        app.execute_game(ADMIN, &ExecuteMsg::SetContractStatus { level })
+            .unwrap();
+    }
+
+    // Admin messages
+
+    #[test]
+    fn test_config_query() {
+        let app = App::new();
+        match config(&app) {
+            QueryAnswer::Config {
+                admin,
+                max_stats,
+                status,
+                loot_contract,
+                pet_contract,
+                progression,
+                tie_breaker,
+                privacy,
+                upgrades,
+                breeding,
+                species,
+            } => {
+                assert_eq!(admin, Addr::unchecked(ADMIN));
+                assert_eq!(max_stats, MAX_STATS);
+                assert_eq!(status, ContractStatusLevel::NormalRun);
+                assert_eq!(loot_contract.addr, LOOT_ADDR);
+                assert_eq!(pet_contract.addr, PET_ADDR);
+                assert_eq!(progression, ProgressionConfig::default());
+                assert_eq!(tie_breaker, TieBreaker::Draw);
+                assert_eq!(privacy, PetPrivacyPolicy::default());
+                assert_eq!(upgrades, UpgradeConfig::default());
+                assert_eq!(breeding, BreedingConfig::default());
+                assert_eq!(species, SpeciesConfig::default());
+            }
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_admin_messages_require_admin() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+
+        let msgs = vec![
+            ExecuteMsg::UpdateConfig {
+                max_stats: Some(30),
+                entropy: None,
+            },
+            ExecuteMsg::SetLootContract {
+                contract: ContractData {
+                    hash: "hash".to_string(),
+                    addr: "other_loot".to_string(),
+                },
+            },
+            ExecuteMsg::SetPetContract {
+                contract: ContractData {
+                    hash: "hash".to_string(),
+                    addr: "other_pet".to_string(),
+                },
+            },
+            ExecuteMsg::ChangeAdmin {
+                address: alice.address.clone(),
+            },
+            ExecuteMsg::SetContractStatus {
+                level: ContractStatusLevel::StopAll,
+            },
+            ExecuteMsg::SetTieBreaker {
+                tie_breaker: TieBreaker::LuckRoll,
+            },
+            ExecuteMsg::SetPrivacyPolicy {
+                policy: PetPrivacyPolicy::default(),
+            },
+            ExecuteMsg::SetUpgradeConfig {
+                config: UpgradeConfig::default(),
+            },
+            ExecuteMsg::SetBreedingConfig {
+                config: BreedingConfig::default(),
+            },
+            ExecuteMsg::SetSpeciesConfig {
+                config: SpeciesConfig::default(),
+            },
+        ];
+        for msg in msgs {
+            let err = app.execute_game(&alice.address, &msg).unwrap_err();
+            assert!(err.contains("unauthorized"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_instantiate_rejects_max_stats_out_of_range() {
+        for max_stats in [MIN_MAX_STATS - 1, MAX_STAT_VALUE + 1] {
+            let msg = InstantiateMsg {
+                admin: Addr::unchecked(ADMIN),
+                max_stats,
+                entropy: "entropy".to_string(),
+                loot_contract: ContractData {
+                    hash: "hash".to_string(),
+                    addr: LOOT_ADDR.to_string(),
+                },
+                pet_contract: ContractData {
+                    hash: "hash".to_string(),
+                    addr: PET_ADDR.to_string(),
+                },
+            };
+            let mut deps = mock_dependencies();
+            let err = instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("invalid_max_stats"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_update_config_and_change_admin() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+
+        for max_stats in [MIN_MAX_STATS - 1, MAX_STAT_VALUE + 1] {
+            let err = app
+                .execute_game(
+                    ADMIN,
+                    &ExecuteMsg::UpdateConfig {
+                        max_stats: Some(max_stats),
+                        entropy: None,
+                    },
+                )
+                .unwrap_err();
+            assert!(err.contains("invalid_max_stats"), "{}", err);
+        }
+        let msg = ExecuteMsg::UpdateConfig {
+            max_stats: Some(MAX_STAT_VALUE),
+            entropy: None,
+        };
+        app.execute_game(ADMIN, &msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            max_stats: Some(30),
+            entropy: Some("rotated".to_string()),
+        };
+        app.execute_game(ADMIN, &msg).unwrap();
+        let msg = ExecuteMsg::SetTieBreaker {
+            tie_breaker: TieBreaker::TotalStats,
+        };
+        app.execute_game(ADMIN, &msg).unwrap();
+        app.execute_game(
+            ADMIN,
+            &ExecuteMsg::ChangeAdmin {
+                address: alice.address.clone(),
+            },
+        )
+        .unwrap();
+
+        match config(&app) {
+            QueryAnswer::Config {
+                admin,
+                max_stats,
+                tie_breaker,
+                ..
+            } => {
+                assert_eq!(admin, Addr::unchecked(alice.address.clone()));
+                assert_eq!(max_stats, 30);
+                assert_eq!(tie_breaker, TieBreaker::TotalStats);
+            }
+            other => panic!("unexpected answer {:?}", other),
+        }
+
+        // the previous admin lost its rights
+        let err = app
+            .execute_game(
+                ADMIN,
+                &ExecuteMsg::UpdateConfig {
+                    max_stats: Some(20),
+                    entropy: None,
+                },
+            )
+            .unwrap_err();
+        assert!(err.contains("unauthorized"), "{}", err);
+    }
+
+    #[test]
+    fn test_set_contracts_repoints_the_game() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+
+        let msg = ExecuteMsg::SetPetContract {
+            contract: ContractData {
+                hash: "hash".to_string(),
+                addr: "missing_pets".to_string(),
+            },
+        };
+        app.execute_game(ADMIN, &msg).unwrap();
+        match config(&app) {
+            QueryAnswer::Config { pet_contract, .. } => {
+                assert_eq!(pet_contract.addr, "missing_pets")
+            }
+            other => panic!("unexpected answer {:?}", other),
+        }
+
+        // minting now targets a contract that does not exist
+        assert!(app.try_mint_pet(&alice).is_err());
+
+        let msg = ExecuteMsg::SetPetContract {
+            contract: ContractData {
+                hash: format!("{}_hash", PET_ADDR),
+                addr: PET_ADDR.to_string(),
+            },
+        };
+        app.execute_game(ADMIN, &msg).unwrap();
+        assert_eq!(app.mint_pet(&alice), "PET_0");
+    }
+
+    #[test]
+    fn test_contract_status_levels() {
+        let mut app = App::new();
+        let alice = app.funded_user("alice", 100);
+        let bob = app.user("bob");
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        set_status(&mut app, ContractStatusLevel::StopQuests);
+        let err = app
+            .send_on_quest(&alice, &alice_pet, "trial_of_titans")
+            .unwrap_err();
+        assert!(err.contains("contract_stopped"), "{}", err);
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+
+        set_status(&mut app, ContractStatusLevel::StopBattles);
+        let err = app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap_err();
+        assert!(err.contains("contract_stopped"), "{}", err);
+        // pending battles can still be cancelled, which unlocks the pet for quests
+        let permits = app.permits(&alice);
+        let msg = ExecuteMsg::CancelBattle {
+            battle_id: 0,
+            permit: permits.permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+        app.send_on_quest(&alice, &alice_pet, "trial_of_titans")
+            .unwrap();
+
+        set_status(&mut app, ContractStatusLevel::StopAll);
+        let err = app.try_mint_pet(&alice).unwrap_err();
+        assert!(err.contains("contract_stopped"), "{}", err);
+
+        // admin messages still go through, so the contract can be resumed
+        set_status(&mut app, ContractStatusLevel::NormalRun);
+        assert_eq!(app.mint_pet(&alice), "PET_2");
+    }
+}
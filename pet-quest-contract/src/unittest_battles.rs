@@ -0,0 +1,636 @@
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{CanonicalAddr, Uint128};
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use crate::battle::{
+        simulate_battle, BattleLog, BattleOutcome, StrikeResult, TieBreaker, MAX_BATTLE_ROUNDS,
+    };
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryWithPermits};
+    use crate::multitest::{App, TestUser, PET_ADDR};
+    use crate::state::{
+        BattleInfo, BattleInfoWithoutInitiator, BattleStatus, Expiration, PetStats, Stat,
+        DEFAULT_BATTLE_EXPIRY_SECONDS, MAX_BATTLE_EXPIRY_SECONDS, MAX_PENDING_BATTLES_PER_PET,
+        MIN_BATTLE_WAGER, MIN_SECONDS_PER_BLOCK,
+    };
+
+    // Helper functions
+
+    fn my_battles(app: &App, user: &TestUser) -> Vec<BattleInfoWithoutInitiator> {
+        let query = QueryWithPermits::MyBattles {
+            pet_permit: app.permits(user).pet_permit,
+        };
+        match app.query_with_permits(user, query).unwrap() {
+            QueryAnswer::Battles { battles } => battles,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn battle_details(
+        app: &App,
+        user: &TestUser,
+        battle_id: u64,
+    ) -> Result<(BattleInfoWithoutInitiator, Option<BattleLog>), String> {
+        let query = QueryWithPermits::BattleDetails { battle_id };
+        match app.query_with_permits(user, query)? {
+            QueryAnswer::BattleDetails { battle, log } => Ok((battle, log)),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn battle_until(
+        app: &mut App,
+        user: &TestUser,
+        pet_id: &str,
+        other_pet_id: &str,
+        wager: u64,
+        expiration: Option<Expiration>,
+    ) -> Result<(), String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::BattlePet {
+            pet_id: pet_id.to_string(),
+            other_pet_id: other_pet_id.to_string(),
+            wager,
+            expiration,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn accept(app: &mut App, user: &TestUser, battle_id: u64) -> Result<(), String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::AcceptBattle {
+            battle_id,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn claim(app: &mut App, user: &TestUser, battle_id: u64, pet_id: &str) -> Result<(), String> {
+        let msg = ExecuteMsg::ClaimBattle {
+            battle_id,
+            pet_id: pet_id.to_string(),
+            pet_permit: app.permits(user).pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn reclaim(app: &mut App, user: &TestUser, battle_id: u64) -> Result<(), String> {
+        let msg = ExecuteMsg::ReclaimExpiredBattle { battle_id };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn cancel(app: &mut App, user: &TestUser, battle_id: u64) -> Result<(), String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::CancelBattle {
+            battle_id,
+            permit: permits.permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn decline(app: &mut App, user: &TestUser, battle_id: u64) -> Result<(), String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::DeclineBattle {
+            battle_id,
+            permit: permits.permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn release(app: &mut App, user: &TestUser, pet_id: &str) -> Result<(), String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::ReleasePet {
+            pet_id: pet_id.to_string(),
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    /// fights an accepted battle and returns the winner and loser with their pets
+    fn fight<'a>(
+        app: &mut App,
+        alice: &'a TestUser,
+        bob: &'a TestUser,
+        wager: u64,
+    ) -> ((&'a TestUser, String), (&'a TestUser, String)) {
+        let alice_pet = app.mint_pet(alice);
+        let bob_pet = app.mint_pet(bob);
+        app.battle(alice, &alice_pet, &bob_pet, wager).unwrap();
+        accept(app, bob, 0).unwrap();
+
+        match my_battles(app, alice)[0].outcome {
+            Some(BattleOutcome::ChallengerWin) => ((alice, alice_pet), (bob, bob_pet)),
+            Some(BattleOutcome::DefenderWin) => ((bob, bob_pet), (alice, alice_pet)),
+            other => panic!("accepted battle has no winner {:?}", other),
+        }
+    }
+
+    // Escrow
+
+    #[test]
+    fn test_wagers_are_escrowed_without_changing_supply() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let supply = app.loot_supply();
+
+        let ((winner, winner_pet), (loser, loser_pet)) = fight(&mut app, &alice, &bob, 10);
+        assert_eq!(app.loot_balance(&alice), 990);
+        assert_eq!(app.loot_balance(&bob), 990);
+        assert_eq!(app.loot_supply(), supply);
+
+        claim(&mut app, loser, 0, &loser_pet).unwrap();
+        assert_eq!(app.loot_balance(loser), 990);
+        claim(&mut app, winner, 0, &winner_pet).unwrap();
+        assert_eq!(app.loot_balance(winner), 1010);
+        assert_eq!(app.loot_supply(), supply);
+
+        // the escrow is paid out once
+        let err = claim(&mut app, winner, 0, &winner_pet).unwrap_err();
+        assert!(err.contains("battle_not_claimable"), "{}", err);
+        assert_eq!(app.loot_balance(winner), 1010);
+    }
+
+    #[test]
+    fn test_declined_and_cancelled_battles_refund_the_escrow() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let supply = app.loot_supply();
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        app.battle(&alice, &alice_pet, &bob_pet, 25).unwrap();
+        let msg = ExecuteMsg::DeclineBattle {
+            battle_id: 0,
+            permit: app.permits(&bob).permit,
+            pet_permit: app.permits(&bob).pet_permit,
+        };
+        app.execute_game(&bob.address, &msg).unwrap();
+        assert_eq!(app.loot_balance(&alice), 1000);
+
+        app.battle(&alice, &alice_pet, &bob_pet, 25).unwrap();
+        assert_eq!(app.loot_balance(&alice), 975);
+        let msg = ExecuteMsg::CancelBattle {
+            battle_id: 1,
+            permit: app.permits(&alice).permit,
+            pet_permit: app.permits(&alice).pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+        assert_eq!(app.loot_balance(&alice), 1000);
+        assert_eq!(app.loot_supply(), supply);
+    }
+
+    #[test]
+    fn test_large_wagers_are_paid_in_full() {
+        // wagers used to be minted back as u16 and wrapped above 65535
+        let (mut app, alice, bob) = App::with_players(100_000);
+
+        let ((winner, winner_pet), _) = fight(&mut app, &alice, &bob, 70_000);
+        claim(&mut app, winner, 0, &winner_pet).unwrap();
+        assert_eq!(app.loot_balance(winner), 170_000);
+        assert_eq!(app.loot_balance(&alice) + app.loot_balance(&bob), 200_000);
+    }
+
+    // Expiry
+
+    #[test]
+    fn test_expired_challenges_cannot_be_accepted_and_are_refunded() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let carol = app.user("carol");
+        let supply = app.loot_supply();
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        let expires = app.block().time.seconds() + 100;
+        let expiration = Some(Expiration::AtTime(expires));
+        battle_until(&mut app, &alice, &alice_pet, &bob_pet, 25, expiration).unwrap();
+        assert_eq!(
+            my_battles(&app, &bob)[0].expiration,
+            Expiration::AtTime(expires)
+        );
+
+        let err = reclaim(&mut app, &carol, 0).unwrap_err();
+        assert!(err.contains("battle_not_expired"), "{}", err);
+
+        app.advance_time(100);
+        assert_eq!(my_battles(&app, &bob)[0].status, BattleStatus::Expired);
+        let err = accept(&mut app, &bob, 0).unwrap_err();
+        assert!(err.contains("battle_expired"), "{}", err);
+        assert_eq!(app.loot_balance(&bob), 1000);
+
+        // anyone may return the wager of a stale challenge to its initiator
+        reclaim(&mut app, &carol, 0).unwrap();
+        assert_eq!(app.loot_balance(&alice), 1000);
+        assert_eq!(app.loot_supply(), supply);
+        assert!(my_battles(&app, &alice).is_empty());
+        assert!(my_battles(&app, &bob).is_empty());
+
+        let err = reclaim(&mut app, &carol, 0).unwrap_err();
+        assert!(err.contains("battle_not_found"), "{}", err);
+    }
+
+    #[test]
+    fn test_challenges_expire_after_a_day_by_default() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        let expiration = Some(Expiration::AtHeight(app.block().height));
+        let err = battle_until(&mut app, &alice, &alice_pet, &bob_pet, 25, expiration).unwrap_err();
+        assert!(err.contains("invalid_expiration"), "{}", err);
+
+        // challenges cannot stay open forever or past the max window
+        let now = app.block().time.seconds();
+        let max_height = app.block().height + MAX_BATTLE_EXPIRY_SECONDS / MIN_SECONDS_PER_BLOCK;
+        for expiration in [
+            Expiration::Never,
+            Expiration::AtTime(now + MAX_BATTLE_EXPIRY_SECONDS + 1),
+            Expiration::AtHeight(max_height + 1),
+        ] {
+            let err = battle_until(&mut app, &alice, &alice_pet, &bob_pet, 25, Some(expiration))
+                .unwrap_err();
+            assert!(err.contains("invalid_expiration"), "{}", err);
+        }
+        assert!(Expiration::AtHeight(max_height).is_within(app.block(), MAX_BATTLE_EXPIRY_SECONDS));
+
+        let expires = app.block().time.seconds() + DEFAULT_BATTLE_EXPIRY_SECONDS;
+        app.battle(&alice, &alice_pet, &bob_pet, 25).unwrap();
+        assert_eq!(
+            my_battles(&app, &bob)[0].expiration,
+            Expiration::AtTime(expires)
+        );
+
+        app.advance_time(DEFAULT_BATTLE_EXPIRY_SECONDS - 1);
+        assert_eq!(my_battles(&app, &bob)[0].status, BattleStatus::Pending);
+        app.advance_time(1);
+        assert_eq!(my_battles(&app, &bob)[0].status, BattleStatus::Expired);
+        reclaim(&mut app, &bob, 0).unwrap();
+        assert_eq!(app.loot_balance(&alice), 1000);
+    }
+
+    // Engine
+
+    #[test]
+    fn test_engine_logs_every_strike_until_a_knock_out() {
+        let strong = PetStats::new(20, 20, 20, 0, 5);
+        let weak = PetStats::new(5, 5, 5, 0, 0);
+
+        for seed in 0..20 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let (outcome, log) = simulate_battle(
+                "PET_0",
+                &strong,
+                "PET_1",
+                &weak,
+                &TieBreaker::Draw,
+                &mut rng,
+            );
+            assert_eq!(outcome, BattleOutcome::ChallengerWin);
+            assert_eq!((log.pet_hp, log.other_pet_hp), (200, 50));
+
+            // without intelligence there are no crits or dodges, only hits of 20 to 25
+            assert_eq!(log.turns[0].attacker, "PET_0");
+            assert!(log
+                .turns
+                .iter()
+                .all(|turn| turn.result == StrikeResult::Hit));
+            let last = log.turns.last().unwrap();
+            assert_eq!(last.attacker, "PET_0");
+            assert_eq!(last.defender_hp, 0);
+            assert_eq!(last.round, log.rounds);
+        }
+
+        // the same seed fights the same battle
+        let fight = |seed| {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            simulate_battle("PET_0", &weak, "PET_1", &weak, &TieBreaker::Draw, &mut rng)
+        };
+        assert_eq!(fight(7), fight(7));
+    }
+
+    #[test]
+    fn test_stamina_limits_turns_and_ties_are_draws() {
+        let sturdy = PetStats::new(100, 1, 0, 0, 0);
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        // both pets run out of turns after three strikes each, with the same hp left
+        let (outcome, log) = simulate_battle(
+            "PET_0",
+            &sturdy,
+            "PET_1",
+            &sturdy,
+            &TieBreaker::Draw,
+            &mut rng,
+        );
+        assert_eq!(outcome, BattleOutcome::Draw);
+        assert_eq!(log.tie_broken_by, None);
+        assert_eq!(log.rounds, 3);
+        assert_eq!(log.turns.len(), 6);
+        assert_eq!(log.turns[5].defender_hp, 997);
+
+        // the rounds are capped for pets with a lot of stamina
+        let tireless = PetStats::new(100, 1, 200, 0, 0);
+        let (_, log) = simulate_battle(
+            "PET_0",
+            &tireless,
+            "PET_1",
+            &tireless,
+            &TieBreaker::Draw,
+            &mut rng,
+        );
+        assert_eq!(log.rounds, MAX_BATTLE_ROUNDS);
+    }
+
+    #[test]
+    fn test_tie_breakers_decide_tied_battles() {
+        // pets without strength or luck cannot hurt each other, so every battle is a tie
+        let sturdy = PetStats::new(100, 0, 0, 0, 0);
+        let wise = PetStats::new(100, 0, 0, 10, 0);
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        let (outcome, log) = simulate_battle(
+            "PET_0",
+            &wise,
+            "PET_1",
+            &sturdy,
+            &TieBreaker::Draw,
+            &mut rng,
+        );
+        assert_eq!(outcome, BattleOutcome::Draw);
+        assert_eq!(log.tie_broken_by, None);
+
+        let (outcome, log) = simulate_battle(
+            "PET_0",
+            &wise,
+            "PET_1",
+            &sturdy,
+            &TieBreaker::TotalStats,
+            &mut rng,
+        );
+        assert_eq!(outcome, BattleOutcome::ChallengerWin);
+        assert_eq!(log.tie_broken_by, Some(TieBreaker::TotalStats));
+        let (outcome, _) = simulate_battle(
+            "PET_0",
+            &sturdy,
+            "PET_1",
+            &sturdy,
+            &TieBreaker::TotalStats,
+            &mut rng,
+        );
+        assert_eq!(outcome, BattleOutcome::Draw);
+
+        // either pet can win the luck roll
+        let outcomes: Vec<BattleOutcome> = (0..20)
+            .map(|seed| {
+                let mut rng = SmallRng::seed_from_u64(seed);
+                let (outcome, log) = simulate_battle(
+                    "PET_0",
+                    &sturdy,
+                    "PET_1",
+                    &sturdy,
+                    &TieBreaker::LuckRoll,
+                    &mut rng,
+                );
+                if outcome != BattleOutcome::Draw {
+                    assert_eq!(log.tie_broken_by, Some(TieBreaker::LuckRoll));
+                }
+                outcome
+            })
+            .collect();
+        assert!(outcomes.contains(&BattleOutcome::ChallengerWin));
+        assert!(outcomes.contains(&BattleOutcome::DefenderWin));
+    }
+
+    #[test]
+    fn test_draws_return_each_pet_its_wager() {
+        let mut battle = BattleInfo {
+            id: 0,
+            initiator_address: CanonicalAddr::from(b"alice".as_slice()),
+            pet_id: "PET_0".to_string(),
+            other_pet_id: "PET_1".to_string(),
+            wager: 25,
+            status: BattleStatus::Accepted,
+            outcome: Some(BattleOutcome::Draw),
+            escrowed: true,
+            expiration: Expiration::Never,
+        };
+        assert_eq!(battle.winner(), None);
+        assert_eq!(battle.payout("PET_0").unwrap(), Uint128::new(25));
+        assert_eq!(battle.payout("PET_1").unwrap(), Uint128::new(25));
+
+        battle.outcome = Some(BattleOutcome::DefenderWin);
+        assert_eq!(battle.payout("PET_0").unwrap(), Uint128::zero());
+        assert_eq!(battle.payout("PET_1").unwrap(), Uint128::new(50));
+
+        battle.outcome = None;
+        assert_eq!(battle.payout("PET_1").unwrap(), Uint128::zero());
+    }
+
+    #[test]
+    fn test_battle_details_are_shown_to_both_owners() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let carol = app.user("carol");
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+        let (pending, log) = battle_details(&app, &bob, 0).unwrap();
+        assert_eq!(pending.status, BattleStatus::Pending);
+        assert!(log.is_none());
+
+        accept(&mut app, &bob, 0).unwrap();
+        let (accepted, log) = battle_details(&app, &alice, 0).unwrap();
+        assert_eq!(accepted, my_battles(&app, &alice).remove(0));
+        let log = log.unwrap();
+        assert!(!log.turns.is_empty());
+        assert!(log.rounds <= MAX_BATTLE_ROUNDS);
+        assert_eq!(battle_details(&app, &bob, 0).unwrap().1, Some(log));
+
+        let err = battle_details(&app, &carol, 0).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        let err = battle_details(&app, &alice, 1).unwrap_err();
+        assert!(err.contains("battle_not_found"), "{}", err);
+    }
+
+    // Lifecycle
+
+    #[test]
+    fn test_pets_cannot_battle_themselves_or_missing_pets() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+
+        let err = app.battle(&alice, &alice_pet, &alice_pet, 10).unwrap_err();
+        assert!(err.contains("cannot_battle_self"), "{}", err);
+        let err = app.battle(&alice, &alice_pet, "PET_9", 10).unwrap_err();
+        assert!(err.contains("pet_not_found"), "{}", err);
+        let err = app
+            .battle(&alice, &alice_pet, "PET_9", MIN_BATTLE_WAGER - 1)
+            .unwrap_err();
+        assert!(err.contains("wager_too_low"), "{}", err);
+        assert_eq!(app.loot_balance(&alice), 1000);
+    }
+
+    #[test]
+    fn test_pets_on_quests_cannot_battle() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        app.send_on_quest(&alice, &alice_pet, "trial_of_titans")
+            .unwrap();
+
+        let err = app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap_err();
+        assert!(err.contains("pet_on_quest"), "{}", err);
+        let err = app.battle(&bob, &bob_pet, &alice_pet, 10).unwrap_err();
+        assert!(err.contains("pet_on_quest"), "{}", err);
+    }
+
+    #[test]
+    fn test_a_pair_has_one_pending_battle_at_a_time() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+
+        let err = app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap_err();
+        assert!(err.contains("battle_already_pending"), "{}", err);
+        let err = app.battle(&bob, &bob_pet, &alice_pet, 10).unwrap_err();
+        assert!(err.contains("battle_already_pending"), "{}", err);
+
+        // a battle can only be answered while it is pending
+        accept(&mut app, &bob, 0).unwrap();
+        let err = accept(&mut app, &bob, 0).unwrap_err();
+        assert!(err.contains("battle_not_pending"), "{}", err);
+        let err = decline(&mut app, &bob, 0).unwrap_err();
+        assert!(err.contains("battle_not_pending"), "{}", err);
+        let err = cancel(&mut app, &alice, 0).unwrap_err();
+        assert!(err.contains("battle_not_pending"), "{}", err);
+        app.battle(&bob, &bob_pet, &alice_pet, 10).unwrap();
+    }
+
+    #[test]
+    fn test_pending_challenges_lock_only_the_challenger() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let carol = app.funded_user("carol", 1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        let carol_pet = app.mint_pet(&carol);
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+
+        let upgrade = |app: &App, user: &TestUser, pet_id: &str| {
+            let permits = app.permits(user);
+            ExecuteMsg::UpgradePetStats {
+                pet_id: pet_id.to_string(),
+                upgrades: vec![(Stat::Strength, 1)],
+                permit: permits.permit,
+                loot_permit: permits.loot_permit,
+                pet_permit: permits.pet_permit,
+            }
+        };
+        let msg = upgrade(&app, &alice, &alice_pet);
+        let err = app.execute_game(&alice.address, &msg).unwrap_err();
+        assert!(err.contains("pet_in_battle"), "{}", err);
+
+        // a third party cannot lock a pet it does not own by challenging it
+        app.battle(&carol, &carol_pet, &bob_pet, 10).unwrap();
+        let bob_msg = upgrade(&app, &bob, &bob_pet);
+        app.execute_game(&bob.address, &bob_msg).unwrap();
+        app.send_on_quest(&bob, &bob_pet, "trial_of_titans")
+            .unwrap();
+
+        // pets waiting in matchmaking cannot start challenges
+        let queued_pet = app.mint_pet(&carol);
+        let permits = app.permits(&carol);
+        let enter = ExecuteMsg::EnterMatchmaking {
+            pet_id: queued_pet.clone(),
+            wager: 25,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&carol.address, &enter).unwrap();
+        let err = app.battle(&carol, &queued_pet, &alice_pet, 10).unwrap_err();
+        assert!(err.contains("pet_in_battle"), "{}", err);
+
+        // cancelling unlocks the challenger
+        cancel(&mut app, &alice, 0).unwrap();
+        app.execute_game(&alice.address, &msg).unwrap();
+    }
+
+    #[test]
+    fn test_released_pets_settle_their_battles() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let supply = app.loot_supply();
+        let ((winner, winner_pet), (_, loser_pet)) = fight(&mut app, &alice, &bob, 10);
+        let carol = app.funded_user("carol", 1000);
+        let carol_pet = app.mint_pet(&carol);
+
+        // the pending challenge is refunded to carol and the unclaimed winnings are paid out
+        app.battle(&carol, &carol_pet, &winner_pet, 25).unwrap();
+        release(&mut app, winner, &winner_pet).unwrap();
+        assert_eq!(app.loot_balance(&carol), 1000);
+        assert_eq!(app.loot_balance(winner), 1010);
+        assert_eq!(app.loot_supply(), supply + 1000);
+        assert!(my_battles(&app, &carol).is_empty());
+
+        // the loser's battle is untouched until it is cleared
+        assert_eq!(
+            my_battles(&app, &bob).len() + my_battles(&app, &alice).len(),
+            1
+        );
+        let loser = if winner.address == alice.address {
+            &bob
+        } else {
+            &alice
+        };
+        claim(&mut app, loser, 0, &loser_pet).unwrap();
+    }
+
+    #[test]
+    fn test_challenges_from_transferred_pets_are_cancelled() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let carol = app.user("carol");
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        app.battle(&alice, &alice_pet, &bob_pet, 25).unwrap();
+
+        let transfer = format!(
+            "{{\"transfer_nft\":{{\"recipient\":\"{}\",\"token_id\":\"{}\"}}}}",
+            carol.address, alice_pet
+        );
+        app.execute_raw(&alice.address, PET_ADDR, transfer.as_bytes())
+            .unwrap();
+
+        // accepting refunds the challenger instead of fighting with a pet they no longer own
+        accept(&mut app, &bob, 0).unwrap();
+        assert_eq!(app.loot_balance(&alice), 1000);
+        assert_eq!(app.loot_balance(&bob), 1000);
+        assert!(my_battles(&app, &bob).is_empty());
+    }
+
+    #[test]
+    fn test_pets_have_a_limit_of_pending_challenges() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let bob_pet = app.mint_pet(&bob);
+
+        for _ in 0..MAX_PENDING_BATTLES_PER_PET {
+            let alice_pet = app.mint_pet(&alice);
+            app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+        }
+        let alice_pet = app.mint_pet(&alice);
+        let err = app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap_err();
+        assert!(err.contains("too_many_pending_battles"), "{}", err);
+
+        // expired challenges no longer count
+        app.advance_time(DEFAULT_BATTLE_EXPIRY_SECONDS);
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+    }
+}
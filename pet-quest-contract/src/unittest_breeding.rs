@@ -0,0 +1,284 @@
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg};
+    use crate::multitest::{App, TestUser, ADMIN, MAX_STATS};
+    use crate::state::{
+        breed_stats, BreedingConfig, PetLineage, PetState, PetStats, Stat, MAX_BREEDING_MUTATION,
+        MIN_BRED_STAT, MIN_MAX_STATS,
+    };
+
+    // Helper functions
+
+    fn breed(
+        app: &mut App,
+        user: &TestUser,
+        parent_a: &str,
+        parent_b: &str,
+    ) -> Result<String, String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::BreedPets {
+            parent_a: parent_a.to_string(),
+            parent_b: parent_b.to_string(),
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        let response = app.execute_game(&user.address, &msg)?;
+        Ok(response.attribute("pet_id").unwrap())
+    }
+
+    fn pet_lineage(app: &App, pet_id: &str) -> (PetLineage, u64) {
+        let msg = QueryMsg::PetLineage {
+            pet_id: pet_id.to_string(),
+        };
+        match app.query_game(&msg).unwrap() {
+            QueryAnswer::PetLineage {
+                lineage,
+                breeding_cooldown,
+                ..
+            } => (lineage, breeding_cooldown),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn pet(pet_id: &str, base: PetStats, max: PetStats) -> PetState {
+        PetState::new(pet_id.to_string(), base, max, PetLineage::default())
+    }
+
+    fn random_stats(rng: &mut SmallRng, min: u16, max: u16) -> PetStats {
+        PetStats::new(
+            rng.gen_range(min..=max),
+            rng.gen_range(min..=max),
+            rng.gen_range(min..=max),
+            rng.gen_range(min..=max),
+            rng.gen_range(min..=max),
+        )
+    }
+
+    // Breeding
+
+    #[test]
+    fn test_breed_pets_mints_child_with_lineage() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let parent_a = app.mint_pet(&alice);
+        let parent_b = app.mint_pet(&alice);
+
+        let child_id = breed(&mut app, &alice, &parent_a, &parent_b).unwrap();
+        assert_eq!(child_id, "PET_2");
+        assert_eq!(
+            app.loot_balance(&alice),
+            1000 - BreedingConfig::default().fee as u128
+        );
+
+        let pets = app.my_pets(&alice);
+        assert_eq!(pets.len(), 3);
+        let child = pets.iter().find(|pet| pet.pet_id == child_id).unwrap();
+        assert_eq!(child.upgrades.base, Some(child.current.clone()));
+        assert_eq!(child.power, child.current.total());
+
+        let (lineage, cooldown) = pet_lineage(&app, &child_id);
+        assert_eq!(lineage.parents, Some((parent_a.clone(), parent_b.clone())));
+        assert_eq!(lineage.generation, 1);
+        assert_eq!(cooldown, 0);
+
+        let (lineage, cooldown) = pet_lineage(&app, &parent_a);
+        assert_eq!(lineage.parents, None);
+        assert_eq!(lineage.generation, 0);
+        assert_eq!(lineage.last_bred, Some(app.block().time));
+        assert_eq!(cooldown, BreedingConfig::default().cooldown_seconds);
+    }
+
+    #[test]
+    fn test_parents_wait_out_breeding_cooldown() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let parent_a = app.mint_pet(&alice);
+        let parent_b = app.mint_pet(&alice);
+        let third = app.mint_pet(&alice);
+        let child = breed(&mut app, &alice, &parent_a, &parent_b).unwrap();
+
+        // the cooldown is per parent, so neither parent breeds with another pet either
+        let err = breed(&mut app, &alice, &parent_a, &third).unwrap_err();
+        assert!(err.contains("breeding_cooldown"), "{}", err);
+        let err = breed(&mut app, &alice, &third, &parent_b).unwrap_err();
+        assert!(err.contains("breeding_cooldown"), "{}", err);
+        let grandchild = breed(&mut app, &alice, &child, &third).unwrap();
+        assert_eq!(pet_lineage(&app, &grandchild).0.generation, 2);
+
+        app.advance_time(BreedingConfig::default().cooldown_seconds - 1);
+        assert_eq!(pet_lineage(&app, &parent_a).1, 1);
+        let err = breed(&mut app, &alice, &parent_a, &parent_b).unwrap_err();
+        assert!(err.contains("breeding_cooldown"), "{}", err);
+
+        app.advance_time(1);
+        breed(&mut app, &alice, &parent_a, &parent_b).unwrap();
+    }
+
+    #[test]
+    fn test_breeding_config_is_validated() {
+        let mut app = App::new();
+
+        let no_cooldown = BreedingConfig {
+            cooldown_seconds: 0,
+            ..BreedingConfig::default()
+        };
+        let wild = BreedingConfig {
+            mutation: MAX_BREEDING_MUTATION + 1,
+            ..BreedingConfig::default()
+        };
+        for config in [no_cooldown, wild] {
+            let err = app
+                .execute_game(ADMIN, &ExecuteMsg::SetBreedingConfig { config })
+                .unwrap_err();
+            assert!(err.contains("invalid_breeding_config"), "{}", err);
+        }
+
+        let config = BreedingConfig {
+            mutation: MAX_BREEDING_MUTATION,
+            ..BreedingConfig::default()
+        };
+        app.execute_game(ADMIN, &ExecuteMsg::SetBreedingConfig { config })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_breeding_requires_owner_and_fee() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let parent_a = app.mint_pet(&alice);
+        let parent_b = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        let err = breed(&mut app, &bob, &parent_a, &parent_b).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        let err = breed(&mut app, &bob, &bob_pet, &parent_b).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        let err = breed(&mut app, &alice, &parent_a, &parent_a).unwrap_err();
+        assert!(err.contains("cannot_breed_self"), "{}", err);
+
+        let carol = app.user("carol");
+        let carol_a = app.mint_pet(&carol);
+        let carol_b = app.mint_pet(&carol);
+        let err = breed(&mut app, &carol, &carol_a, &carol_b).unwrap_err();
+        assert!(err.contains("insufficient_loot"), "{}", err);
+        assert_eq!(app.my_pets(&carol).len(), 2);
+
+        // without a fee breeding is free
+        let config = BreedingConfig {
+            fee: 0,
+            ..BreedingConfig::default()
+        };
+        let err = app
+            .execute_game(
+                &alice.address,
+                &ExecuteMsg::SetBreedingConfig {
+                    config: config.clone(),
+                },
+            )
+            .unwrap_err();
+        assert!(err.contains("unauthorized"), "{}", err);
+        app.execute_game(ADMIN, &ExecuteMsg::SetBreedingConfig { config })
+            .unwrap();
+        breed(&mut app, &carol, &carol_a, &carol_b).unwrap();
+        assert_eq!(app.loot_balance(&carol), 0);
+        assert_eq!(app.my_pets(&carol).len(), 3);
+    }
+
+    #[test]
+    fn test_pets_on_quest_cannot_breed() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let parent_a = app.mint_pet(&alice);
+        let parent_b = app.mint_pet(&alice);
+
+        app.send_on_quest(&alice, &parent_a, "trial_of_titans")
+            .unwrap();
+
+        let err = breed(&mut app, &alice, &parent_a, &parent_b).unwrap_err();
+        assert!(err.contains("pet_on_quest"), "{}", err);
+        assert_eq!(app.loot_balance(&alice), 1000);
+    }
+
+    #[test]
+    fn test_pets_in_battle_cannot_breed() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let parent_a = app.mint_pet(&alice);
+        let parent_b = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        // a pending challenge locks the challenger until it is accepted, declined or cancelled
+        app.battle(&alice, &parent_b, &bob_pet, 10).unwrap();
+
+        let err = breed(&mut app, &alice, &parent_a, &parent_b).unwrap_err();
+        assert!(err.contains("pet_in_battle"), "{}", err);
+        assert_eq!(app.loot_balance(&alice), 990);
+        assert_eq!(app.my_pets(&alice).len(), 2);
+    }
+
+    #[test]
+    fn test_bred_stats_are_inherited_within_mutation() {
+        for seed in 0..200 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mutation = rng.gen_range(0..=3);
+            let parent_a = pet(
+                "PET_0",
+                random_stats(&mut rng, 1, MAX_STATS),
+                random_stats(&mut rng, MIN_MAX_STATS, MAX_STATS + 5),
+            );
+            let mut parent_b = pet(
+                "PET_1",
+                random_stats(&mut rng, 1, MAX_STATS),
+                random_stats(&mut rng, MIN_MAX_STATS, MAX_STATS + 5),
+            );
+            // upgrades are not passed on
+            parent_b.current = PetStats::new(MAX_STATS, MAX_STATS, MAX_STATS, MAX_STATS, MAX_STATS);
+
+            let (base, max) = breed_stats(&parent_a, &parent_b, mutation, MAX_STATS, &mut rng);
+            let base_b = parent_b.upgrades.base.clone().unwrap();
+            for stat in Stat::all() {
+                let max_value = max.get_stat(stat.clone());
+                let (a, b) = (
+                    parent_a.max.get_stat(stat.clone()),
+                    parent_b.max.get_stat(stat.clone()),
+                );
+                assert!(
+                    (MIN_MAX_STATS..=MAX_STATS).contains(&max_value),
+                    "seed {}",
+                    seed
+                );
+                assert!(
+                    max_value + mutation >= a.min(b).min(MAX_STATS),
+                    "seed {}",
+                    seed
+                );
+                assert!(
+                    max_value <= (a.max(b) + mutation).max(MIN_MAX_STATS),
+                    "seed {}",
+                    seed
+                );
+
+                let base_value = base.get_stat(stat.clone());
+                let (a, b) = (
+                    parent_a.current.get_stat(stat.clone()),
+                    base_b.get_stat(stat.clone()),
+                );
+                assert!(
+                    (MIN_BRED_STAT..=max_value).contains(&base_value),
+                    "seed {}",
+                    seed
+                );
+                assert!(
+                    base_value + mutation >= a.min(b).min(max_value),
+                    "seed {}",
+                    seed
+                );
+                assert!(
+                    base_value <= (a.max(b) + mutation).max(MIN_BRED_STAT),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+    }
+}
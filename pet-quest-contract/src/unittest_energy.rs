@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg};
+    use crate::multitest::{App, TestUser};
+    use crate::state::{
+        PetLineage, PetState, PetStats, BATTLE_ENERGY_COST, DEFAULT_QUEST_EXPLORE_SECONDS,
+        ENERGY_BASE, ENERGY_PER_STAMINA, ENERGY_REGEN_SECONDS, QUEST_ENERGY_COST,
+    };
+
+    // Helper functions
+
+    /// (energy, max energy, seconds until full)
+    fn pet_energy(app: &App, pet_id: &str) -> (u16, u16, u64) {
+        let msg = QueryMsg::PetEnergy {
+            pet_id: pet_id.to_string(),
+        };
+        match app.query_game(&msg).unwrap() {
+            QueryAnswer::PetEnergy {
+                energy,
+                max_energy,
+                seconds_until_full,
+                ..
+            } => (energy, max_energy, seconds_until_full),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn pet_with_stamina(stamina: u16) -> PetState {
+        let stats = PetStats::new(10, 10, stamina, 10, 10);
+        PetState::new(
+            "PET_0".to_string(),
+            stats.clone(),
+            stats,
+            PetLineage::default(),
+        )
+    }
+
+    /// runs three different quests back to back, leaving the pet with 29 energy used
+    fn tire_out(app: &mut App, user: &TestUser, pet_id: &str) {
+        for quest_type in ["trial_of_titans", "trial_of_endurance", "trial_of_wisdom"] {
+            app.send_on_quest(user, pet_id, quest_type).unwrap();
+            app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+            app.claim_quest(user, quest_type).unwrap();
+        }
+    }
+
+    // Energy
+
+    #[test]
+    fn test_new_pets_start_with_full_energy_from_stamina() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let pet_id = app.mint_pet(&alice);
+
+        let (energy, max_energy, seconds_until_full) = pet_energy(&app, &pet_id);
+        // stamina is rolled between 5 and 8
+        assert!((30..=36).contains(&max_energy));
+        assert_eq!(energy, max_energy);
+        assert_eq!(seconds_until_full, 0);
+
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+        let (energy, _, seconds_until_full) = pet_energy(&app, &pet_id);
+        assert_eq!(energy, max_energy - QUEST_ENERGY_COST);
+        assert_eq!(
+            seconds_until_full,
+            QUEST_ENERGY_COST as u64 * ENERGY_REGEN_SECONDS
+        );
+    }
+
+    #[test]
+    fn test_max_energy_saturates_for_huge_stamina() {
+        assert_eq!(pet_with_stamina(0).max_energy(), ENERGY_BASE);
+        assert_eq!(
+            pet_with_stamina(7).max_energy(),
+            ENERGY_BASE + 7 * ENERGY_PER_STAMINA
+        );
+        assert_eq!(
+            pet_with_stamina(u16::MAX / ENERGY_PER_STAMINA).max_energy(),
+            u16::MAX
+        );
+        assert_eq!(pet_with_stamina(u16::MAX).max_energy(), u16::MAX);
+    }
+
+    #[test]
+    fn test_exhausted_pets_cannot_quest_until_rested() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let pet_id = app.mint_pet(&alice);
+        let (_, max_energy, _) = pet_energy(&app, &pet_id);
+
+        // one point regenerated during the three quests
+        tire_out(&mut app, &alice, &pet_id);
+        let (energy, _, seconds_until_full) = pet_energy(&app, &pet_id);
+        assert_eq!(energy, max_energy - 29);
+        assert_eq!(seconds_until_full, 29 * ENERGY_REGEN_SECONDS - 30);
+
+        let err = app
+            .send_on_quest(&alice, &pet_id, "trial_of_resilience")
+            .unwrap_err();
+        assert!(err.contains("pet_exhausted"), "{}", err);
+
+        app.advance_time(seconds_until_full);
+        assert_eq!(pet_energy(&app, &pet_id), (max_energy, max_energy, 0));
+        app.send_on_quest(&alice, &pet_id, "trial_of_resilience")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_battles_spend_energy_of_both_pets() {
+        let mut app = App::new();
+        let alice = app.funded_user("alice", 100);
+        let bob = app.funded_user("bob", 100);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        let (_, bob_max, _) = pet_energy(&app, &bob_pet);
+
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+        let permits = app.permits(&bob);
+        let msg = ExecuteMsg::AcceptBattle {
+            battle_id: 0,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&bob.address, &msg).unwrap();
+        assert_eq!(pet_energy(&app, &bob_pet).0, bob_max - BATTLE_ENERGY_COST);
+
+        // a pet with at most 36 energy has enough for two battles
+        let other_bob_pet = app.mint_pet(&bob);
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+        let err = app
+            .battle(&alice, &alice_pet, &other_bob_pet, 10)
+            .unwrap_err();
+        assert!(err.contains("pet_exhausted"), "{}", err);
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod tests {
+    use crate::battle::BattleOutcome;
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryWithPermits};
+    use crate::multitest::{App, TestUser};
+    use crate::state::{
+        BattleInfoWithoutInitiator, BattleStatus, Stat, DEFAULT_QUEST_EXPLORE_SECONDS,
+    };
+
+    // Helper functions
+
+    fn my_battles(app: &App, user: &TestUser) -> Vec<BattleInfoWithoutInitiator> {
+        let query = QueryWithPermits::MyBattles {
+            pet_permit: app.permits(user).pet_permit,
+        };
+        match app.query_with_permits(user, query).unwrap() {
+            QueryAnswer::Battles { battles } => battles,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    // Flows
+
+    #[test]
+    fn test_mint_pet_registers_nft_and_state() {
+        let (mut app, alice, bob) = App::with_players(1000);
+
+        let first = app.mint_pet(&alice);
+        let second = app.mint_pet(&bob);
+        assert_eq!(first, "PET_0");
+        assert_eq!(second, "PET_1");
+
+        let pets = app.my_pets(&alice);
+        assert_eq!(pets.len(), 1);
+        assert_eq!(pets[0].pet_id, first);
+        assert!(pets[0].on_quest.is_none());
+    }
+
+    #[test]
+    fn test_quest_flow_mints_loot() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let pet_id = app.mint_pet(&alice);
+
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+        let err = app
+            .send_on_quest(&alice, &pet_id, "trial_of_endurance")
+            .unwrap_err();
+        assert!(err.contains("pet_on_quest"), "{}", err);
+
+        let err = app.claim_quest(&alice, "trial_of_titans").unwrap_err();
+        assert!(err.contains("quest_not_claimable"), "{}", err);
+
+        let before = app.loot_balance(&alice);
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        app.claim_quest(&alice, "trial_of_titans").unwrap();
+        assert!(app.loot_balance(&alice) >= before);
+
+        let query = QueryWithPermits::MyQuestHistory {
+            page: None,
+            page_size: None,
+            quest_type: None,
+            outcome: None,
+        };
+        let history = match app.query_with_permits(&alice, query).unwrap() {
+            QueryAnswer::History { quest_history } => quest_history,
+            other => panic!("unexpected answer {:?}", other),
+        };
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].pet_id, pet_id);
+        assert_eq!(history[0].quest_type, "trial_of_titans");
+        assert_eq!(
+            app.loot_balance(&alice),
+            before + history[0].loot_collected as u128
+        );
+    }
+
+    #[test]
+    fn test_upgrade_burns_loot() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let pet_id = app.mint_pet(&alice);
+        let pet = app.my_pets(&alice).remove(0);
+        let cost = pet.upgrade_costs.strength as u128;
+
+        let permits = app.permits(&alice);
+        let msg = ExecuteMsg::UpgradePetStats {
+            pet_id: pet_id.clone(),
+            upgrades: vec![(Stat::Strength, 1)],
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+
+        assert_eq!(app.loot_balance(&alice), 1000 - cost);
+        let upgraded = app.my_pets(&alice).remove(0);
+        assert_eq!(upgraded.current.strength, pet.current.strength + 1);
+
+        // only the owner of the pet may upgrade it
+        let permits = app.permits(&bob);
+        let msg = ExecuteMsg::UpgradePetStats {
+            pet_id,
+            upgrades: vec![(Stat::Strength, 1)],
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        let err = app.execute_game(&bob.address, &msg).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        assert_eq!(app.loot_balance(&bob), 1000);
+    }
+
+    #[test]
+    fn test_battle_flow_pays_out_winner() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        app.battle(&alice, &alice_pet, &bob_pet, 10).unwrap();
+        assert_eq!(app.loot_balance(&alice), 990);
+
+        let battles = my_battles(&app, &bob);
+        assert_eq!(battles.len(), 1);
+        assert_eq!(battles[0].status, BattleStatus::Pending);
+
+        let permits = app.permits(&bob);
+        let msg = ExecuteMsg::AcceptBattle {
+            battle_id: battles[0].id,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&bob.address, &msg).unwrap();
+        assert_eq!(app.loot_balance(&bob), 990);
+
+        let battle = my_battles(&app, &alice).remove(0);
+        assert_eq!(battle.status, BattleStatus::Accepted);
+        let (winner, winner_pet) = match battle.outcome {
+            Some(BattleOutcome::ChallengerWin) => (&alice, alice_pet),
+            Some(BattleOutcome::DefenderWin) => (&bob, bob_pet),
+            other => panic!("accepted battle has no winner {:?}", other),
+        };
+
+        let msg = ExecuteMsg::ClaimBattle {
+            battle_id: battle.id,
+            pet_id: winner_pet,
+            pet_permit: app.permits(winner).pet_permit,
+        };
+        app.execute_game(&winner.address, &msg).unwrap();
+
+        assert_eq!(app.loot_balance(winner), 1010);
+        assert_eq!(app.loot_balance(&alice) + app.loot_balance(&bob), 2000);
+    }
+
+    #[test]
+    fn test_failed_transaction_rolls_back_every_contract() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        // the wager is larger than the game is allowed to escrow, so loot20 rejects the transfer
+        // after the game already stored the battle
+        app.give_loot(&alice, 5000);
+        app.battle(&alice, &alice_pet, &bob_pet, 2000).unwrap_err();
+
+        assert_eq!(app.loot_balance(&alice), 6000);
+        assert!(my_battles(&app, &alice).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use crate::battle::BattleOutcome;
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg, QueryWithPermits};
+    use crate::multitest::{App, TestUser};
+    use crate::state::{
+        get_leaderboard_page, rating_change, update_leaderboard, BattleRecord, BattleScore,
+        LeaderboardEntry, RatedPet, INITIAL_RATING, LEADERBOARD_BUCKET_SIZE,
+    };
+
+    // Helper functions
+
+    fn leaderboard(
+        app: &App,
+        limit: Option<u32>,
+        start_after: Option<&str>,
+    ) -> Vec<LeaderboardEntry> {
+        let msg = QueryMsg::Leaderboard {
+            limit,
+            start_after: start_after.map(str::to_string),
+        };
+        match app.query_game(&msg).unwrap() {
+            QueryAnswer::Leaderboard { entries } => entries,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    /// fights the first battle, alice's pet challenging bob's, and returns the winning and
+    /// losing pet
+    fn fight(
+        app: &mut App,
+        alice: &TestUser,
+        alice_pet: &str,
+        bob: &TestUser,
+        bob_pet: &str,
+    ) -> (String, String) {
+        app.battle(alice, alice_pet, bob_pet, 10).unwrap();
+
+        let permits = app.permits(bob);
+        let msg = ExecuteMsg::AcceptBattle {
+            battle_id: 0,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&bob.address, &msg).unwrap();
+
+        let query = QueryWithPermits::BattleDetails { battle_id: 0 };
+        match app.query_with_permits(bob, query).unwrap() {
+            QueryAnswer::BattleDetails { battle, .. } => match battle.outcome {
+                Some(BattleOutcome::ChallengerWin) => (alice_pet.to_string(), bob_pet.to_string()),
+                Some(BattleOutcome::DefenderWin) => (bob_pet.to_string(), alice_pet.to_string()),
+                other => panic!("accepted battle has no winner {:?}", other),
+            },
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    // Ratings
+
+    #[test]
+    fn test_rating_changes_follow_the_expected_score() {
+        assert_eq!(rating_change(1200, 1200, &BattleScore::Win), 16);
+        assert_eq!(rating_change(1200, 1200, &BattleScore::Loss), -16);
+        assert_eq!(rating_change(1200, 1200, &BattleScore::Draw), 0);
+
+        // the favourite gains little and the underdog a lot
+        assert_eq!(rating_change(1400, 1200, &BattleScore::Win), 7);
+        assert_eq!(rating_change(1400, 1200, &BattleScore::Loss), -24);
+        assert_eq!(rating_change(1200, 1400, &BattleScore::Win), 24);
+        assert_eq!(rating_change(1200, 1400, &BattleScore::Draw), 8);
+
+        // differences above 400 count as 400
+        assert_eq!(
+            rating_change(2000, 1000, &BattleScore::Win),
+            rating_change(1600, 1200, &BattleScore::Win)
+        );
+
+        let mut record = BattleRecord::default();
+        record.record(&BattleScore::Win, INITIAL_RATING);
+        record.record(&BattleScore::Draw, INITIAL_RATING);
+        assert_eq!(
+            record,
+            BattleRecord {
+                rating: INITIAL_RATING + 16 - 1,
+                wins: 1,
+                losses: 0,
+                draws: 1,
+            }
+        );
+    }
+
+    // Leaderboard
+
+    #[test]
+    fn test_battles_rank_pets_on_the_leaderboard() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        app.mint_pet(&bob);
+        assert!(leaderboard(&app, None, None).is_empty());
+
+        let (winner, loser) = fight(&mut app, &alice, &alice_pet, &bob, &bob_pet);
+        let entries = leaderboard(&app, None, None);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0],
+            LeaderboardEntry {
+                rank: 1,
+                pet_id: winner.clone(),
+                record: Some(BattleRecord {
+                    rating: INITIAL_RATING + 16,
+                    wins: 1,
+                    losses: 0,
+                    draws: 0,
+                }),
+            }
+        );
+        assert_eq!(entries[1].rank, 2);
+        assert_eq!(entries[1].pet_id, loser);
+        let loser_record = entries[1].record.clone().unwrap();
+        assert_eq!(loser_record.rating, INITIAL_RATING - 16);
+        assert_eq!(loser_record.losses, 1);
+
+        // pages continue after the last pet of the previous page
+        assert_eq!(leaderboard(&app, Some(1), None), entries[..1].to_vec());
+        assert_eq!(
+            leaderboard(&app, Some(1), Some(winner.as_str())),
+            entries[1..].to_vec()
+        );
+
+        // released pets leave the leaderboard
+        let (owner, pet_id) = if loser == alice_pet {
+            (&alice, alice_pet)
+        } else {
+            (&bob, bob_pet)
+        };
+        let permits = app.permits(owner);
+        let msg = ExecuteMsg::ReleasePet {
+            pet_id,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&owner.address, &msg).unwrap();
+        let entries = leaderboard(&app, None, None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].pet_id, winner);
+    }
+
+    #[test]
+    fn test_leaderboard_pages_across_rating_buckets() {
+        let mut storage = MockStorage::new();
+        let rated = |pet_id: &str, rating: u32| RatedPet {
+            pet_id: pet_id.to_string(),
+            rating,
+        };
+        let page = |storage: &MockStorage, start_after: Option<RatedPet>, limit: usize| {
+            get_leaderboard_page(start_after, limit, storage)
+                .unwrap()
+                .into_iter()
+                .map(|(rank, rated)| (rank, rated.pet_id))
+                .collect::<Vec<_>>()
+        };
+        let ranked = |entries: &[(u32, &str)]| {
+            entries
+                .iter()
+                .map(|(rank, pet_id)| (*rank, pet_id.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // ties within a bucket are ordered by pet id
+        let bucket = INITIAL_RATING;
+        for (pet_id, rating) in [
+            ("PET_3", bucket + 1),
+            ("PET_1", bucket + 1),
+            ("PET_2", bucket + 5),
+            ("PET_4", bucket + 2 * LEADERBOARD_BUCKET_SIZE),
+            ("PET_0", bucket - LEADERBOARD_BUCKET_SIZE),
+        ] {
+            update_leaderboard(pet_id, None, Some(rating), &mut storage).unwrap();
+        }
+        let all = ranked(&[
+            (1, "PET_4"),
+            (2, "PET_2"),
+            (3, "PET_1"),
+            (4, "PET_3"),
+            (5, "PET_0"),
+        ]);
+        assert_eq!(page(&storage, None, 10), all);
+        assert!(page(&storage, None, 0).is_empty());
+
+        // pages after a pet keep the ranks of the whole leaderboard
+        let start = rated("PET_2", bucket + 5);
+        assert_eq!(page(&storage, Some(start), 2), all[2..4].to_vec());
+        let start = rated("PET_1", bucket + 1);
+        assert_eq!(page(&storage, Some(start), 10), all[3..].to_vec());
+
+        // rating changes move only the pet, emptied buckets are dropped
+        let top = bucket + 2 * LEADERBOARD_BUCKET_SIZE;
+        update_leaderboard(
+            "PET_0",
+            Some(bucket - LEADERBOARD_BUCKET_SIZE),
+            Some(top + 1),
+            &mut storage,
+        )
+        .unwrap();
+        update_leaderboard("PET_4", Some(top), None, &mut storage).unwrap();
+        update_leaderboard("PET_3", Some(bucket + 1), Some(bucket + 6), &mut storage).unwrap();
+        assert_eq!(
+            page(&storage, None, 10),
+            ranked(&[(1, "PET_0"), (2, "PET_3"), (3, "PET_2"), (4, "PET_1")])
+        );
+    }
+}
@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::CanonicalAddr;
+
+    use crate::battle::BattleOutcome;
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryWithPermits};
+    use crate::multitest::{App, TestUser, PET_ADDR};
+    use crate::state::{MatchmakingEntry, INITIAL_RATING, MIN_BATTLE_WAGER};
+
+    // Helper functions
+
+    /// returns whether the pet was matched right away
+    fn enter(app: &mut App, user: &TestUser, pet_id: &str, wager: u64) -> Result<bool, String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::EnterMatchmaking {
+            pet_id: pet_id.to_string(),
+            wager,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        let response = app.execute_game(&user.address, &msg)?;
+        Ok(response.attribute("matchmaking").unwrap() == "matched")
+    }
+
+    fn leave(app: &mut App, user: &TestUser, pet_id: &str) -> Result<(), String> {
+        let msg = ExecuteMsg::LeaveMatchmaking {
+            pet_id: pet_id.to_string(),
+            pet_permit: app.permits(user).pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    /// the winning pet of the battle
+    fn winner(app: &App, user: &TestUser, battle_id: u64) -> String {
+        let query = QueryWithPermits::BattleDetails { battle_id };
+        match app.query_with_permits(user, query).unwrap() {
+            QueryAnswer::BattleDetails { battle, log } => {
+                assert!(log.is_some());
+                match battle.outcome {
+                    Some(BattleOutcome::ChallengerWin) => battle.pet_id,
+                    Some(BattleOutcome::DefenderWin) => battle.other_pet_id,
+                    other => panic!("matched battle has no winner {:?}", other),
+                }
+            }
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn entry(owner: &[u8], wager: u64, rating: u32) -> MatchmakingEntry {
+        MatchmakingEntry {
+            pet_id: "1".to_string(),
+            owner: CanonicalAddr::from(owner),
+            wager,
+            rating,
+        }
+    }
+
+    // Pairing
+
+    #[test]
+    fn test_entries_match_on_wager_rating_and_owner() {
+        let queued = entry(b"alice", 10, INITIAL_RATING);
+        assert!(entry(b"bob", 10, INITIAL_RATING).matches(&queued));
+        // ratings match up to the neighbouring bucket
+        assert!(entry(b"bob", 10, 1000).matches(&queued));
+        assert!(entry(b"bob", 10, 1599).matches(&queued));
+        assert!(!entry(b"bob", 10, 999).matches(&queued));
+        assert!(!entry(b"bob", 10, 1600).matches(&queued));
+        // wagers must be equal and players never fight themselves
+        assert!(!entry(b"bob", 20, INITIAL_RATING).matches(&queued));
+        assert!(!entry(b"alice", 10, INITIAL_RATING).matches(&queued));
+    }
+
+    #[test]
+    fn test_compatible_pets_are_paired_and_fight() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        assert!(!enter(&mut app, &alice, &alice_pet, 25).unwrap());
+        assert_eq!(app.loot_balance(&alice), 975);
+        assert!(enter(&mut app, &bob, &bob_pet, 25).unwrap());
+        assert_eq!(app.loot_balance(&bob), 975);
+
+        // the battle is settled like an accepted challenge, the waiting pet challenging
+        let winner = winner(&app, &alice, 0);
+        let (owner, loser, loser_pet) = if winner == alice_pet {
+            (&alice, &bob, &bob_pet)
+        } else {
+            (&bob, &alice, &alice_pet)
+        };
+        let msg = ExecuteMsg::ClaimBattle {
+            battle_id: 0,
+            pet_id: winner.clone(),
+            pet_permit: app.permits(owner).pet_permit,
+        };
+        app.execute_game(&owner.address, &msg).unwrap();
+        assert_eq!(app.loot_balance(owner), 1025);
+        assert_eq!(app.loot_balance(loser), 975);
+
+        // both pets left the queue once they fought
+        let err = leave(&mut app, loser, loser_pet).unwrap_err();
+        assert!(err.contains("not_in_matchmaking"), "{}", err);
+    }
+
+    #[test]
+    fn test_incompatible_pets_stay_queued() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let other_alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        assert!(!enter(&mut app, &alice, &alice_pet, 25).unwrap());
+        // pets of the same player are never paired
+        assert!(!enter(&mut app, &alice, &other_alice_pet, 25).unwrap());
+        // nor pets with different wagers
+        assert!(!enter(&mut app, &bob, &bob_pet, 30).unwrap());
+        assert_eq!(app.loot_balance(&alice), 950);
+        assert_eq!(app.loot_balance(&bob), 970);
+
+        let err = enter(&mut app, &alice, &alice_pet, 25).unwrap_err();
+        assert!(err.contains("already_in_matchmaking"), "{}", err);
+    }
+
+    #[test]
+    fn test_wagers_below_the_minimum_are_rejected() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+
+        let err = enter(&mut app, &alice, &alice_pet, MIN_BATTLE_WAGER - 1).unwrap_err();
+        assert!(err.contains("wager_too_low"), "{}", err);
+        assert_eq!(app.loot_balance(&alice), 1000);
+        assert!(!enter(&mut app, &alice, &alice_pet, MIN_BATTLE_WAGER).unwrap());
+    }
+
+    #[test]
+    fn test_longest_waiting_pet_is_paired_first() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let other_alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        enter(&mut app, &alice, &alice_pet, 25).unwrap();
+        enter(&mut app, &alice, &other_alice_pet, 25).unwrap();
+        assert!(enter(&mut app, &bob, &bob_pet, 25).unwrap());
+
+        leave(&mut app, &alice, &other_alice_pet).unwrap();
+        let err = leave(&mut app, &alice, &alice_pet).unwrap_err();
+        assert!(err.contains("not_in_matchmaking"), "{}", err);
+    }
+
+    // Leaving
+
+    #[test]
+    fn test_leaving_refunds_the_wager_and_unlocks_the_pet() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        enter(&mut app, &alice, &alice_pet, 25).unwrap();
+
+        // queued pets cannot leave on quests
+        app.advance_time(600);
+        let err = app
+            .send_on_quest(&alice, &alice_pet, "trial_of_titans")
+            .unwrap_err();
+        assert!(err.contains("pet_in_battle"), "{}", err);
+
+        let err = leave(&mut app, &bob, &alice_pet).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        leave(&mut app, &alice, &alice_pet).unwrap();
+        assert_eq!(app.loot_balance(&alice), 1000);
+        app.send_on_quest(&alice, &alice_pet, "trial_of_titans")
+            .unwrap();
+
+        let err = leave(&mut app, &alice, &alice_pet).unwrap_err();
+        assert!(err.contains("not_in_matchmaking"), "{}", err);
+    }
+
+    #[test]
+    fn test_transferred_pets_are_refunded_instead_of_paired() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let carol = app.user("carol");
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        enter(&mut app, &alice, &alice_pet, 25).unwrap();
+
+        let transfer = format!(
+            "{{\"transfer_nft\":{{\"recipient\":\"{}\",\"token_id\":\"{}\"}}}}",
+            carol.address, alice_pet
+        );
+        app.execute_raw(&alice.address, PET_ADDR, transfer.as_bytes())
+            .unwrap();
+
+        // the stale entry is dropped and refunded to the player that queued it
+        assert!(!enter(&mut app, &bob, &bob_pet, 25).unwrap());
+        assert_eq!(app.loot_balance(&alice), 1000);
+        assert_eq!(app.loot_balance(&bob), 975);
+    }
+
+    #[test]
+    fn test_releasing_a_queued_pet_refunds_the_wager() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        enter(&mut app, &alice, &alice_pet, 25).unwrap();
+
+        let permits = app.permits(&alice);
+        let msg = ExecuteMsg::ReleasePet {
+            pet_id: alice_pet,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+        assert_eq!(app.loot_balance(&alice), 1000);
+    }
+}
@@ -0,0 +1,237 @@
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_slice, to_vec};
+    use snip721_reference_impl::msg::{QueryAnswer as PetQueryAnswer, QueryMsg as PetQueryMsg};
+    use snip721_reference_impl::token::Extension;
+
+    use crate::msg::ExecuteMsg;
+    use crate::multitest::{App, TestUser, ADMIN, PET_ADDR};
+    use crate::state::{
+        PetPrivacyPolicy, ProgressionConfig, Stat, DEFAULT_QUEST_EXPLORE_SECONDS, MAX_PETS_INFO,
+        MAX_PET_NAME_LENGTH,
+    };
+
+    /// the name and the trait type and value pairs of one side of a token's metadata
+    type TokenMetadata = (Option<String>, Vec<(String, String)>);
+
+    // Helper functions
+
+    fn setup() -> (App, TestUser, String) {
+        let mut app = App::new();
+        let alice = app.funded_user("alice", 1000);
+        let pet_id = app.mint_pet(&alice);
+        (app, alice, pet_id)
+    }
+
+    fn token_metadata(extension: Option<Extension>) -> TokenMetadata {
+        let extension = extension.unwrap_or_default();
+        let attributes = extension
+            .attributes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|attribute| (attribute.trait_type.unwrap(), attribute.value))
+            .collect();
+        (extension.name, attributes)
+    }
+
+    fn public_metadata(app: &App, pet_id: &str) -> TokenMetadata {
+        let msg = PetQueryMsg::NftInfo {
+            token_id: pet_id.to_string(),
+        };
+        match app.query(PET_ADDR, &msg).unwrap() {
+            PetQueryAnswer::NftInfo { extension, .. } => token_metadata(extension),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    /// the private metadata as the owner sees it through their pet721 permit
+    fn private_metadata(app: &App, user: &TestUser, pet_id: &str) -> TokenMetadata {
+        let permit = to_vec(&app.permits(user).pet_permit).unwrap();
+        let query = format!(
+            "{{\"with_permit\":{{\"query\":{{\"private_metadata\":{{\"token_id\":\"{}\"}}}},\"permit\":{}}}}}",
+            pet_id,
+            String::from_utf8_lossy(&permit)
+        );
+        let msg: PetQueryMsg = from_slice(query.as_bytes()).unwrap();
+        match app.query(PET_ADDR, &msg).unwrap() {
+            PetQueryAnswer::PrivateMetadata { extension, .. } => token_metadata(extension),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn value(attributes: &[(String, String)], trait_type: &str) -> Option<String> {
+        attributes
+            .iter()
+            .find(|(attribute, _)| attribute == trait_type)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn rename_pet(
+        app: &mut App,
+        user: &TestUser,
+        pet_id: &str,
+        name: Option<&str>,
+    ) -> Result<(), String> {
+        let msg = ExecuteMsg::RenamePet {
+            pet_id: pet_id.to_string(),
+            name: name.map(|name| name.to_string()),
+            pet_permit: app.permits(user).pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn resync_pets(app: &mut App, user: &TestUser, pet_ids: Vec<String>) -> Result<String, String> {
+        let response = app.execute_game(&user.address, &ExecuteMsg::ResyncPets { pet_ids })?;
+        Ok(response.attribute("resynced").unwrap())
+    }
+
+    // Metadata
+
+    #[test]
+    fn test_minted_token_shows_level_stats_and_hidden_maxes() {
+        let (app, alice, pet_id) = setup();
+        let pet = app.my_pet(&alice, &pet_id);
+
+        let (name, public) = public_metadata(&app, &pet_id);
+        assert_eq!(name, None);
+        let traits = pet.traits.clone().unwrap();
+        assert_eq!(value(&public, "species"), Some(traits.species));
+        assert_eq!(
+            value(&public, "rarity"),
+            Some(traits.rarity.name().to_string())
+        );
+        assert_eq!(value(&public, "level"), Some("1".to_string()));
+
+        // the max stats stay private by default, as they do in the public pet queries
+        let (_, private) = private_metadata(&app, &alice, &pet_id);
+        for stat in Stat::all() {
+            let current = pet.current.get_stat(stat.clone()).to_string();
+            let max = pet.max.get_stat(stat.clone()).to_string();
+            assert_eq!(value(&public, stat.name()), Some(current));
+            let max_trait = format!("max_{}", stat.name());
+            assert_eq!(value(&public, &max_trait), None);
+            assert_eq!(value(&private, &max_trait), Some(max));
+        }
+    }
+
+    #[test]
+    fn test_upgrades_and_level_ups_update_the_token() {
+        let (mut app, alice, pet_id) = setup();
+        let before = app.my_pet(&alice, &pet_id);
+
+        let permits = app.permits(&alice);
+        let msg = ExecuteMsg::UpgradePetStats {
+            pet_id: pet_id.clone(),
+            upgrades: vec![(Stat::Health, 1)],
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+        let (_, public) = public_metadata(&app, &pet_id);
+        let health = (before.current.health + 1).to_string();
+        assert_eq!(value(&public, "health"), Some(health));
+
+        let config = ProgressionConfig {
+            xp_on_fail: 10,
+            xp_on_pass: 10,
+            xp_on_exceptional_pass: 10,
+            level_thresholds: vec![10],
+            stat_points_per_level: 1,
+            max_stats_per_level: 1,
+        };
+        app.execute_game(ADMIN, &ExecuteMsg::SetProgressionConfig { config })
+            .unwrap();
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        app.claim_quest(&alice, "trial_of_titans").unwrap();
+
+        let (_, public) = public_metadata(&app, &pet_id);
+        assert_eq!(value(&public, "level"), Some("2".to_string()));
+        let (_, private) = private_metadata(&app, &alice, &pet_id);
+        let max_luck = (before.max.luck + 1).to_string();
+        assert_eq!(value(&private, "max_luck"), Some(max_luck));
+
+        let msg = ExecuteMsg::SpendStatPoints {
+            pet_id: pet_id.clone(),
+            stat: Stat::Luck,
+            points: 1,
+            pet_permit: app.permits(&alice).pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+        let (_, public) = public_metadata(&app, &pet_id);
+        let luck = (before.current.luck + 1).to_string();
+        assert_eq!(value(&public, "luck"), Some(luck));
+
+        // nothing drifted, so there is nothing to resync
+        assert_eq!(resync_pets(&mut app, &alice, vec![pet_id]).unwrap(), "0");
+    }
+
+    #[test]
+    fn test_rename_pet_names_the_token() {
+        let (mut app, alice, pet_id) = setup();
+        let bob = app.user("bob");
+
+        rename_pet(&mut app, &alice, &pet_id, Some("Sir Fluffington")).unwrap();
+        assert_eq!(
+            public_metadata(&app, &pet_id).0,
+            Some("Sir Fluffington".to_string())
+        );
+        assert_eq!(
+            app.my_pet(&alice, &pet_id).name,
+            Some("Sir Fluffington".to_string())
+        );
+
+        let err = rename_pet(&mut app, &bob, &pet_id, Some("Stolen")).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        let too_long = "a".repeat(MAX_PET_NAME_LENGTH + 1);
+        for name in ["", "   ", " padded", "line\nbreak", too_long.as_str()] {
+            let err = rename_pet(&mut app, &alice, &pet_id, Some(name)).unwrap_err();
+            assert!(err.contains("invalid_pet_name"), "{:?} {}", name, err);
+        }
+        let longest = "é".repeat(MAX_PET_NAME_LENGTH);
+        rename_pet(&mut app, &alice, &pet_id, Some(&longest)).unwrap();
+
+        rename_pet(&mut app, &alice, &pet_id, None).unwrap();
+        assert_eq!(public_metadata(&app, &pet_id).0, None);
+    }
+
+    #[test]
+    fn test_resync_pets_rewrites_drifted_tokens() {
+        let (mut app, alice, pet_id) = setup();
+        let bob = app.user("bob");
+        let other_pet_id = app.mint_pet(&bob);
+        let pet = app.my_pet(&alice, &pet_id);
+
+        // tokens follow a new privacy policy only once they are resynced
+        let policy = PetPrivacyPolicy {
+            max_stats: true,
+            current_stats: false,
+            ..PetPrivacyPolicy::default()
+        };
+        app.execute_game(ADMIN, &ExecuteMsg::SetPrivacyPolicy { policy })
+            .unwrap();
+        assert_eq!(value(&public_metadata(&app, &pet_id).1, "max_health"), None);
+
+        // anyone can resync any pet
+        let pet_ids = vec![pet_id.clone(), other_pet_id.clone()];
+        assert_eq!(resync_pets(&mut app, &bob, pet_ids.clone()).unwrap(), "2");
+        let (_, public) = public_metadata(&app, &pet_id);
+        let (_, private) = private_metadata(&app, &alice, &pet_id);
+        let max_health = pet.max.health.to_string();
+        assert_eq!(value(&public, "max_health"), Some(max_health));
+        assert_eq!(value(&public, "health"), None);
+        assert_eq!(
+            value(&private, "health"),
+            Some(pet.current.health.to_string())
+        );
+        assert_eq!(resync_pets(&mut app, &bob, pet_ids).unwrap(), "0");
+
+        let err = resync_pets(&mut app, &bob, vec!["PET_99".to_string()]).unwrap_err();
+        assert!(err.contains("pet_not_found"), "{}", err);
+        let pet_ids = vec![pet_id; MAX_PETS_INFO as usize + 1];
+        let err = resync_pets(&mut app, &bob, pet_ids).unwrap_err();
+        assert!(err.contains("too_many_pets"), "{}", err);
+    }
+}
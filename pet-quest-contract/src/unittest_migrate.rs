@@ -0,0 +1,392 @@
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{from_binary, Addr, Api, CanonicalAddr, OwnedDeps, Response, Timestamp};
+
+    use crate::battle::BattleOutcome;
+    use crate::contract::{migrate, query};
+    use crate::error::ContractError;
+    use crate::migrate::{v0, v2, v3, CONTRACT_NAME, STORAGE_VERSION};
+    use crate::msg::{MigrateMsg, QueryAnswer, QueryMsg};
+    use crate::state::{
+        default_quest_catalogue, get_pet_quest_history, get_player_quest_history,
+        get_quest_catalogue, BattleStatus, Config, ContractData, ContractInfo, Expiration,
+        PetStats, QuestHistory, QuestHistoryFilter, QuestOutcome, BATTLES, BATTLE_COUNTER, CONFIG,
+        CONTRACT_INFO, LOOT20_DATA, PET721_DATA, PETS, PET_BATTLES, PET_COUNTER, PRNG_SEED, QUESTS,
+    };
+
+    type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+    // Helper functions
+
+    fn stats(value: u16) -> PetStats {
+        PetStats::new(value, value, value, value, value)
+    }
+
+    fn legacy_quest(quest_type: &str, pet_id: Option<&str>) -> v0::Quest {
+        v0::Quest {
+            pet_id: pet_id.map(|pet_id| pet_id.to_string()),
+            quest_type: quest_type.to_string(),
+            awaiting_claiming: pet_id.is_some(),
+            finished_exploring: pet_id.map(|_| Timestamp::from_seconds(1_000)),
+            finished_cooldown: pet_id.map(|_| Timestamp::from_seconds(1_060)),
+            base_loot: 3,
+            difficulty: 2,
+            difficulty_increment: 1,
+        }
+    }
+
+    fn alice(deps: &MockDeps) -> CanonicalAddr {
+        deps.api.addr_canonicalize("alice").unwrap()
+    }
+
+    /// storage as written by the contract before storage versions were introduced
+    fn v0_snapshot() -> MockDeps {
+        let mut deps = mock_dependencies();
+        let alice = alice(&deps);
+        let storage = &mut deps.storage;
+
+        let config = Config {
+            admin: Addr::unchecked("admin"),
+            max_stats: 20,
+            entropy: "entropy".to_string(),
+        };
+        v0::CONFIG.save(storage, &config).unwrap();
+        LOOT20_DATA
+            .save(
+                storage,
+                &ContractData {
+                    hash: "loot_hash".to_string(),
+                    addr: "loot".to_string(),
+                },
+            )
+            .unwrap();
+        PET721_DATA
+            .save(
+                storage,
+                &ContractData {
+                    hash: "pet_hash".to_string(),
+                    addr: "pet".to_string(),
+                },
+            )
+            .unwrap();
+
+        PET_COUNTER.save(storage, &2).unwrap();
+        let questing_pet = v0::PetState {
+            pet_id: "PET_0".to_string(),
+            on_quest: Some(legacy_quest("Trial Of Titans", Some("PET_0"))),
+            current: stats(6),
+            max: stats(15),
+            upgrade_costs: stats(30),
+        };
+        let idle_pet = v0::PetState {
+            pet_id: "PET_1".to_string(),
+            on_quest: None,
+            current: stats(5),
+            max: stats(12),
+            upgrade_costs: stats(25),
+        };
+        v0::PETS
+            .insert(storage, &"PET_0".to_string(), &questing_pet)
+            .unwrap();
+        v0::PETS
+            .insert(storage, &"PET_1".to_string(), &idle_pet)
+            .unwrap();
+
+        let quests = vec![
+            legacy_quest("Trial Of Resilience", None),
+            legacy_quest("Trial Of Endurance", None),
+            legacy_quest("Trial Of Titans", Some("PET_0")),
+            legacy_quest("Trial Of Wisdom", None),
+        ];
+        v0::QUESTS.insert(storage, &alice, &quests).unwrap();
+
+        let history = vec![v0::QuestHistory {
+            pet_id: "PET_1".to_string(),
+            quest_type: "Trial Of Wisdom".to_string(),
+            time_started: Timestamp::from_seconds(100),
+            time_ended: Timestamp::from_seconds(130),
+            loot_collected: 9,
+            outcome: "Exceptional Pass".to_string(),
+        }];
+        v0::QUEST_HISTORY.insert(storage, &alice, &history).unwrap();
+
+        BATTLE_COUNTER.save(storage, &1).unwrap();
+        let battle = v0::BattleInfo {
+            id: 0,
+            initiator_address: alice.clone(),
+            pet_id: "PET_0".to_string(),
+            other_pet_id: "PET_1".to_string(),
+            wager: 10,
+            status: "accepted".to_string(),
+            outcome: Some(true),
+        };
+        v0::BATTLES.insert(storage, &0, &battle).unwrap();
+        PET_BATTLES
+            .insert(storage, &"PET_0".to_string(), &vec![0])
+            .unwrap();
+        PET_BATTLES
+            .insert(storage, &"PET_1".to_string(), &vec![0])
+            .unwrap();
+
+        deps
+    }
+
+    fn run_migrate(deps: &mut MockDeps) -> Result<Response, ContractError> {
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {})
+    }
+
+    fn attribute(response: &Response, key: &str) -> String {
+        response
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.clone())
+            .unwrap()
+    }
+
+    // Migrations
+
+    #[test]
+    fn test_migrate_v0_snapshot() {
+        let mut deps = v0_snapshot();
+        let response = run_migrate(&mut deps).unwrap();
+        assert_eq!(attribute(&response, "from_storage_version"), "0");
+        assert_eq!(
+            attribute(&response, "storage_version"),
+            STORAGE_VERSION.to_string()
+        );
+
+        let alice = alice(&deps);
+        let storage = &deps.storage;
+
+        // every store moved to its new layout
+        let pet = PETS.get(storage, &"PET_0".to_string()).unwrap();
+        let on_quest = pet.on_quest.unwrap();
+        assert_eq!(on_quest.quest_type, "trial_of_titans");
+        assert_eq!(on_quest.pet_id, Some("PET_0".to_string()));
+        assert_eq!(pet.current, stats(6));
+        assert_eq!(pet.max, stats(15));
+        assert!(PETS
+            .get(storage, &"PET_1".to_string())
+            .unwrap()
+            .on_quest
+            .is_none());
+
+        let quests = QUESTS.get(storage, &alice).unwrap();
+        let quest_types: Vec<String> = quests
+            .iter()
+            .map(|quest| quest.quest_type.clone())
+            .collect();
+        assert_eq!(
+            quest_types,
+            vec![
+                "trial_of_resilience",
+                "trial_of_endurance",
+                "trial_of_titans",
+                "trial_of_wisdom"
+            ]
+        );
+        assert!(quests[2].awaiting_claiming);
+        assert_eq!(quests[2].difficulty_increment, 1);
+
+        let history =
+            get_player_quest_history(&alice, &QuestHistoryFilter::default(), 0, 10, storage)
+                .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].quest_type, "trial_of_wisdom");
+        assert_eq!(history[0].outcome, QuestOutcome::ExceptionalPass);
+        assert_eq!(history[0].loot_collected, 9);
+
+        let battle = BATTLES.get(storage, &0).unwrap();
+        assert_eq!(battle.status, BattleStatus::Accepted);
+        assert_eq!(battle.outcome, Some(BattleOutcome::ChallengerWin));
+        assert_eq!(battle.initiator_address, alice);
+        // the wagers of battles from before escrow were burned
+        assert!(!battle.escrowed);
+
+        // untouched stores keep their data
+        assert_eq!(PET_COUNTER.load(storage).unwrap(), 2);
+        assert_eq!(
+            PET_BATTLES.get(storage, &"PET_1".to_string()),
+            Some(vec![0])
+        );
+        assert_eq!(CONFIG.load(storage).unwrap().max_stats, 20);
+        assert!(PRNG_SEED.may_load(storage).unwrap().is_some());
+        assert_eq!(
+            get_quest_catalogue(storage).unwrap(),
+            default_quest_catalogue()
+        );
+
+        // the legacy stores are emptied
+        assert!(v0::CONFIG.may_load(storage).unwrap().is_none());
+        assert!(v0::PETS.get(storage, &"PET_0".to_string()).is_none());
+        assert!(v0::QUESTS.get(storage, &alice).is_none());
+        assert!(v0::QUEST_HISTORY.get(storage, &alice).is_none());
+        assert!(v0::BATTLES.get(storage, &0).is_none());
+
+        let answer: QueryAnswer =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ContractInfo {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            answer,
+            QueryAnswer::ContractInfo {
+                contract: CONTRACT_NAME.to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                storage_version: STORAGE_VERSION,
+            }
+        );
+        let answer: QueryAnswer =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert!(matches!(answer, QueryAnswer::Config { max_stats: 20, .. }));
+    }
+
+    #[test]
+    fn test_migrate_v2_battle_outcomes() {
+        let mut deps = mock_dependencies();
+        let alice = alice(&deps);
+        let info = ContractInfo {
+            contract: CONTRACT_NAME.to_string(),
+            version: "0.2.0".to_string(),
+            storage_version: 2,
+        };
+        CONTRACT_INFO.save(&mut deps.storage, &info).unwrap();
+
+        for (battle_id, status, outcome) in [
+            (0, BattleStatus::Accepted, Some(false)),
+            (1, BattleStatus::Pending, None),
+        ] {
+            let battle = v2::BattleInfo {
+                id: battle_id,
+                initiator_address: alice.clone(),
+                pet_id: "PET_0".to_string(),
+                other_pet_id: "PET_1".to_string(),
+                wager: 10,
+                status,
+                outcome,
+                escrowed: true,
+                expiration: Expiration::Never,
+            };
+            v2::BATTLES
+                .insert(&mut deps.storage, &battle_id, &battle)
+                .unwrap();
+        }
+
+        let response = run_migrate(&mut deps).unwrap();
+        assert_eq!(attribute(&response, "from_storage_version"), "2");
+        let battle = BATTLES.get(&deps.storage, &0).unwrap();
+        assert_eq!(battle.outcome, Some(BattleOutcome::DefenderWin));
+        assert!(battle.escrowed);
+        assert_eq!(BATTLES.get(&deps.storage, &1).unwrap().outcome, None);
+    }
+
+    #[test]
+    fn test_migrate_v3_quest_history() {
+        let mut deps = mock_dependencies();
+        let alice = alice(&deps);
+        let info = ContractInfo {
+            contract: CONTRACT_NAME.to_string(),
+            version: "0.3.0".to_string(),
+            storage_version: 3,
+        };
+        CONTRACT_INFO.save(&mut deps.storage, &info).unwrap();
+
+        let history: Vec<QuestHistory> = [("PET_0", 10), ("PET_1", 20), ("PET_0", 30)]
+            .into_iter()
+            .map(|(pet_id, time_started)| QuestHistory {
+                pet_id: pet_id.to_string(),
+                quest_type: "trial_of_wisdom".to_string(),
+                time_started: Timestamp::from_seconds(time_started),
+                time_ended: Timestamp::from_seconds(time_started + 5),
+                loot_collected: 3,
+                outcome: QuestOutcome::Pass,
+            })
+            .collect();
+        v3::QUEST_HISTORY
+            .insert(&mut deps.storage, &alice, &history)
+            .unwrap();
+
+        let response = run_migrate(&mut deps).unwrap();
+        assert_eq!(attribute(&response, "from_storage_version"), "3");
+        let storage = &deps.storage;
+        let filter = QuestHistoryFilter::default();
+        // the histories list the latest quests first
+        let migrated = get_player_quest_history(&alice, &filter, 0, 10, storage).unwrap();
+        assert_eq!(migrated, history.iter().rev().cloned().collect::<Vec<_>>());
+        let pet_history = get_pet_quest_history("PET_0", &filter, 0, 10, storage).unwrap();
+        assert_eq!(pet_history, vec![history[2].clone(), history[0].clone()]);
+        assert!(v3::QUEST_HISTORY.get(storage, &alice).is_none());
+    }
+
+    #[test]
+    fn test_migrate_current_version_keeps_data() {
+        let mut deps = v0_snapshot();
+        run_migrate(&mut deps).unwrap();
+        let pet = PETS.get(&deps.storage, &"PET_0".to_string()).unwrap();
+        let seed = PRNG_SEED.load(&deps.storage).unwrap();
+
+        let response = run_migrate(&mut deps).unwrap();
+        assert_eq!(
+            attribute(&response, "from_storage_version"),
+            STORAGE_VERSION.to_string()
+        );
+        assert_eq!(PETS.get(&deps.storage, &"PET_0".to_string()).unwrap(), pet);
+        assert_eq!(PRNG_SEED.load(&deps.storage).unwrap(), seed);
+    }
+
+    #[test]
+    fn test_migrate_rejects_other_contracts() {
+        let mut deps = v0_snapshot();
+        let info = ContractInfo {
+            contract: "snip20-reference-impl".to_string(),
+            version: "1.0.0".to_string(),
+            storage_version: 0,
+        };
+        CONTRACT_INFO.save(&mut deps.storage, &info).unwrap();
+
+        let err = run_migrate(&mut deps).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MigrationContractMismatch {
+                contract: "snip20-reference-impl".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_storage() {
+        let mut deps = v0_snapshot();
+        let info = ContractInfo {
+            contract: CONTRACT_NAME.to_string(),
+            version: "9.0.0".to_string(),
+            storage_version: STORAGE_VERSION + 1,
+        };
+        CONTRACT_INFO.save(&mut deps.storage, &info).unwrap();
+
+        let err = run_migrate(&mut deps).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnsupportedStorageVersion {
+                version: STORAGE_VERSION + 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_migrate_rejects_unknown_legacy_values() {
+        let mut deps = v0_snapshot();
+        let alice = alice(&deps);
+        let quests = vec![legacy_quest("Trial Of Nothing", None)];
+        v0::QUESTS
+            .insert(&mut deps.storage, &alice, &quests)
+            .unwrap();
+
+        let err = run_migrate(&mut deps).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnknownLegacyValue {
+                value: "Trial Of Nothing".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,234 @@
+#[cfg(test)]
+mod tests {
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg, QueryWithPermits};
+    use crate::multitest::{App, TestUser, ADMIN};
+    use crate::state::{
+        BattleRecord, PetEnergy, PetLineage, PetProgression, PetState, PetStats, PetUpgrades,
+        ProgressionConfig, QuestHistory, QuestOutcome, Stat, UpgradeConfig,
+        DEFAULT_QUEST_COOLDOWN_SECONDS, DEFAULT_QUEST_EXPLORE_SECONDS, INITIAL_RATING,
+        MAX_STAT_VALUE,
+    };
+
+    // Helper functions
+
+    fn my_history(app: &App, user: &TestUser) -> Vec<QuestHistory> {
+        let query = QueryWithPermits::MyQuestHistory {
+            page: None,
+            page_size: None,
+            quest_type: None,
+            outcome: None,
+        };
+        match app.query_with_permits(user, query).unwrap() {
+            QueryAnswer::History { quest_history } => quest_history,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn pet_progression(app: &App, pet_id: &str) -> (PetProgression, Option<u64>) {
+        let msg = QueryMsg::PetProgression {
+            pet_id: pet_id.to_string(),
+        };
+        match app.query_game(&msg).unwrap() {
+            QueryAnswer::PetProgression {
+                progression,
+                next_level_xp,
+                ..
+            } => (progression, next_level_xp),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    /// sends the pet on a quest and claims it, waiting out the cooldown of the previous one
+    fn complete_quest(app: &mut App, user: &TestUser, pet_id: &str) {
+        app.send_on_quest(user, pet_id, "trial_of_titans").unwrap();
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        app.claim_quest(user, "trial_of_titans").unwrap();
+        app.advance_time(DEFAULT_QUEST_COOLDOWN_SECONDS);
+    }
+
+    fn spend_stat_points(
+        app: &mut App,
+        user: &TestUser,
+        pet_id: &str,
+        stat: Stat,
+        points: u16,
+    ) -> Result<(), String> {
+        let msg = ExecuteMsg::SpendStatPoints {
+            pet_id: pet_id.to_string(),
+            stat,
+            points,
+            pet_permit: app.permits(user).pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn fast_levels() -> ProgressionConfig {
+        ProgressionConfig {
+            xp_on_fail: 10,
+            xp_on_pass: 10,
+            xp_on_exceptional_pass: 10,
+            level_thresholds: vec![10, 20],
+            stat_points_per_level: 2,
+            max_stats_per_level: 1,
+        }
+    }
+
+    // Progression
+
+    #[test]
+    fn test_claimed_quest_awards_experience() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let pet_id = app.mint_pet(&alice);
+        assert_eq!(
+            pet_progression(&app, &pet_id),
+            (PetProgression::default(), Some(10))
+        );
+
+        complete_quest(&mut app, &alice, &pet_id);
+
+        let outcome = my_history(&app, &alice).remove(0).outcome;
+        let (progression, _) = pet_progression(&app, &pet_id);
+        assert_eq!(
+            progression.xp,
+            ProgressionConfig::default().xp_for(&outcome)
+        );
+        let counts = (
+            progression.quests_failed,
+            progression.quests_passed,
+            progression.quests_exceptional,
+        );
+        match outcome {
+            QuestOutcome::Fail => assert_eq!(counts, (1, 0, 0)),
+            QuestOutcome::Pass => assert_eq!(counts, (0, 1, 0)),
+            QuestOutcome::ExceptionalPass => assert_eq!(counts, (0, 0, 1)),
+        }
+    }
+
+    #[test]
+    fn test_level_ups_grant_stat_points_and_caps() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let bob = app.user("bob");
+        let pet_id = app.mint_pet(&alice);
+        app.execute_game(
+            ADMIN,
+            &ExecuteMsg::SetProgressionConfig {
+                config: fast_levels(),
+            },
+        )
+        .unwrap();
+        let before = app.my_pet(&alice, &pet_id);
+
+        complete_quest(&mut app, &alice, &pet_id);
+        let (progression, next_level_xp) = pet_progression(&app, &pet_id);
+        assert_eq!(progression.level, 2);
+        assert_eq!(progression.stat_points, 2);
+        assert_eq!(next_level_xp, Some(20));
+        let pet = app.my_pet(&alice, &pet_id);
+        assert_eq!(pet.max.health, before.max.health + 1);
+        assert_eq!(pet.max.luck, before.max.luck + 1);
+
+        // stat points are spent without loot and only by the owner
+        let err = spend_stat_points(&mut app, &bob, &pet_id, Stat::Health, 1).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        let err = spend_stat_points(&mut app, &alice, &pet_id, Stat::Health, 3).unwrap_err();
+        assert!(err.contains("insufficient_stat_points"), "{}", err);
+        spend_stat_points(&mut app, &alice, &pet_id, Stat::Health, 1).unwrap();
+        assert_eq!(
+            app.my_pet(&alice, &pet_id).current.health,
+            before.current.health + 1
+        );
+        assert_eq!(pet_progression(&app, &pet_id).0.stat_points, 1);
+
+        // the last threshold is the max level
+        complete_quest(&mut app, &alice, &pet_id);
+        complete_quest(&mut app, &alice, &pet_id);
+        let (progression, next_level_xp) = pet_progression(&app, &pet_id);
+        assert_eq!(progression.level, 3);
+        assert_eq!(progression.xp, 30);
+        assert_eq!(progression.stat_points, 3);
+        assert_eq!(next_level_xp, None);
+    }
+
+    #[test]
+    fn test_progression_config_is_validated() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+
+        let msg = ExecuteMsg::SetProgressionConfig {
+            config: fast_levels(),
+        };
+        let err = app.execute_game(&alice.address, &msg).unwrap_err();
+        assert!(err.contains("unauthorized"), "{}", err);
+
+        for level_thresholds in [vec![0, 10], vec![20, 10], vec![10, 10]] {
+            let config = ProgressionConfig {
+                level_thresholds,
+                ..fast_levels()
+            };
+            let err = app
+                .execute_game(ADMIN, &ExecuteMsg::SetProgressionConfig { config })
+                .unwrap_err();
+            assert!(err.contains("invalid_progression_config"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_award_quest_applies_every_level_reached() {
+        let stats = PetStats::new(5, 5, 5, 5, 5);
+        let mut pet = PetState {
+            pet_id: "PET_0".to_string(),
+            on_quest: None,
+            current: stats.clone(),
+            max: PetStats::new(12, 12, 12, 12, 12),
+            upgrade_costs: UpgradeConfig::default().upgrade_costs(&stats),
+            power: 25,
+            battle_rating: INITIAL_RATING,
+            upgrades: PetUpgrades::default(),
+            progression: PetProgression::default(),
+            energy: PetEnergy::default(),
+            record: BattleRecord::default(),
+            lineage: PetLineage::default(),
+            traits: None,
+            name: None,
+        };
+        let config = ProgressionConfig {
+            xp_on_exceptional_pass: 25,
+            level_thresholds: vec![5, 10, 30],
+            ..fast_levels()
+        };
+
+        assert_eq!(pet.award_quest(&QuestOutcome::ExceptionalPass, &config), 2);
+        assert_eq!(pet.progression.level, 3);
+        assert_eq!(pet.progression.stat_points, 4);
+        assert_eq!(pet.max, PetStats::new(14, 14, 14, 14, 14));
+        assert_eq!(pet.award_quest(&QuestOutcome::Fail, &config), 1);
+        assert_eq!(pet.progression.xp, 35);
+        assert_eq!(pet.progression.quests_exceptional, 1);
+        assert_eq!(pet.progression.quests_failed, 1);
+
+        // huge level ups saturate and the max stats stop at the ceiling
+        let config = ProgressionConfig {
+            xp_on_fail: u64::MAX,
+            level_thresholds: vec![5, 10, 30, 40, 50],
+            stat_points_per_level: u16::MAX,
+            max_stats_per_level: u16::MAX,
+            ..config
+        };
+        pet.award_quest(&QuestOutcome::Fail, &config);
+        assert_eq!(pet.progression.level, 6);
+        assert_eq!(pet.progression.xp, u64::MAX);
+        assert_eq!(pet.progression.stat_points, u16::MAX);
+        assert_eq!(
+            pet.max,
+            PetStats::new(
+                MAX_STAT_VALUE,
+                MAX_STAT_VALUE,
+                MAX_STAT_VALUE,
+                MAX_STAT_VALUE,
+                MAX_STAT_VALUE
+            )
+        );
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod tests {
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg};
+    use crate::multitest::{App, TestUser, ADMIN};
+    use crate::state::{
+        BattleInfoWithoutInitiator, Expiration, PetPrivacyPolicy, PublicPetInfo, MAX_PETS_INFO,
+    };
+
+    // Helper functions
+
+    fn pet_info(app: &App, pet_id: &str) -> Result<PublicPetInfo, String> {
+        let msg = QueryMsg::PetInfo {
+            pet_id: pet_id.to_string(),
+        };
+        match app.query_game(&msg)? {
+            QueryAnswer::PetInfo { pet } => Ok(pet),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn pet_battles(app: &App, pet_id: &str) -> Result<Vec<BattleInfoWithoutInitiator>, String> {
+        let msg = QueryMsg::PetBattles {
+            pet_id: pet_id.to_string(),
+        };
+        match app.query_game(&msg)? {
+            QueryAnswer::Battles { battles } => Ok(battles),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn challenge(
+        app: &mut App,
+        user: &TestUser,
+        pet_id: &str,
+        other_pet_id: &str,
+        expiration: Option<Expiration>,
+    ) {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::BattlePet {
+            pet_id: pet_id.to_string(),
+            other_pet_id: other_pet_id.to_string(),
+            wager: 10,
+            expiration,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).unwrap();
+    }
+
+    fn set_policy(app: &mut App, policy: PetPrivacyPolicy) {
+        let msg = ExecuteMsg::SetPrivacyPolicy { policy };
+        app.execute_game(ADMIN, &msg).unwrap();
+    }
+
+    // Pet info
+
+    #[test]
+    fn test_pet_info_follows_the_default_policy() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+
+        let info = pet_info(&app, &alice_pet).unwrap();
+        assert_eq!(info.pet_id, alice_pet);
+        assert!(info.current.is_some());
+        assert!(info.progression.is_some());
+        assert!(info.energy.is_some());
+        assert!(info.record.is_some());
+        // rolled max stats and quest status stay with the owner
+        assert_eq!(info.max, None);
+        assert_eq!(info.on_quest, None);
+
+        let err = pet_info(&app, "999").unwrap_err();
+        assert!(err.contains("pet_not_found"), "{}", err);
+    }
+
+    #[test]
+    fn test_privacy_policy_hides_fields() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        set_policy(
+            &mut app,
+            PetPrivacyPolicy {
+                current_stats: false,
+                max_stats: true,
+                progression: false,
+                energy: false,
+                record: false,
+                quest_status: true,
+                open_challenges: false,
+                lineage: false,
+            },
+        );
+
+        let info = pet_info(&app, &alice_pet).unwrap();
+        assert_eq!(info.current, None);
+        assert_eq!(info.progression, None);
+        assert_eq!(info.energy, None);
+        assert_eq!(info.record, None);
+        assert!(info.max.is_some());
+        assert_eq!(info.on_quest, Some(false));
+
+        let msg = QueryMsg::PetEnergy {
+            pet_id: alice_pet.clone(),
+        };
+        let err = app.query_game(&msg).unwrap_err();
+        assert!(err.contains("pet_field_private"), "{}", err);
+        let msg = QueryMsg::PetProgression {
+            pet_id: alice_pet.clone(),
+        };
+        let err = app.query_game(&msg).unwrap_err();
+        assert!(err.contains("pet_field_private"), "{}", err);
+        let err = pet_battles(&app, &alice_pet).unwrap_err();
+        assert!(err.contains("pet_field_private"), "{}", err);
+        let msg = QueryMsg::PetLineage {
+            pet_id: alice_pet.clone(),
+        };
+        let err = app.query_game(&msg).unwrap_err();
+        assert!(err.contains("pet_field_private"), "{}", err);
+
+        // ranked pets keep their place on the leaderboard without their record
+        let bob_pet = app.mint_pet(&bob);
+        challenge(&mut app, &alice, &alice_pet, &bob_pet, None);
+        let permits = app.permits(&bob);
+        let msg = ExecuteMsg::AcceptBattle {
+            battle_id: 0,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&bob.address, &msg).unwrap();
+        let msg = QueryMsg::Leaderboard {
+            limit: None,
+            start_after: None,
+        };
+        match app.query_game(&msg).unwrap() {
+            QueryAnswer::Leaderboard { entries } => {
+                assert_eq!(entries.len(), 2);
+                assert!(entries.iter().all(|entry| entry.record.is_none()));
+            }
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pets_info_skips_unknown_pets() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+
+        let msg = QueryMsg::PetsInfo {
+            pet_ids: vec![alice_pet.clone(), "999".to_string(), bob_pet.clone()],
+        };
+        match app.query_game(&msg).unwrap() {
+            QueryAnswer::PetsInfo { pets } => {
+                let pet_ids: Vec<String> = pets.into_iter().map(|pet| pet.pet_id).collect();
+                assert_eq!(pet_ids, vec![alice_pet, bob_pet]);
+            }
+            other => panic!("unexpected answer {:?}", other),
+        }
+
+        let msg = QueryMsg::PetsInfo {
+            pet_ids: (0..=MAX_PETS_INFO).map(|id| id.to_string()).collect(),
+        };
+        let err = app.query_game(&msg).unwrap_err();
+        assert!(err.contains("too_many_pets"), "{}", err);
+    }
+
+    // Pet battles
+
+    #[test]
+    fn test_pet_battles_lists_challenges_against_the_pet() {
+        let (mut app, alice, bob) = App::with_players(1000);
+        let alice_pet = app.mint_pet(&alice);
+        let bob_pet = app.mint_pet(&bob);
+        let expiration = Expiration::AtTime(app.block().time.seconds() + 100);
+        challenge(&mut app, &alice, &alice_pet, &bob_pet, Some(expiration));
+
+        let battles = pet_battles(&app, &bob_pet).unwrap();
+        assert_eq!(battles.len(), 1);
+        assert_eq!(battles[0].pet_id, alice_pet);
+        // the challenger's own challenge is not listed against it
+        assert!(pet_battles(&app, &alice_pet).unwrap().is_empty());
+
+        app.advance_time(100);
+        assert!(pet_battles(&app, &bob_pet).unwrap().is_empty());
+
+        let err = pet_battles(&app, "999").unwrap_err();
+        assert!(err.contains("pet_not_found"), "{}", err);
+    }
+
+    // My pets
+
+    #[test]
+    fn test_my_pets_leaves_out_released_pets() {
+        let (mut app, alice, _) = App::with_players(1000);
+        let released_pet = app.mint_pet(&alice);
+        let kept_pet = app.mint_pet(&alice);
+
+        let permits = app.permits(&alice);
+        let msg = ExecuteMsg::ReleasePet {
+            pet_id: released_pet,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+
+        let pets = app.my_pets(&alice);
+        let pet_ids: Vec<String> = pets.into_iter().map(|pet| pet.pet_id).collect();
+        assert_eq!(pet_ids, vec![kept_pet]);
+    }
+}
@@ -0,0 +1,487 @@
+#[cfg(test)]
+mod tests {
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg, QueryWithPermits};
+    use crate::multitest::{App, TestUser, ADMIN, PET_ADDR};
+    use crate::state::{
+        default_quest_catalogue, PetStats, QuestCheck, QuestDefinition, QuestHistory, QuestOdds,
+        QuestOutcome, QuestRange, QuestSummary, Stat, StatWeight, DEFAULT_QUEST_COOLDOWN_SECONDS,
+        DEFAULT_QUEST_EXPLORE_SECONDS, MAX_QUEST_BASE_LOOT, MAX_QUEST_DIFFICULTY, MAX_QUEST_GROWTH,
+    };
+
+    // Helper functions
+
+    fn expedition() -> QuestDefinition {
+        QuestDefinition {
+            id: "midnight_expedition".to_string(),
+            name: "Midnight Expedition".to_string(),
+            stat_weights: vec![
+                StatWeight {
+                    stat: Stat::Stamina,
+                    weight: 2,
+                },
+                StatWeight {
+                    stat: Stat::Intelligence,
+                    weight: 1,
+                },
+            ],
+            explore_seconds: 100,
+            cooldown_seconds: 200,
+            base_loot: QuestRange::new(4, 6),
+            difficulty: QuestRange::new(2, 4),
+            growth_on_pass: QuestRange::new(1, 1),
+            growth_on_exceptional_pass: QuestRange::new(2, 3),
+            enabled: true,
+        }
+    }
+
+    fn catalogue(app: &App) -> Vec<QuestDefinition> {
+        match app.query_game(&QueryMsg::QuestCatalogue {}).unwrap() {
+            QueryAnswer::QuestCatalogue { quests } => quests,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn my_quests(app: &App, user: &TestUser) -> Vec<QuestSummary> {
+        let query = QueryWithPermits::MyQuests {};
+        match app.query_with_permits(user, query).unwrap() {
+            QueryAnswer::Quests { quests } => quests,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn quest_odds(app: &App, user: &TestUser, pet_id: &str, quest_type: &str) -> QuestOdds {
+        let query = QueryWithPermits::QuestOdds {
+            pet_id: pet_id.to_string(),
+            quest_type: quest_type.to_string(),
+        };
+        match app.query_with_permits(user, query).unwrap() {
+            QueryAnswer::QuestOdds { odds, .. } => odds,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn my_history(app: &App, user: &TestUser) -> Vec<QuestHistory> {
+        let query = QueryWithPermits::MyQuestHistory {
+            page: None,
+            page_size: None,
+            quest_type: None,
+            outcome: None,
+        };
+        history(app, user, query).unwrap()
+    }
+
+    fn history(
+        app: &App,
+        user: &TestUser,
+        query: QueryWithPermits,
+    ) -> Result<Vec<QuestHistory>, String> {
+        match app.query_with_permits(user, query)? {
+            QueryAnswer::History { quest_history } => Ok(quest_history),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn quest_types(history: &[QuestHistory]) -> Vec<&str> {
+        history
+            .iter()
+            .map(|entry| entry.quest_type.as_str())
+            .collect()
+    }
+
+    // Catalogue
+
+    #[test]
+    fn test_catalogue_starts_with_the_trials() {
+        let app = App::new();
+        let quests = catalogue(&app);
+        assert_eq!(quests, default_quest_catalogue());
+
+        let ids: Vec<&str> = quests.iter().map(|quest| quest.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "trial_of_resilience",
+                "trial_of_endurance",
+                "trial_of_titans",
+                "trial_of_wisdom"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_quest_is_validated() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+
+        let msg = ExecuteMsg::AddQuest {
+            quest: expedition(),
+        };
+        let err = app.execute_game(&alice.address, &msg).unwrap_err();
+        assert!(err.contains("unauthorized"), "{}", err);
+
+        let mut invalid = expedition();
+        invalid.id = "Midnight Expedition".to_string();
+        let err = app
+            .execute_game(ADMIN, &ExecuteMsg::AddQuest { quest: invalid })
+            .unwrap_err();
+        assert!(err.contains("invalid_quest_definition"), "{}", err);
+
+        let mut invalid = expedition();
+        invalid.stat_weights = vec![StatWeight {
+            stat: Stat::Luck,
+            weight: 0,
+        }];
+        let err = app
+            .execute_game(ADMIN, &ExecuteMsg::AddQuest { quest: invalid })
+            .unwrap_err();
+        assert!(err.contains("invalid_quest_definition"), "{}", err);
+
+        let mut invalid = expedition();
+        invalid.cooldown_seconds = 10;
+        let err = app
+            .execute_game(ADMIN, &ExecuteMsg::AddQuest { quest: invalid })
+            .unwrap_err();
+        assert!(err.contains("invalid_quest_definition"), "{}", err);
+
+        let mut invalid = expedition();
+        invalid.difficulty = QuestRange::new(5, 1);
+        let err = app
+            .execute_game(ADMIN, &ExecuteMsg::AddQuest { quest: invalid })
+            .unwrap_err();
+        assert!(err.contains("invalid_quest_definition"), "{}", err);
+
+        // ranges are bounded so loot and checks cannot overflow
+        let mut too_rich = expedition();
+        too_rich.base_loot = QuestRange::new(1, MAX_QUEST_BASE_LOOT + 1);
+        let mut too_hard = expedition();
+        too_hard.difficulty = QuestRange::new(1, MAX_QUEST_DIFFICULTY + 1);
+        let mut too_steep = expedition();
+        too_steep.growth_on_exceptional_pass = QuestRange::new(1, MAX_QUEST_GROWTH + 1);
+        for invalid in [too_rich, too_hard, too_steep] {
+            let err = app
+                .execute_game(ADMIN, &ExecuteMsg::AddQuest { quest: invalid })
+                .unwrap_err();
+            assert!(err.contains("invalid_quest_definition"), "{}", err);
+        }
+
+        app.execute_game(ADMIN, &msg).unwrap();
+        let err = app.execute_game(ADMIN, &msg).unwrap_err();
+        assert!(err.contains("quest_already_exists"), "{}", err);
+
+        let mut unknown = expedition();
+        unknown.id = "unknown_quest".to_string();
+        let err = app
+            .execute_game(ADMIN, &ExecuteMsg::UpdateQuest { quest: unknown })
+            .unwrap_err();
+        assert!(err.contains("quest_not_found"), "{}", err);
+    }
+
+    #[test]
+    fn test_new_quests_reach_existing_players_lazily() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let pet_id = app.mint_pet(&alice);
+        assert_eq!(my_quests(&app, &alice).len(), 4);
+
+        app.execute_game(
+            ADMIN,
+            &ExecuteMsg::AddQuest {
+                quest: expedition(),
+            },
+        )
+        .unwrap();
+
+        // the new quest is shown at the bottom of its ranges until it is first rolled
+        let quests = my_quests(&app, &alice);
+        assert_eq!(quests.len(), 5);
+        let expedition_summary = quests
+            .iter()
+            .find(|quest| quest.quest_type == "midnight_expedition")
+            .unwrap();
+        assert_eq!(expedition_summary.status, "available");
+        assert_eq!(expedition_summary.loot.pass, 4 + 2);
+
+        app.send_on_quest(&alice, &pet_id, "midnight_expedition")
+            .unwrap();
+
+        // the explore time comes from the definition
+        app.advance_time(50);
+        let err = app.claim_quest(&alice, "midnight_expedition").unwrap_err();
+        assert!(err.contains("quest_not_claimable"), "{}", err);
+        app.advance_time(50);
+        app.claim_quest(&alice, "midnight_expedition").unwrap();
+
+        // a player created after the quest was added rolls it right away
+        let bob = app.user("bob");
+        app.mint_pet(&bob);
+        let quests = my_quests(&app, &bob);
+        assert_eq!(quests.len(), 5);
+        let loot = &quests
+            .iter()
+            .find(|quest| quest.quest_type == "midnight_expedition")
+            .unwrap()
+            .loot;
+        assert!((4 + 2..=6 + 4).contains(&loot.pass));
+    }
+
+    #[test]
+    fn test_disabled_quests_cannot_be_started() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let pet_id = app.mint_pet(&alice);
+
+        let mut trial = default_quest_catalogue().remove(0);
+        trial.enabled = false;
+        let trial_id = trial.id.clone();
+        app.execute_game(ADMIN, &ExecuteMsg::UpdateQuest { quest: trial })
+            .unwrap();
+
+        let err = app.send_on_quest(&alice, &pet_id, &trial_id).unwrap_err();
+        assert!(err.contains("quest_not_available"), "{}", err);
+        let err = app
+            .send_on_quest(&alice, &pet_id, "unknown_quest")
+            .unwrap_err();
+        assert!(err.contains("quest_not_found"), "{}", err);
+    }
+
+    #[test]
+    fn test_stat_weights_average_the_pet_stats() {
+        let stats = PetStats::new(1, 2, 9, 6, 4);
+        // (9 * 2 + 6) / 3
+        assert_eq!(expedition().stat_value(&stats), 8);
+
+        let titans = default_quest_catalogue().remove(2);
+        assert_eq!(titans.stat_value(&stats), 2);
+    }
+
+    // Resolution
+
+    #[test]
+    fn test_quest_check_resolves_d20_rolls() {
+        let check = QuestCheck {
+            modifier: 5,
+            target: 12,
+        };
+        assert_eq!(check.resolve(6), QuestOutcome::Fail);
+        assert_eq!(check.resolve(7), QuestOutcome::Pass);
+        assert_eq!(check.resolve(16), QuestOutcome::Pass);
+        assert_eq!(check.resolve(17), QuestOutcome::ExceptionalPass);
+        assert_eq!(
+            check.odds(),
+            QuestOdds {
+                fail: 3000,
+                pass: 5000,
+                exceptional_pass: 2000
+            }
+        );
+
+        // natural rolls win or lose regardless of the modifier
+        let easy = QuestCheck {
+            modifier: 40,
+            target: 10,
+        };
+        assert_eq!(easy.resolve(1), QuestOutcome::Fail);
+        let hard = QuestCheck {
+            modifier: 0,
+            target: 60,
+        };
+        assert_eq!(hard.resolve(20), QuestOutcome::ExceptionalPass);
+        assert_eq!(
+            hard.odds(),
+            QuestOdds {
+                fail: 9500,
+                pass: 0,
+                exceptional_pass: 500
+            }
+        );
+
+        // extreme checks saturate instead of overflowing
+        let extreme = QuestCheck {
+            modifier: u16::MAX,
+            target: u16::MAX,
+        };
+        assert_eq!(extreme.resolve(2), QuestOutcome::ExceptionalPass);
+    }
+
+    #[test]
+    fn test_outcome_is_fixed_when_the_quest_starts() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let pet_id = app.mint_pet(&alice);
+
+        let odds = quest_odds(&app, &alice, &pet_id, "trial_of_titans");
+        assert_eq!(odds.fail + odds.pass + odds.exceptional_pass, 10_000);
+        assert!(odds.fail >= 500 && odds.exceptional_pass >= 500);
+
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+        let preview = |app: &App| {
+            my_quests(app, &alice)
+                .into_iter()
+                .find(|quest| quest.quest_type == "trial_of_titans")
+                .unwrap()
+        };
+        let in_progress = preview(&app);
+        assert_eq!(in_progress.status, "in_progress");
+        assert_eq!(in_progress.outcome, None);
+
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        let claimable = preview(&app);
+        assert_eq!(claimable.status, "claimable");
+        let outcome = claimable.outcome.unwrap();
+
+        // claiming in another block does not change the rolled outcome
+        app.advance_blocks(3);
+        app.claim_quest(&alice, "trial_of_titans").unwrap();
+        assert_eq!(my_history(&app, &alice)[0].outcome, outcome);
+    }
+
+    #[test]
+    fn test_quests_wait_out_their_cooldown() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let pet_id = app.mint_pet(&alice);
+
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        app.claim_quest(&alice, "trial_of_titans").unwrap();
+
+        // the cooldown counts from the start of the quest, other quests are not held back
+        let err = app
+            .send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap_err();
+        assert!(err.contains("quest_not_available"), "{}", err);
+        let status = |app: &App, quest_type: &str| {
+            my_quests(app, &alice)
+                .into_iter()
+                .find(|quest| quest.quest_type == quest_type)
+                .unwrap()
+                .status
+        };
+        assert_eq!(status(&app, "trial_of_titans"), "on_cooldown");
+        assert_eq!(status(&app, "trial_of_wisdom"), "available");
+
+        app.advance_time(DEFAULT_QUEST_COOLDOWN_SECONDS - DEFAULT_QUEST_EXPLORE_SECONDS - 1);
+        let err = app
+            .send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap_err();
+        assert!(err.contains("quest_not_available"), "{}", err);
+        app.advance_time(1);
+        assert_eq!(status(&app, "trial_of_titans"), "available");
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+    }
+
+    // History
+
+    #[test]
+    fn test_quest_history_is_paged_and_filtered() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let bob = app.user("bob");
+        let pet_id = app.mint_pet(&alice);
+        let other_pet_id = app.mint_pet(&alice);
+
+        // new players have an empty history
+        assert!(my_history(&app, &bob).is_empty());
+
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+        app.send_on_quest(&alice, &other_pet_id, "trial_of_wisdom")
+            .unwrap();
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        app.claim_quest(&alice, "trial_of_titans").unwrap();
+        app.claim_quest(&alice, "trial_of_wisdom").unwrap();
+        app.send_on_quest(&alice, &pet_id, "trial_of_endurance")
+            .unwrap();
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        app.claim_quest(&alice, "trial_of_endurance").unwrap();
+
+        // latest quests come first
+        let all = my_history(&app, &alice);
+        assert_eq!(
+            quest_types(&all),
+            vec!["trial_of_endurance", "trial_of_wisdom", "trial_of_titans"]
+        );
+
+        let query = QueryWithPermits::MyQuestHistory {
+            page: Some(1),
+            page_size: Some(2),
+            quest_type: None,
+            outcome: None,
+        };
+        let page = history(&app, &alice, query).unwrap();
+        assert_eq!(quest_types(&page), vec!["trial_of_titans"]);
+
+        let query = QueryWithPermits::MyQuestHistory {
+            page: None,
+            page_size: None,
+            quest_type: Some("trial_of_wisdom".to_string()),
+            outcome: None,
+        };
+        let wisdom = history(&app, &alice, query).unwrap();
+        assert_eq!(wisdom.len(), 1);
+        assert_eq!(wisdom[0].pet_id, other_pet_id);
+
+        let outcome = all[1].outcome.clone();
+        let query = QueryWithPermits::MyQuestHistory {
+            page: None,
+            page_size: None,
+            quest_type: None,
+            outcome: Some(outcome.clone()),
+        };
+        let same_outcome = history(&app, &alice, query).unwrap();
+        let expected: Vec<QuestHistory> = all
+            .iter()
+            .filter(|entry| entry.outcome == outcome)
+            .cloned()
+            .collect();
+        assert_eq!(same_outcome, expected);
+
+        // filters apply before paging
+        let query = QueryWithPermits::PetQuestHistory {
+            pet_id: pet_id.clone(),
+            page: Some(1),
+            page_size: Some(1),
+            quest_type: None,
+            outcome: None,
+        };
+        let page = history(&app, &alice, query).unwrap();
+        assert_eq!(quest_types(&page), vec!["trial_of_titans"]);
+    }
+
+    #[test]
+    fn test_pet_quest_history_is_for_the_owner() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let bob = app.user("bob");
+        let pet_id = app.mint_pet(&alice);
+        app.send_on_quest(&alice, &pet_id, "trial_of_titans")
+            .unwrap();
+        app.advance_time(DEFAULT_QUEST_EXPLORE_SECONDS);
+        app.claim_quest(&alice, "trial_of_titans").unwrap();
+
+        let query = |pet_id: &str| QueryWithPermits::PetQuestHistory {
+            pet_id: pet_id.to_string(),
+            page: None,
+            page_size: None,
+            quest_type: None,
+            outcome: None,
+        };
+        let err = history(&app, &bob, query(&pet_id)).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+
+        // the history stays with the pet when it changes hands
+        let transfer = format!(
+            "{{\"transfer_nft\":{{\"recipient\":\"{}\",\"token_id\":\"{}\"}}}}",
+            bob.address, pet_id
+        );
+        app.execute_raw(&alice.address, PET_ADDR, transfer.as_bytes())
+            .unwrap();
+        let pet_history = history(&app, &bob, query(&pet_id)).unwrap();
+        assert_eq!(quest_types(&pet_history), vec!["trial_of_titans"]);
+        assert!(my_history(&app, &bob).is_empty());
+        assert_eq!(my_history(&app, &alice).len(), 1);
+    }
+}
@@ -0,0 +1,276 @@
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use snip721_reference_impl::msg::{QueryAnswer as PetQueryAnswer, QueryMsg as PetQueryMsg};
+
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg};
+    use crate::multitest::{App, ADMIN, MAX_STATS, PET_ADDR};
+    use crate::species::{
+        PetTrait, Rarity, RarityTier, Species, SpeciesConfig, TraitPool, MAX_ROLLED_STAT,
+        MIN_ROLLED_STAT,
+    };
+    use crate::state::{BreedingConfig, PetStats, RaritySupply, Stat, MIN_MAX_STATS};
+
+    // Helper functions
+
+    /// the public attributes of the pet721 token as trait type and value pairs, the species
+    /// attributes come first
+    fn token_attributes(app: &App, pet_id: &str) -> Vec<PetTrait> {
+        let msg = PetQueryMsg::NftInfo {
+            token_id: pet_id.to_string(),
+        };
+        match app.query(PET_ADDR, &msg).unwrap() {
+            PetQueryAnswer::NftInfo { extension, .. } => extension
+                .and_then(|extension| extension.attributes)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|attribute| PetTrait {
+                    trait_type: attribute.trait_type.unwrap(),
+                    value: attribute.value,
+                })
+                .collect(),
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn rarity_supply(app: &App) -> Vec<RaritySupply> {
+        match app.query_game(&QueryMsg::RaritySupply {}).unwrap() {
+            QueryAnswer::RaritySupply { supply } => supply,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn set_species_config(app: &mut App, config: SpeciesConfig) -> Result<(), String> {
+        app.execute_game(ADMIN, &ExecuteMsg::SetSpeciesConfig { config })
+            .map(|_| ())
+    }
+
+    fn tier(rarity: Rarity, weight: u16, supply_cap: Option<u64>) -> RarityTier {
+        RarityTier {
+            rarity,
+            weight,
+            stat_bonus: 0,
+            supply_cap,
+        }
+    }
+
+    // Species
+
+    #[test]
+    fn test_minted_pets_carry_species_traits_on_their_token() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let config = SpeciesConfig::default();
+        for _ in 0..10 {
+            app.mint_pet(&alice);
+        }
+
+        for pet in app.my_pets(&alice) {
+            let traits = pet.traits.clone().unwrap();
+            let species = config.species(&traits.species).unwrap();
+            assert_eq!(
+                traits
+                    .cosmetics
+                    .iter()
+                    .map(|cosmetic| cosmetic.trait_type.clone())
+                    .collect::<Vec<_>>(),
+                vec!["color", "pattern"]
+            );
+            for (cosmetic, pool) in traits.cosmetics.iter().zip(&species.traits) {
+                assert!(pool.values.contains(&cosmetic.value));
+            }
+            for stat in Stat::all() {
+                let bias = species.stat_bias.get_stat(stat.clone());
+                let base = pet.current.get_stat(stat.clone());
+                assert!((MIN_ROLLED_STAT + bias..=MAX_ROLLED_STAT + bias).contains(&base));
+                let max = pet.max.get_stat(stat.clone());
+                assert!((MIN_MAX_STATS..=MAX_STATS).contains(&max));
+            }
+
+            // marketplaces see the same species, rarity and cosmetics as the game
+            let attributes = token_attributes(&app, &pet.pet_id);
+            assert_eq!(attributes[..traits.attributes().len()], traits.attributes());
+            assert_eq!(attributes[0].trait_type, "species");
+            assert_eq!(attributes[1].value, traits.rarity.name());
+        }
+
+        let minted: u64 = rarity_supply(&app).iter().map(|supply| supply.minted).sum();
+        assert_eq!(minted, 10);
+    }
+
+    #[test]
+    fn test_rarity_supply_caps_are_enforced() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let config = SpeciesConfig {
+            rarities: vec![
+                tier(Rarity::Common, 0, None),
+                tier(Rarity::Legendary, 1, Some(2)),
+            ],
+            ..SpeciesConfig::default()
+        };
+        set_species_config(&mut app, config.clone()).unwrap();
+
+        let first = app.mint_pet(&alice);
+        let second = app.mint_pet(&alice);
+        let err = app.try_mint_pet(&alice).unwrap_err();
+        assert!(err.contains("supply_exhausted"), "{}", err);
+        let legendary = rarity_supply(&app)
+            .into_iter()
+            .find(|supply| supply.rarity == Rarity::Legendary)
+            .unwrap();
+        assert_eq!(legendary.minted, 2);
+        assert_eq!(legendary.supply_cap, Some(2));
+
+        // bred pets count against the caps as well
+        app.execute_game(
+            ADMIN,
+            &ExecuteMsg::SetBreedingConfig {
+                config: BreedingConfig {
+                    fee: 0,
+                    ..BreedingConfig::default()
+                },
+            },
+        )
+        .unwrap();
+        let permits = app.permits(&alice);
+        let msg = ExecuteMsg::BreedPets {
+            parent_a: first,
+            parent_b: second,
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        let err = app.execute_game(&alice.address, &msg).unwrap_err();
+        assert!(err.contains("supply_exhausted"), "{}", err);
+
+        // once a capped rarity is gone the others are rolled instead
+        let config = SpeciesConfig {
+            rarities: vec![
+                tier(Rarity::Common, 1, None),
+                tier(Rarity::Legendary, 1000, Some(2)),
+            ],
+            ..config
+        };
+        set_species_config(&mut app, config).unwrap();
+        let response = app.execute_game(&alice.address, &msg).unwrap();
+        let child_id = response.attribute("pet_id").unwrap();
+        let child = app
+            .my_pets(&alice)
+            .into_iter()
+            .find(|pet| pet.pet_id == child_id)
+            .unwrap();
+        let traits = child.traits.unwrap();
+        assert_eq!(traits.rarity, Rarity::Common);
+        assert!(["drake", "tortoise", "owl"].contains(&traits.species.as_str()));
+        let attributes = token_attributes(&app, &child_id);
+        assert_eq!(attributes[..traits.attributes().len()], traits.attributes());
+    }
+
+    #[test]
+    fn test_species_config_is_validated() {
+        let mut app = App::new();
+        let alice = app.user("alice");
+        let msg = ExecuteMsg::SetSpeciesConfig {
+            config: SpeciesConfig::default(),
+        };
+        let err = app.execute_game(&alice.address, &msg).unwrap_err();
+        assert!(err.contains("unauthorized"), "{}", err);
+
+        let default = SpeciesConfig::default();
+        let with_species = |change: &dyn Fn(&mut Species)| {
+            let mut config = default.clone();
+            change(&mut config.species[0]);
+            config
+        };
+        let invalid = [
+            SpeciesConfig {
+                species: vec![],
+                ..default.clone()
+            },
+            SpeciesConfig {
+                species: vec![default.species[0].clone(), default.species[0].clone()],
+                ..default.clone()
+            },
+            SpeciesConfig {
+                rarities: vec![tier(Rarity::Common, 1, None), tier(Rarity::Common, 1, None)],
+                ..default.clone()
+            },
+            SpeciesConfig {
+                rarities: vec![tier(Rarity::Common, 0, None)],
+                ..default.clone()
+            },
+            with_species(&|species| species.max.luck = MIN_MAX_STATS - 1),
+            with_species(&|species| species.stat_bias.health = MIN_MAX_STATS - MAX_ROLLED_STAT),
+            with_species(&|species| {
+                species.traits.push(TraitPool {
+                    trait_type: "eyes".to_string(),
+                    values: vec![],
+                })
+            }),
+        ];
+        for config in invalid {
+            let err = set_species_config(&mut app, config.clone()).unwrap_err();
+            assert!(
+                err.contains("invalid_species_config"),
+                "{:?} {}",
+                config,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_rolls_respect_weights_and_species_maxes() {
+        let species = Species {
+            id: "slug".to_string(),
+            name: "Slug".to_string(),
+            weight: 1,
+            stat_bias: PetStats::new(3, 0, 0, 0, 0),
+            max: PetStats::new(14, 30, 30, 30, 12),
+            traits: vec![],
+        };
+        let config = SpeciesConfig {
+            species: vec![
+                Species {
+                    weight: 0,
+                    ..SpeciesConfig::default().species[0].clone()
+                },
+                species.clone(),
+            ],
+            rarities: vec![
+                tier(Rarity::Common, 5, None),
+                tier(Rarity::Rare, 0, None),
+                RarityTier {
+                    stat_bonus: 3,
+                    ..tier(Rarity::Epic, 5, Some(1))
+                },
+            ],
+        };
+        config.validate().unwrap();
+
+        for seed in 0..200 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            assert_eq!(config.roll_species(&mut rng).id, "slug");
+            let rolled = config.roll_rarity(|_| 0, &mut rng).unwrap();
+            assert_ne!(rolled.rarity, Rarity::Rare);
+            let rolled = config.roll_rarity(|_| 1, &mut rng).unwrap();
+            assert_eq!(rolled.rarity, Rarity::Common);
+
+            let (base, max) = species.roll_stats(&config.rarities[2], MAX_STATS, &mut rng);
+            assert!((MIN_MAX_STATS..=14).contains(&max.health), "seed {}", seed);
+            assert!((15..=MAX_STATS).contains(&max.strength), "seed {}", seed);
+            assert_eq!(max.luck, 12, "seed {}", seed);
+            assert!((8..=11).contains(&base.health), "seed {}", seed);
+            for stat in Stat::all() {
+                assert!(
+                    base.get_stat(stat.clone()) < max.get_stat(stat),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+    }
+}
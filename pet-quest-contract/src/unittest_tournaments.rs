@@ -0,0 +1,372 @@
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    use crate::battle::{BattleOutcome, TieBreaker};
+    use crate::msg::{ExecuteMsg, QueryAnswer, QueryMsg};
+    use crate::multitest::{App, TestUser};
+    use crate::state::{
+        EntrantProgress, Expiration, PetStats, TournamentInfo, TournamentStatus,
+        MAX_TOURNAMENT_REGISTRATION_SECONDS, MIN_SECONDS_PER_BLOCK,
+    };
+    use crate::tournament::{place_entrants, resolve_bracket, Placement, TournamentMatch};
+
+    // Helper functions
+
+    fn setup(names: &[&str]) -> (App, Vec<TestUser>) {
+        let mut app = App::new();
+        let users: Vec<TestUser> = names
+            .iter()
+            .map(|name| app.funded_user(name, 1000))
+            .collect();
+        (app, users)
+    }
+
+    fn create(
+        app: &mut App,
+        user: &TestUser,
+        entry_fee: u64,
+        max_entrants: u32,
+        registration: Option<Expiration>,
+        prize_split: Option<Vec<u16>>,
+    ) -> Result<u64, String> {
+        let msg = ExecuteMsg::CreateTournament {
+            name: "spring cup".to_string(),
+            entry_fee,
+            max_entrants,
+            registration,
+            prize_split,
+        };
+        let response = app.execute_game(&user.address, &msg)?;
+        Ok(response
+            .attribute("tournament_id")
+            .unwrap()
+            .parse()
+            .unwrap())
+    }
+
+    fn join(
+        app: &mut App,
+        user: &TestUser,
+        tournament_id: u64,
+        pet_id: &str,
+    ) -> Result<(), String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::JoinTournament {
+            tournament_id,
+            pet_id: pet_id.to_string(),
+            entropy: None,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn leave(
+        app: &mut App,
+        user: &TestUser,
+        tournament_id: u64,
+        pet_id: &str,
+    ) -> Result<(), String> {
+        let msg = ExecuteMsg::LeaveTournament {
+            tournament_id,
+            pet_id: pet_id.to_string(),
+            pet_permit: app.permits(user).pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn play(app: &mut App, user: &TestUser, tournament_id: u64) -> Result<(), String> {
+        let msg = ExecuteMsg::PlayTournament {
+            tournament_id,
+            entropy: None,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn tournament(app: &App, tournament_id: u64) -> TournamentInfo {
+        match app
+            .query_game(&QueryMsg::Tournament { tournament_id })
+            .unwrap()
+        {
+            QueryAnswer::Tournament { tournament } => tournament,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn progress(app: &App, tournament_id: u64, pet_id: &str) -> EntrantProgress {
+        let msg = QueryMsg::TournamentEntrant {
+            tournament_id,
+            pet_id: pet_id.to_string(),
+        };
+        match app.query_game(&msg).unwrap() {
+            QueryAnswer::TournamentEntrant { progress } => progress,
+            other => panic!("unexpected answer {:?}", other),
+        }
+    }
+
+    fn fought(round: u16, pet_id: &str, other_pet_id: &str, winner: &str) -> TournamentMatch {
+        TournamentMatch {
+            round,
+            pet_id: pet_id.to_string(),
+            other_pet_id: Some(other_pet_id.to_string()),
+            outcome: Some(BattleOutcome::from_challenger_wins(winner == pet_id)),
+            winner: winner.to_string(),
+        }
+    }
+
+    fn prizes(placements: &[Placement]) -> Vec<(&str, u32, u64)> {
+        placements
+            .iter()
+            .map(|placement| (placement.pet_id.as_str(), placement.place, placement.prize))
+            .collect()
+    }
+
+    // Brackets
+
+    #[test]
+    fn test_bracket_gives_byes_and_crowns_one_champion() {
+        let strong = PetStats::new(50, 50, 10, 0, 0);
+        let weak = PetStats::new(5, 5, 0, 0, 0);
+
+        for seed in 0..20 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut entrants = vec![("PET_0".to_string(), strong.clone())];
+            for i in 1..5 {
+                entrants.push((format!("PET_{}", i), weak.clone()));
+            }
+            let matches = resolve_bracket(entrants, &TieBreaker::Draw, &mut rng);
+
+            // five pets fill a bracket of eight, three of them skip the first round
+            let byes: Vec<&TournamentMatch> = matches
+                .iter()
+                .filter(|m| m.other_pet_id.is_none())
+                .collect();
+            assert_eq!(byes.len(), 3);
+            assert!(byes.iter().all(|m| m.round == 1));
+            assert_eq!(
+                matches.iter().filter(|m| m.other_pet_id.is_some()).count(),
+                4
+            );
+            assert_eq!(matches.last().unwrap().round, 3);
+            assert_eq!(matches.last().unwrap().winner, "PET_0");
+        }
+    }
+
+    #[test]
+    fn test_prizes_are_split_across_places() {
+        let matches = vec![
+            fought(1, "A", "B", "A"),
+            fought(1, "C", "D", "C"),
+            fought(2, "A", "C", "A"),
+        ];
+        let placements = place_entrants(&matches, 4, 100, &[60, 30, 10]);
+        assert_eq!(
+            prizes(&placements),
+            vec![("A", 1, 60), ("C", 2, 30), ("B", 3, 5), ("D", 3, 5)]
+        );
+        assert_eq!(placements[1].eliminated_in, Some(2));
+
+        // rounding leftovers go to the champion
+        let matches = vec![
+            TournamentMatch {
+                round: 1,
+                pet_id: "A".to_string(),
+                other_pet_id: None,
+                outcome: None,
+                winner: "A".to_string(),
+            },
+            fought(1, "B", "C", "B"),
+            fought(2, "A", "B", "B"),
+        ];
+        let placements = place_entrants(&matches, 3, 75, &[60, 30, 10]);
+        assert_eq!(
+            prizes(&placements),
+            vec![("B", 1, 46), ("A", 2, 22), ("C", 3, 7)]
+        );
+
+        // and so do the shares of places nobody reached
+        let placements = place_entrants(&[fought(1, "A", "B", "B")], 2, 50, &[60, 30, 10]);
+        assert_eq!(prizes(&placements), vec![("B", 1, 35), ("A", 2, 15)]);
+    }
+
+    // Tournaments
+
+    #[test]
+    fn test_full_tournament_is_played_and_pays_prizes() {
+        let (mut app, users) = setup(&["alice", "bob", "carol", "dave"]);
+        let pets: Vec<String> = users.iter().map(|user| app.mint_pet(user)).collect();
+        let tournament_id = create(&mut app, &users[0], 40, 4, None, None).unwrap();
+
+        for (user, pet_id) in users.iter().zip(&pets) {
+            join(&mut app, user, tournament_id, pet_id).unwrap();
+        }
+        let info = tournament(&app, tournament_id);
+        assert_eq!(info.status, TournamentStatus::Finished);
+        assert_eq!(info.prize_pool, 160);
+        assert_eq!(info.entrants, pets);
+        assert_eq!(info.matches.len(), 3);
+        assert_eq!(
+            info.placements
+                .iter()
+                .map(|placement| placement.prize)
+                .collect::<Vec<u64>>(),
+            vec![96, 48, 8, 8]
+        );
+
+        // every player paid the fee and is paid the prize of their pet
+        for (user, pet_id) in users.iter().zip(&pets) {
+            let placement = progress(&app, tournament_id, pet_id).placement.unwrap();
+            assert_eq!(
+                app.loot_balance(user),
+                1000 - 40 + u128::from(placement.prize)
+            );
+        }
+        let champion = progress(&app, tournament_id, &info.placements[0].pet_id);
+        assert_eq!(champion.wins, 2);
+        assert_eq!(champion.matches.len(), 2);
+
+        let err = join(&mut app, &users[0], tournament_id, &pets[0]).unwrap_err();
+        assert!(err.contains("tournament_closed"), "{}", err);
+    }
+
+    #[test]
+    fn test_registration_closes_at_the_deadline() {
+        let (mut app, users) = setup(&["alice", "bob"]);
+        let alice_pet = app.mint_pet(&users[0]);
+        let bob_pet = app.mint_pet(&users[1]);
+        let deadline = Expiration::AtTime(app.block().time.seconds() + 100);
+        let tournament_id = create(&mut app, &users[1], 25, 4, Some(deadline), None).unwrap();
+
+        join(&mut app, &users[0], tournament_id, &alice_pet).unwrap();
+        assert_eq!(app.loot_balance(&users[0]), 975);
+        // registered pets are locked until the tournament is played
+        let err = join(&mut app, &users[0], tournament_id, &alice_pet).unwrap_err();
+        assert!(err.contains("pet_in_battle"), "{}", err);
+        let err = play(&mut app, &users[1], tournament_id).unwrap_err();
+        assert!(err.contains("tournament_registration_open"), "{}", err);
+
+        app.advance_time(100);
+        let err = join(&mut app, &users[1], tournament_id, &bob_pet).unwrap_err();
+        assert!(err.contains("tournament_closed"), "{}", err);
+
+        // a single entrant cannot be played, the tournament is cancelled and refunded
+        play(&mut app, &users[1], tournament_id).unwrap();
+        assert_eq!(
+            tournament(&app, tournament_id).status,
+            TournamentStatus::Cancelled
+        );
+        assert_eq!(app.loot_balance(&users[0]), 1000);
+        assert_eq!(progress(&app, tournament_id, &alice_pet).placement, None);
+
+        let err = play(&mut app, &users[1], tournament_id).unwrap_err();
+        assert!(err.contains("tournament_closed"), "{}", err);
+    }
+
+    #[test]
+    fn test_registration_must_end_within_a_week() {
+        let (mut app, users) = setup(&["alice"]);
+        let alice = &users[0];
+
+        // a tournament that never fills would never be played
+        let err = create(&mut app, alice, 10, 8, Some(Expiration::Never), None).unwrap_err();
+        assert!(err.contains("invalid_expiration"), "{}", err);
+
+        let now = app.block().time.seconds();
+        let too_late = Expiration::AtTime(now + MAX_TOURNAMENT_REGISTRATION_SECONDS + 1);
+        let err = create(&mut app, alice, 10, 8, Some(too_late), None).unwrap_err();
+        assert!(err.contains("invalid_expiration"), "{}", err);
+        let blocks = MAX_TOURNAMENT_REGISTRATION_SECONDS / MIN_SECONDS_PER_BLOCK;
+        let too_late = Expiration::AtHeight(app.block().height + blocks + 1);
+        let err = create(&mut app, alice, 10, 8, Some(too_late), None).unwrap_err();
+        assert!(err.contains("invalid_expiration"), "{}", err);
+
+        let deadline = Expiration::AtTime(now + MAX_TOURNAMENT_REGISTRATION_SECONDS);
+        let tournament_id = create(&mut app, alice, 10, 8, Some(deadline), None).unwrap();
+        assert_eq!(tournament(&app, tournament_id).registration, deadline);
+    }
+
+    #[test]
+    fn test_invalid_tournaments_are_rejected() {
+        let (mut app, users) = setup(&["alice"]);
+        let alice = &users[0];
+
+        for (max_entrants, prize_split) in [
+            (1, None),
+            (33, None),
+            (8, Some(vec![])),
+            (8, Some(vec![50, 40])),
+            (8, Some(vec![60, 10, 10, 10, 10])),
+        ] {
+            let err = create(&mut app, alice, 10, max_entrants, None, prize_split).unwrap_err();
+            assert!(err.contains("invalid_tournament"), "{}", err);
+        }
+
+        let past = Expiration::AtHeight(app.block().height);
+        let err = create(&mut app, alice, 10, 8, Some(past), None).unwrap_err();
+        assert!(err.contains("invalid_expiration"), "{}", err);
+
+        let err = play(&mut app, alice, 0).unwrap_err();
+        assert!(err.contains("tournament_not_found"), "{}", err);
+        assert_eq!(create(&mut app, alice, 0, 8, None, Some(vec![100])), Ok(0));
+    }
+
+    #[test]
+    fn test_releasing_a_registered_pet_refunds_the_fee() {
+        let (mut app, users) = setup(&["alice"]);
+        let alice = &users[0];
+        let alice_pet = app.mint_pet(alice);
+        let tournament_id = create(&mut app, alice, 25, 4, None, None).unwrap();
+        join(&mut app, alice, tournament_id, &alice_pet).unwrap();
+
+        let permits = app.permits(alice);
+        let msg = ExecuteMsg::ReleasePet {
+            pet_id: alice_pet,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&alice.address, &msg).unwrap();
+        assert_eq!(app.loot_balance(alice), 1000);
+        assert!(tournament(&app, tournament_id).entrants.is_empty());
+    }
+
+    #[test]
+    fn test_leaving_a_tournament_refunds_the_fee() {
+        let (mut app, users) = setup(&["alice", "bob", "carol"]);
+        let (alice, bob) = (&users[0], &users[1]);
+        let alice_pet = app.mint_pet(alice);
+        let bob_pet = app.mint_pet(bob);
+        let carol_pet = app.mint_pet(&users[2]);
+        let deadline = Expiration::AtTime(app.block().time.seconds() + 100);
+        let tournament_id = create(&mut app, alice, 25, 4, Some(deadline), None).unwrap();
+        join(&mut app, alice, tournament_id, &alice_pet).unwrap();
+        join(&mut app, bob, tournament_id, &bob_pet).unwrap();
+
+        let err = leave(&mut app, bob, tournament_id, &alice_pet).unwrap_err();
+        assert!(err.contains("not_pet_owner"), "{}", err);
+        let err = leave(&mut app, &users[2], tournament_id, &carol_pet).unwrap_err();
+        assert!(err.contains("not_in_tournament"), "{}", err);
+
+        // the fee is refunded and the pet is free to quest again
+        leave(&mut app, alice, tournament_id, &alice_pet).unwrap();
+        assert_eq!(app.loot_balance(alice), 1000);
+        assert_eq!(
+            tournament(&app, tournament_id).entrants,
+            vec![bob_pet.clone()]
+        );
+        app.send_on_quest(alice, &alice_pet, "trial_of_titans")
+            .unwrap();
+        let err = leave(&mut app, alice, tournament_id, &alice_pet).unwrap_err();
+        assert!(err.contains("not_in_tournament"), "{}", err);
+
+        // once the tournament is played or cancelled nobody can leave it
+        app.advance_time(100);
+        play(&mut app, alice, tournament_id).unwrap();
+        let err = leave(&mut app, bob, tournament_id, &bob_pet).unwrap_err();
+        assert!(err.contains("tournament_closed"), "{}", err);
+        assert_eq!(app.loot_balance(bob), 1000);
+    }
+}
@@ -0,0 +1,370 @@
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::msg::ExecuteMsg;
+    use crate::multitest::{App, TestUser, ADMIN};
+    use crate::state::{
+        BattleRecord, CostCurve, PetEnergy, PetLineage, PetProgression, PetState, PetStats,
+        PetUpgrades, Stat, UpgradeConfig, UpgradeCosts, INITIAL_RATING,
+    };
+
+    // Helper functions
+
+    fn setup() -> (App, TestUser) {
+        let mut app = App::new();
+        let alice = app.funded_user("alice", 1000);
+        (app, alice)
+    }
+
+    fn upgrade(
+        app: &mut App,
+        user: &TestUser,
+        pet_id: &str,
+        upgrades: Vec<(Stat, u16)>,
+    ) -> Result<u64, String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::UpgradePetStats {
+            pet_id: pet_id.to_string(),
+            upgrades,
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        let response = app.execute_game(&user.address, &msg)?;
+        Ok(response.attribute("cost").unwrap().parse().unwrap())
+    }
+
+    fn respec(app: &mut App, user: &TestUser, pet_id: &str) -> Result<(), String> {
+        let permits = app.permits(user);
+        let msg = ExecuteMsg::RespecPet {
+            pet_id: pet_id.to_string(),
+            permit: permits.permit,
+            loot_permit: permits.loot_permit,
+            pet_permit: permits.pet_permit,
+        };
+        app.execute_game(&user.address, &msg).map(|_| ())
+    }
+
+    fn pet(current: PetStats, stat_points: u16) -> PetState {
+        let mut pet = PetState {
+            pet_id: "PET_0".to_string(),
+            on_quest: None,
+            current,
+            max: PetStats::new(12, 12, 12, 12, 12),
+            upgrade_costs: UpgradeCosts::default(),
+            power: 0,
+            battle_rating: 0,
+            upgrades: PetUpgrades::default(),
+            progression: PetProgression {
+                stat_points,
+                ..PetProgression::default()
+            },
+            energy: PetEnergy::default(),
+            record: BattleRecord::default(),
+            lineage: PetLineage::default(),
+            traits: None,
+            name: None,
+        };
+        pet.derive(&UpgradeConfig::default());
+        pet
+    }
+
+    fn shown_cost(costs: &UpgradeCosts, stat: &Stat) -> u64 {
+        match stat {
+            Stat::Health => costs.health,
+            Stat::Strength => costs.strength,
+            Stat::Stamina => costs.stamina,
+            Stat::Intelligence => costs.intelligence,
+            Stat::Luck => costs.luck,
+        }
+    }
+
+    fn random_curve(rng: &mut SmallRng) -> CostCurve {
+        match rng.gen_range(0..3) {
+            0 => CostCurve::Linear {
+                base: rng.gen_range(0..20),
+                per_point: rng.gen_range(0..10),
+            },
+            1 => CostCurve::Quadratic {
+                base: rng.gen_range(0..20),
+                per_point: rng.gen_range(0..5),
+            },
+            _ => CostCurve::Table {
+                costs: (0..rng.gen_range(1..25))
+                    .map(|_| rng.gen_range(0..100))
+                    .collect(),
+            },
+        }
+    }
+
+    fn random_config(rng: &mut SmallRng) -> UpgradeConfig {
+        UpgradeConfig {
+            health: random_curve(rng),
+            strength: random_curve(rng),
+            stamina: random_curve(rng),
+            intelligence: random_curve(rng),
+            luck: random_curve(rng),
+            respec_fee: rng.gen_range(0..100),
+            respec_refund_percent: rng.gen_range(0..=100),
+        }
+    }
+
+    // Curves
+
+    #[test]
+    fn test_cost_curves() {
+        let linear = CostCurve::Linear {
+            base: 0,
+            per_point: 5,
+        };
+        assert_eq!(linear.cost(7), 35);
+        let quadratic = CostCurve::Quadratic {
+            base: 10,
+            per_point: 2,
+        };
+        assert_eq!(quadratic.cost(3), 28);
+        // values past the end of a table cost its last entry
+        let table = CostCurve::Table {
+            costs: vec![1, 2, 3],
+        };
+        assert_eq!(table.cost(1), 2);
+        assert_eq!(table.cost(9), 3);
+
+        // every point costs what raising the stat from its value at that point costs
+        let config = UpgradeConfig::default();
+        let upgrades = vec![(Stat::Strength, 2), (Stat::Luck, 1), (Stat::Strength, 1)];
+        assert_eq!(
+            config.cost_of_upgrades(&PetStats::new(5, 5, 5, 5, 5), &upgrades),
+            25 + 30 + 25 + 35
+        );
+    }
+
+    #[test]
+    fn test_respec_resets_stats_and_returns_points() {
+        let config = UpgradeConfig::default();
+        let base = PetStats::new(5, 5, 5, 5, 5);
+        let mut pet = pet(base.clone(), 2);
+
+        pet.spend_stat_points(Stat::Luck, 2, &config).unwrap();
+        let cost = pet.apply_upgrades(&[(Stat::Health, 2)], &config).unwrap();
+        assert_eq!(cost, 25 + 30);
+        assert_eq!(pet.current, PetStats::new(7, 5, 5, 5, 7));
+        assert_eq!(pet.upgrade_costs.health, 35);
+        assert_eq!(pet.upgrades.base, Some(base.clone()));
+
+        // half of the spent loot is refunded by default
+        assert_eq!(pet.respec(&config), 27);
+        assert_eq!(pet.current, base);
+        assert_eq!(pet.progression.stat_points, 2);
+        assert_eq!(pet.upgrades.loot_spent, 0);
+        assert_eq!(pet.upgrade_costs, config.upgrade_costs(&base));
+    }
+
+    // Upgrades
+
+    #[test]
+    fn test_batch_upgrade_is_charged_once() {
+        let (mut app, alice) = setup();
+        let pet_id = app.mint_pet(&alice);
+        let pet = app.my_pet(&alice, &pet_id);
+
+        let cost = upgrade(
+            &mut app,
+            &alice,
+            &pet_id,
+            vec![(Stat::Strength, 1), (Stat::Health, 1)],
+        )
+        .unwrap();
+        assert_eq!(cost, pet.upgrade_costs.strength + pet.upgrade_costs.health);
+        assert_eq!(app.loot_balance(&alice), 1000 - u128::from(cost));
+
+        let upgraded = app.my_pet(&alice, &pet_id);
+        assert_eq!(upgraded.current.strength, pet.current.strength + 1);
+        assert_eq!(upgraded.current.health, pet.current.health + 1);
+        assert_eq!(
+            upgraded.upgrade_costs.strength,
+            pet.upgrade_costs.strength + 5
+        );
+    }
+
+    #[test]
+    fn test_invalid_upgrades_are_rejected() {
+        let (mut app, alice) = setup();
+        let pet_id = app.mint_pet(&alice);
+        let pet = app.my_pet(&alice, &pet_id);
+
+        let past_max = pet.max.strength - pet.current.strength + 1;
+        let err = upgrade(&mut app, &alice, &pet_id, vec![(Stat::Strength, past_max)]).unwrap_err();
+        assert!(err.contains("stat_maxed"), "{}", err);
+        for upgrades in [vec![], vec![(Stat::Luck, 0)]] {
+            let err = upgrade(&mut app, &alice, &pet_id, upgrades).unwrap_err();
+            assert!(err.contains("invalid_upgrade"), "{}", err);
+        }
+        assert_eq!(app.loot_balance(&alice), 1000);
+    }
+
+    #[test]
+    fn test_respec_refunds_part_of_the_spent_loot() {
+        let (mut app, alice) = setup();
+        let pet_id = app.mint_pet(&alice);
+        let pet = app.my_pet(&alice, &pet_id);
+        let points = pet.max.strength - pet.current.strength;
+        let spent = upgrade(&mut app, &alice, &pet_id, vec![(Stat::Strength, points)]).unwrap();
+
+        respec(&mut app, &alice, &pet_id).unwrap();
+        let config = UpgradeConfig::default();
+        assert_eq!(
+            app.loot_balance(&alice),
+            1000 - u128::from(spent) + u128::from(config.respec_refund(spent))
+                - u128::from(config.respec_fee)
+        );
+        let respecced = app.my_pet(&alice, &pet_id);
+        assert_eq!(respecced.current, pet.current);
+        assert_eq!(respecced.upgrade_costs, pet.upgrade_costs);
+    }
+
+    // Config
+
+    #[test]
+    fn test_upgrade_costs_follow_the_active_config() {
+        let (mut app, alice) = setup();
+        let pet_id = app.mint_pet(&alice);
+        let pet = app.my_pet(&alice, &pet_id);
+
+        let config = UpgradeConfig {
+            strength: CostCurve::Quadratic {
+                base: 0,
+                per_point: 2,
+            },
+            ..UpgradeConfig::default()
+        };
+        let msg = ExecuteMsg::SetUpgradeConfig { config };
+        app.execute_game(ADMIN, &msg).unwrap();
+
+        let strength = u64::from(pet.current.strength);
+        let repriced = app.my_pet(&alice, &pet_id);
+        assert_eq!(repriced.upgrade_costs.strength, 2 * strength * strength);
+        assert_eq!(repriced.upgrade_costs.health, pet.upgrade_costs.health);
+
+        for config in [
+            UpgradeConfig {
+                respec_refund_percent: 101,
+                ..UpgradeConfig::default()
+            },
+            UpgradeConfig {
+                luck: CostCurve::Table { costs: vec![] },
+                ..UpgradeConfig::default()
+            },
+        ] {
+            let msg = ExecuteMsg::SetUpgradeConfig { config };
+            let err = app.execute_game(ADMIN, &msg).unwrap_err();
+            assert!(err.contains("invalid_upgrade_config"), "{}", err);
+        }
+    }
+
+    // Derived fields
+
+    #[test]
+    fn test_derive_fills_every_derived_field() {
+        let mut pet = pet(PetStats::new(5, 6, 7, 8, 9), 0);
+        assert_eq!(pet.power, 35);
+        assert_eq!(pet.battle_rating, INITIAL_RATING);
+        assert_eq!(pet.upgrade_costs.luck, 45);
+
+        pet.record.rating = 1300;
+        pet.derive(&UpgradeConfig::default());
+        assert_eq!(pet.battle_rating, 1300);
+    }
+
+    #[test]
+    fn test_shown_costs_match_charged_costs() {
+        for seed in 0..50 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut config = random_config(&mut rng);
+            let mut pet = pet(
+                PetStats::new(
+                    rng.gen_range(5..=8),
+                    rng.gen_range(5..=8),
+                    rng.gen_range(5..=8),
+                    rng.gen_range(5..=8),
+                    rng.gen_range(5..=8),
+                ),
+                0,
+            );
+            pet.derive(&config);
+
+            for _ in 0..20 {
+                match rng.gen_range(0..10) {
+                    // the curves change, queries derive the pet again before showing it
+                    0 => {
+                        config = random_config(&mut rng);
+                        pet.derive(&config);
+                    }
+                    1 => {
+                        pet.respec(&config);
+                    }
+                    _ => {
+                        let stat = Stat::all()[rng.gen_range(0..5)].clone();
+                        let points = rng.gen_range(1..=3);
+
+                        // raising point by point charges exactly the cost shown before each point
+                        let mut stepped = pet.clone();
+                        let mut shown = 0;
+                        let mut maxed = false;
+                        for _ in 0..points {
+                            let cost = shown_cost(&stepped.upgrade_costs, &stat);
+                            match stepped.apply_upgrades(&[(stat.clone(), 1)], &config) {
+                                Ok(charged) => assert_eq!(charged, cost, "seed {}", seed),
+                                Err(_) => maxed = true,
+                            }
+                            shown += cost;
+                        }
+
+                        // and a batch charges their sum
+                        match pet.apply_upgrades(&[(stat, points)], &config) {
+                            Ok(charged) => {
+                                assert!(!maxed);
+                                assert_eq!(charged, shown, "seed {}", seed);
+                                assert_eq!(pet, stepped);
+                            }
+                            Err(_) => assert!(maxed),
+                        }
+                    }
+                }
+                assert_eq!(pet.upgrade_costs, config.upgrade_costs(&pet.current));
+            }
+        }
+    }
+
+    #[test]
+    fn test_queried_costs_match_charged_costs() {
+        let (mut app, alice) = setup();
+        let pet_id = app.mint_pet(&alice);
+        let config = UpgradeConfig {
+            strength: CostCurve::Quadratic {
+                base: 1,
+                per_point: 1,
+            },
+            luck: CostCurve::Table {
+                costs: vec![3, 1, 4, 1, 5, 9, 2, 6],
+            },
+            ..UpgradeConfig::default()
+        };
+        let msg = ExecuteMsg::SetUpgradeConfig { config };
+        app.execute_game(ADMIN, &msg).unwrap();
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        for _ in 0..6 {
+            let pet = app.my_pet(&alice, &pet_id);
+            let stat = Stat::all()[rng.gen_range(0..5)].clone();
+            if pet.current.get_stat(stat.clone()) >= pet.max.get_stat(stat.clone()) {
+                continue;
+            }
+            let shown = shown_cost(&pet.upgrade_costs, &stat);
+            let charged = upgrade(&mut app, &alice, &pet_id, vec![(stat, 1)]).unwrap();
+            assert_eq!(charged, shown);
+        }
+    }
+}
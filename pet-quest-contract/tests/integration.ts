@@ -341,7 +341,7 @@ const initializePetContract = async (
       public_owner: true,
       enable_sealed_metadata: false,
       unwrapped_metadata_is_private: false,
-      minter_may_update_metadata: false,
+      minter_may_update_metadata: true,
       owner_may_update_metadata: false,
       enable_burn: true,
     },
@@ -763,7 +763,7 @@ async function upgradeStat(
     msg: {
       upgrade_pet_stats: {
         pet_id,
-        stat,
+        upgrades: [[stat, 1]],
         permit: user_info.permit,
         loot_permit: user_info.loot_permit,
         pet_permit: user_info.pet_permit,
@@ -1286,7 +1286,7 @@ async function test_upgrade_pet(
   let cost_of_upgrade = pets[0].upgrade_costs?.strength
 
   // upgrade stat
-  await upgradeStat(client_info.user1, pet_id, "Strength", cost_of_upgrade, client_info.main.codeHash,
+  await upgradeStat(client_info.user1, pet_id, "strength", cost_of_upgrade, client_info.main.codeHash,
     client_info.main.address, client_info.loot.codeHash, client_info.loot.address)
 
   // check that stat is +1 than before
@@ -1312,7 +1312,7 @@ async function test_upgrade_pet_without_funds(
   let pet_id = pets[0].pet_id
   let cost_of_upgrade = pets[0].upgrade_costs.strength
 
-  let txList = await upgradeStat(client_info.user3, pet_id, "Strength", cost_of_upgrade, client_info.main.codeHash,
+  let txList = await upgradeStat(client_info.user3, pet_id, "strength", cost_of_upgrade, client_info.main.codeHash,
     client_info.main.address, client_info.loot.codeHash, client_info.loot.address)
 
   assert(
@@ -151,31 +151,31 @@ const MainContainer = (props: MainContainerProps) => {
                       current={pet!.current.health}
                       max={pet!.max.health}
                       cost={pet!.upgrade_costs.health}
-                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "Health", pet!.upgrade_costs.health)}
+                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "health", pet!.upgrade_costs.health)}
                 />
                 <Stat stat_type="Strength"
                       current={pet!.current.strength}
                       max={pet!.max.strength}
                       cost={pet!.upgrade_costs.strength}
-                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "Strength", pet!.upgrade_costs.strength)}
+                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "strength", pet!.upgrade_costs.strength)}
                 />
                 <Stat stat_type="Stamina"
                       current={pet!.current.stamina}
                       max={pet!.max.stamina}
                       cost={pet!.upgrade_costs.stamina}
-                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "Stamina", pet!.upgrade_costs.stamina)}
+                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "stamina", pet!.upgrade_costs.stamina)}
                 />
                 <Stat stat_type="Intelligence"
                       current={pet!.current.intelligence}
                       max={pet!.max.intelligence}
                       cost={pet!.upgrade_costs.intelligence}
-                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "Intelligence", pet!.upgrade_costs.intelligence)}
+                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "intelligence", pet!.upgrade_costs.intelligence)}
                 />
                 <Stat stat_type="Luck"
                       current={pet!.current.luck}
                       max={pet!.max.luck}
                       cost={pet!.upgrade_costs.luck}
-                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "Luck", pet!.upgrade_costs.luck)}
+                      upgrade_stat={() => executePetUpgrade(pet!.pet_id, "luck", pet!.upgrade_costs.luck)}
                 />
               </Card>
             </Flex>
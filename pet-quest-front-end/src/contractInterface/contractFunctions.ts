@@ -448,7 +448,7 @@ const ContractFunctions = () => {
       msg: {
         upgrade_pet_stats: {
           pet_id,
-          stat,
+          upgrades: [[stat, 1]],
           permit,
           loot_permit,
           pet_permit,
@@ -102,7 +102,7 @@ const instantiatePet721Contract = async (codeId: string, contractCodeHash: strin
       public_owner: true,
       enable_sealed_metadata: false,
       unwrapped_metadata_is_private: false,
-      minter_may_update_metadata: false,
+      minter_may_update_metadata: true,
       owner_may_update_metadata: false,
       enable_burn: true,
     },